@@ -0,0 +1,126 @@
+mod tests;
+use crate::ray::Ray;
+use crate::vec3::{Vec3, Vector};
+use std::f32;
+
+/// An axis-aligned bounding box, stored as its minimum and maximum corners.
+///
+/// Infinite primitives report `Aabb::unbounded()`, which every ray hits. Anything that builds
+/// an acceleration structure should check `is_bounded` and keep those objects out of it.
+#[derive(Copy, Clone)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Aabb {
+    pub fn new(min: Vec3, max: Vec3) -> Self {
+        Aabb { min, max }
+    }
+
+    /// A box that contains nothing. Surrounding it with any other box gives back the other box.
+    pub fn empty() -> Self {
+        Aabb {
+            min: Vec3(f32::INFINITY, f32::INFINITY, f32::INFINITY),
+            max: Vec3(f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY),
+        }
+    }
+
+    /// A box that contains everything, for primitives such as infinite planes.
+    pub fn unbounded() -> Self {
+        Aabb {
+            min: Vec3(f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY),
+            max: Vec3(f32::INFINITY, f32::INFINITY, f32::INFINITY),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.min.x() > self.max.x() || self.min.y() > self.max.y() || self.min.z() > self.max.z()
+    }
+
+    /// Whether the box has finite extent on every axis. Empty boxes count as bounded.
+    pub fn is_bounded(&self) -> bool {
+        self.is_empty()
+            || (0..3).all(|axis| self.min[axis].is_finite() && self.max[axis].is_finite())
+    }
+
+    /// The smallest box containing both boxes.
+    pub fn surrounding(box1: &Aabb, box2: &Aabb) -> Self {
+        Aabb {
+            min: Vec3(
+                box1.min.x().min(box2.min.x()),
+                box1.min.y().min(box2.min.y()),
+                box1.min.z().min(box2.min.z()),
+            ),
+            max: Vec3(
+                box1.max.x().max(box2.max.x()),
+                box1.max.y().max(box2.max.y()),
+                box1.max.z().max(box2.max.z()),
+            ),
+        }
+    }
+
+    pub fn centroid(&self) -> Vec3 {
+        0.5 * (self.min + self.max)
+    }
+
+    /// Surface area of the box, which is proportional to the chance that a random ray hits it.
+    pub fn surface_area(&self) -> f32 {
+        if self.is_empty() {
+            return 0.0;
+        }
+        let extent = self.max - self.min;
+        2.0 * (extent.x() * extent.y() + extent.y() * extent.z() + extent.z() * extent.x())
+    }
+
+    /// The axis (0, 1 or 2) along which the box is longest.
+    pub fn longest_axis(&self) -> usize {
+        let extent = self.max - self.min;
+        if extent.x() >= extent.y() && extent.x() >= extent.z() {
+            0
+        } else if extent.y() >= extent.z() {
+            1
+        } else {
+            2
+        }
+    }
+
+    /// Slab test: whether the ray passes through the box anywhere between `distance_min` and
+    /// `distance_max`.
+    pub fn hit(&self, ray: &Ray, distance_min: f32, distance_max: f32) -> bool {
+        self.hit_with_inverse(
+            ray.origin(),
+            &ray.inverse_direction(),
+            distance_min,
+            distance_max,
+        )
+    }
+
+    /// The same slab test as `hit`, with the reciprocal of the ray direction computed up front
+    /// so that traversing a whole tree of boxes costs no divisions.
+    ///
+    /// The slab bounds are picked by the sign of the direction rather than with `min`/`max`, so
+    /// an empty box is never hit. A zero direction component gives an infinite reciprocal; the
+    /// NaN this produces when the origin lies exactly on a slab is ignored by `f32::max` and
+    /// `f32::min`.
+    pub fn hit_with_inverse(
+        &self,
+        origin: &Vec3,
+        inverse_direction: &Vec3,
+        distance_min: f32,
+        distance_max: f32,
+    ) -> bool {
+        let mut near = distance_min;
+        let mut far = distance_max;
+        for axis in 0..3 {
+            let (entry, exit) = if inverse_direction[axis] < 0.0 {
+                (self.max[axis], self.min[axis])
+            } else {
+                (self.min[axis], self.max[axis])
+            };
+            near = ((entry - origin[axis]) * inverse_direction[axis]).max(near);
+            far = ((exit - origin[axis]) * inverse_direction[axis]).min(far);
+        }
+        near <= far
+    }
+}
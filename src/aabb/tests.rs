@@ -0,0 +1,73 @@
+#[cfg(test)]
+use crate::aabb::Aabb;
+#[cfg(test)]
+use crate::ray::Ray;
+#[cfg(test)]
+use crate::vec3::Vec3;
+
+#[test]
+fn test_ray_hits_box() {
+    let bounding_box = Aabb::new(Vec3(-1.0, -1.0, -1.0), Vec3(1.0, 1.0, 1.0));
+    let ray = Ray::new(Vec3(-5.0, 0.5, 0.5), Vec3(1.0, 0.0, 0.0));
+
+    assert!(bounding_box.hit(&ray, 0.0, f32::MAX));
+    assert!(!bounding_box.hit(&ray, 0.0, 3.0));
+    assert!(!bounding_box.hit(&ray, 7.0, f32::MAX));
+}
+
+#[test]
+fn test_ray_misses_box() {
+    let bounding_box = Aabb::new(Vec3(-1.0, -1.0, -1.0), Vec3(1.0, 1.0, 1.0));
+    let parallel = Ray::new(Vec3(-5.0, 2.0, 0.0), Vec3(1.0, 0.0, 0.0));
+    let diagonal = Ray::new(Vec3(-5.0, 0.0, 0.0), Vec3(1.0, 1.0, 0.0));
+    let behind = Ray::new(Vec3(5.0, 0.0, 0.0), Vec3(1.0, 0.0, 0.0));
+
+    assert!(!bounding_box.hit(&parallel, 0.0, f32::MAX));
+    assert!(!bounding_box.hit(&diagonal, 0.0, f32::MAX));
+    assert!(!bounding_box.hit(&behind, 0.0, f32::MAX));
+}
+
+#[test]
+fn test_ray_hits_flat_box() {
+    let bounding_box = Aabb::new(Vec3(-1.0, 0.0, -1.0), Vec3(1.0, 0.0, 1.0));
+    let ray = Ray::new(Vec3(0.0, 5.0, 0.0), Vec3(0.0, -1.0, 0.0));
+
+    assert!(bounding_box.hit(&ray, 0.0, f32::MAX));
+}
+
+#[test]
+fn test_empty_box_is_never_hit() {
+    let ray = Ray::new(Vec3(0.0, 0.0, 0.0), Vec3(1.0, -1.0, 0.5));
+
+    assert!(!Aabb::empty().hit(&ray, 0.0, f32::MAX));
+    assert!(Aabb::empty().is_empty());
+    assert!(Aabb::empty().is_bounded());
+}
+
+#[test]
+fn test_unbounded_box_is_always_hit() {
+    let ray = Ray::new(Vec3(3.0, 0.0, 0.0), Vec3(0.0, 0.0, -1.0));
+
+    assert!(Aabb::unbounded().hit(&ray, 0.0, f32::MAX));
+    assert!(!Aabb::unbounded().is_bounded());
+}
+
+#[test]
+fn test_surrounding_box() {
+    let box1 = Aabb::new(Vec3(0.0, 0.0, 0.0), Vec3(1.0, 1.0, 1.0));
+    let box2 = Aabb::new(Vec3(-1.0, 0.5, 2.0), Vec3(0.5, 3.0, 4.0));
+    let surrounding = Aabb::surrounding(&box1, &box2);
+
+    assert!(surrounding.min == Vec3(-1.0, 0.0, 0.0));
+    assert!(surrounding.max == Vec3(1.0, 3.0, 4.0));
+    assert!(Aabb::surrounding(&Aabb::empty(), &box1).min == box1.min);
+}
+
+#[test]
+fn test_surface_area_and_longest_axis() {
+    let bounding_box = Aabb::new(Vec3(0.0, 0.0, 0.0), Vec3(1.0, 2.0, 3.0));
+
+    assert!(bounding_box.surface_area() == 22.0);
+    assert!(bounding_box.longest_axis() == 2);
+    assert!(Aabb::empty().surface_area() == 0.0);
+}
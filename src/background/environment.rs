@@ -0,0 +1,219 @@
+use super::Background;
+use crate::framebuffer::Framebuffer;
+use crate::image;
+use crate::import::ImportError;
+use crate::sampler::Sampler;
+use crate::vec3::{Vec3, Vector};
+use rand::prelude::*;
+use std::f32::consts::PI;
+use std::path::Path;
+
+/// A panorama in equirectangular (latitude-longitude) projection, surrounding the scene at an
+/// infinite distance. The top row of the image lies straight up and the bottom row straight
+/// down; the middle column faces -z, with +x a quarter of the way to the right. Radiance
+/// between pixel centers is blended bilinearly.
+///
+/// Directions are sampled in proportion to the brightness of the image, so that a small,
+/// bright sun is found by light sampling rather than by chance.
+pub struct EnvironmentMap {
+    image: Framebuffer,
+    /// Turns the panorama about the y axis, counter-clockwise seen from above.
+    rotation: f32,
+    intensity: f32,
+    /// Picks a row of the image, then a column within it.
+    rows: Distribution,
+    columns: Vec<Distribution>,
+}
+
+impl EnvironmentMap {
+    /// Wraps an image of linear radiance, rotated by `rotation` degrees about the y axis and
+    /// scaled by `intensity`. Panics if the image has no pixels.
+    pub fn new(image: Framebuffer, rotation: f32, intensity: f32) -> Self {
+        let (width, height) = (image.width(), image.height());
+        assert!(width > 0 && height > 0, "an environment map needs pixels");
+        let luminance = |x: i64, y: i64| {
+            let pixel = image.pixels()[pixel_index(width, height, x, y)];
+            0.2126 * pixel.0 + 0.7152 * pixel.1 + 0.0722 * pixel.2
+        };
+        // Bilinear filtering spreads a pixel over its neighbours, so each one is weighed by
+        // the brightest around it, or a dark pixel next to the sun could never be sampled.
+        let columns: Vec<Distribution> = (0..height as i64)
+            .map(|y| {
+                Distribution::new(
+                    (0..width as i64)
+                        .map(|x| {
+                            let mut brightest = 0.0f32;
+                            for (dx, dy) in (-1..=1).flat_map(|dx| (-1..=1).map(move |dy| (dx, dy)))
+                            {
+                                brightest = brightest.max(luminance(x + dx, y + dy));
+                            }
+                            brightest
+                        })
+                        .collect(),
+                )
+            })
+            .collect();
+        // Rows near the poles cover less of the sphere.
+        let rows = Distribution::new(
+            columns
+                .iter()
+                .enumerate()
+                .map(|(y, row)| row.total * ((y as f32 + 0.5) / height as f32 * PI).sin())
+                .collect(),
+        );
+        EnvironmentMap {
+            image,
+            rotation: rotation.to_radians(),
+            intensity,
+            rows,
+            columns,
+        }
+    }
+
+    /// Loads a Radiance HDR or OpenEXR panorama.
+    pub fn load(path: &Path, rotation: f32, intensity: f32) -> Result<Self, ImportError> {
+        let image = image::load(path)?;
+        if image.pixels().is_empty() {
+            return Err(ImportError::Malformed {
+                path: path.to_path_buf(),
+                message: String::from("the image has no pixels"),
+            });
+        }
+        Ok(Self::new(image, rotation, intensity))
+    }
+
+    /// Turns a direction in the scene into one relative to the unrotated panorama, or back
+    /// with `sign` -1.
+    fn rotate(&self, direction: &Vec3, sign: f32) -> Vec3 {
+        let (sin, cos) = (sign * self.rotation).sin_cos();
+        Vec3(
+            cos * direction.0 - sin * direction.2,
+            direction.1,
+            sin * direction.0 + cos * direction.2,
+        )
+    }
+
+    fn pixel(&self, x: i64, y: i64) -> Vec3 {
+        let (width, height) = (self.image.width(), self.image.height());
+        self.image.pixels()[pixel_index(width, height, x, y)]
+    }
+}
+
+impl Background for EnvironmentMap {
+    fn radiance(&self, direction: &Vec3) -> Vec3 {
+        let (u, v) = to_uv(&self.rotate(direction, 1.0));
+        // Pixel centers lie at half-integer positions.
+        let x = u * self.image.width() as f32 - 0.5;
+        let y = v * self.image.height() as f32 - 0.5;
+        let (left, top) = (x.floor(), y.floor());
+        let (right_weight, bottom_weight) = (x - left, y - top);
+        let (left, top) = (left as i64, top as i64);
+        let upper =
+            (1.0 - right_weight) * self.pixel(left, top) + right_weight * self.pixel(left + 1, top);
+        let lower = (1.0 - right_weight) * self.pixel(left, top + 1)
+            + right_weight * self.pixel(left + 1, top + 1);
+        self.intensity * ((1.0 - bottom_weight) * upper + bottom_weight * lower)
+    }
+
+    fn can_sample(&self) -> bool {
+        self.rows.total > 0.0 && self.intensity > 0.0
+    }
+
+    fn direction_pdf(&self, direction: &Vec3) -> f32 {
+        let (u, v) = to_uv(&self.rotate(direction, 1.0));
+        let row = ((v * self.rows.len() as f32) as usize).min(self.rows.len() - 1);
+        let columns = &self.columns[row];
+        let column = ((u * columns.len() as f32) as usize).min(columns.len() - 1);
+        uv_to_solid_angle(self.rows.density(row) * columns.density(column), v)
+    }
+
+    fn sample_direction(&self, sampler: &mut Sampler) -> Vec3 {
+        let (v, row) = self.rows.sample(sampler.gen());
+        let (u, _) = self.columns[row].sample(sampler.gen());
+        self.rotate(&from_uv(u, v), -1.0)
+    }
+}
+
+/// Wraps columns around the panorama and clamps rows at the poles.
+fn pixel_index(width: usize, height: usize, x: i64, y: i64) -> usize {
+    let x = x.rem_euclid(width as i64) as usize;
+    let y = y.clamp(0, height as i64 - 1) as usize;
+    y * width + x
+}
+
+/// Where a direction lands on the unrotated panorama, with u from 0 at the left edge to 1 at
+/// the right, and v from 0 at the top to 1 at the bottom.
+fn to_uv(direction: &Vec3) -> (f32, f32) {
+    let direction = direction.unit_vector();
+    let u = 0.5 + direction.0.atan2(-direction.2) / (2.0 * PI);
+    let v = direction.1.clamp(-1.0, 1.0).acos() / PI;
+    (u, v)
+}
+
+fn from_uv(u: f32, v: f32) -> Vec3 {
+    let (sin_theta, cos_theta) = (v * PI).sin_cos();
+    let (sin_phi, cos_phi) = ((u - 0.5) * 2.0 * PI).sin_cos();
+    Vec3(sin_theta * sin_phi, cos_theta, -sin_theta * cos_phi)
+}
+
+/// Converts a density over the image, whose area is 1, into one over solid angle. A pixel
+/// row at height v covers 2π² sin(πv) steradians per unit area of the image.
+fn uv_to_solid_angle(pdf: f32, v: f32) -> f32 {
+    let sin_theta = (v * PI).sin();
+    if sin_theta <= 0.0 {
+        return 0.0;
+    }
+    pdf / (2.0 * PI * PI * sin_theta)
+}
+
+/// A piecewise constant density over [0, 1), with one step per weight.
+struct Distribution {
+    /// The running total of the weights, divided by their sum, starting at 0.
+    cdf: Vec<f32>,
+    /// The sum of the weights, before normalizing.
+    total: f32,
+}
+
+impl Distribution {
+    /// Weights that are all zero give a uniform density.
+    fn new(weights: Vec<f32>) -> Self {
+        let total: f32 = weights.iter().sum();
+        let count = weights.len() as f32;
+        let mut cdf = Vec::with_capacity(weights.len() + 1);
+        cdf.push(0.0);
+        let mut sum = 0.0;
+        for weight in weights.iter() {
+            sum += if total > 0.0 {
+                weight / total
+            } else {
+                1.0 / count
+            };
+            cdf.push(sum);
+        }
+        // Rounding may leave the last step short of 1.
+        *cdf.last_mut().unwrap() = 1.0;
+        Distribution { cdf, total }
+    }
+
+    fn len(&self) -> usize {
+        self.cdf.len() - 1
+    }
+
+    /// A position in [0, 1) drawn from the density, given `random` in [0, 1), and the step it
+    /// falls in.
+    fn sample(&self, random: f32) -> (f32, usize) {
+        let index = (self.cdf.partition_point(|&sum| sum <= random) - 1).min(self.len() - 1);
+        let width = self.cdf[index + 1] - self.cdf[index];
+        let offset = if width > 0.0 {
+            ((random - self.cdf[index]) / width).clamp(0.0, 1.0)
+        } else {
+            0.5
+        };
+        ((index as f32 + offset) / self.len() as f32, index)
+    }
+
+    /// The density over [0, 1) within step `index`.
+    fn density(&self, index: usize) -> f32 {
+        (self.cdf[index + 1] - self.cdf[index]) * self.len() as f32
+    }
+}
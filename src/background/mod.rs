@@ -0,0 +1,72 @@
+mod environment;
+mod sky;
+mod tests;
+use crate::sampler::Sampler;
+use crate::vec3::{Vec3, Vector};
+
+pub use environment::EnvironmentMap;
+pub use sky::PhysicalSky;
+
+/// The light arriving along rays that leave the scene without hitting anything. Shared between
+/// render threads, so implementors must be `Send + Sync`.
+pub trait Background: Send + Sync {
+    /// The radiance arriving from `direction`, looking out along it. The direction needn't be
+    /// of unit length.
+    fn radiance(&self, direction: &Vec3) -> Vec3;
+
+    /// Whether `sample_direction` is worth calling: true for backgrounds bright in some
+    /// directions and dark in others, which diffuse hits then sample like an emissive shape.
+    fn can_sample(&self) -> bool {
+        false
+    }
+
+    /// The probability density, per unit solid angle, of `sample_direction` picking
+    /// `direction`. Zero everywhere for backgrounds that can't be sampled.
+    #[allow(unused_variables)]
+    fn direction_pdf(&self, direction: &Vec3) -> f32 {
+        0.0
+    }
+
+    /// A random unit direction, favouring the brighter ones. Backgrounds that can't be sampled
+    /// return an arbitrary direction, which their zero `direction_pdf` then gives no weight.
+    #[allow(unused_variables)]
+    fn sample_direction(&self, sampler: &mut Sampler) -> Vec3 {
+        Vec3(0.0, 1.0, 0.0)
+    }
+}
+
+/// The same radiance from every direction. Black for scenes lit only by their own lights.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Constant(pub Vec3);
+
+impl Background for Constant {
+    #[allow(unused_variables)]
+    fn radiance(&self, direction: &Vec3) -> Vec3 {
+        self.0
+    }
+}
+
+/// A blend from one color straight down to another straight up, linear in the height of the
+/// direction.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Gradient {
+    pub down: Vec3,
+    pub up: Vec3,
+}
+
+/// A sky fading from white at the bottom to light blue overhead.
+impl Default for Gradient {
+    fn default() -> Self {
+        Gradient {
+            down: Vec3(1.0, 1.0, 1.0),
+            up: Vec3(0.5, 0.7, 1.0),
+        }
+    }
+}
+
+impl Background for Gradient {
+    fn radiance(&self, direction: &Vec3) -> Vec3 {
+        let t = 0.5 * (direction.unit_vector().y() + 1.0);
+        self.down * (1.0 - t) + self.up * t
+    }
+}
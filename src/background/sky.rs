@@ -0,0 +1,214 @@
+use super::Background;
+use crate::sampler::Sampler;
+use crate::vec3::{orthonormal_basis, Vec3, Vector};
+use rand::prelude::*;
+use std::f32::consts::PI;
+
+/// Half the angle the sun's disk spans, in degrees.
+const SUN_ANGULAR_RADIUS: f32 = 0.27;
+/// The sun's luminance before the atmosphere dims it, in the sky's units of 10 kcd/m².
+const SUN_LUMINANCE: f32 = 2.2e5;
+/// The share of light samples aimed at the sun rather than spread over the whole sky.
+const SUN_SAMPLE_SHARE: f32 = 0.5;
+
+/// An analytic daylight sky (Preetham, Shirley and Smits, 1999) with the sun's disk in it.
+///
+/// Radiance comes out in units of 10 kcd/m², which makes a clear sky overhead at noon about
+/// as bright as the default `Gradient`, with the sun lighting a level surface about five times
+/// more than the rest of the sky does. The model only holds for a sun above the horizon, and
+/// below the horizon the sky keeps the color it has at it.
+pub struct PhysicalSky {
+    sun_direction: Vec3,
+    /// The cosine of `SUN_ANGULAR_RADIUS`, and one minus it, kept apart as the difference is
+    /// too small to take accurately.
+    sun_cos_radius: f32,
+    sun_one_minus_cos: f32,
+    /// Transmitted through the atmosphere, so that a low sun is dimmer and redder.
+    sun_radiance: Vec3,
+    /// Perez distribution coefficients A to E for the luminance Y and the chromaticities x
+    /// and y.
+    perez: [[f32; 5]; 3],
+    /// Y, x and y at the zenith, divided by the Perez distribution there, so that multiplying
+    /// by the distribution in some direction gives the value in that direction.
+    zenith: [f32; 3],
+    /// Scales the sky and the sun alike.
+    pub intensity: f32,
+}
+
+impl PhysicalSky {
+    /// A sky with the sun `elevation` degrees above the horizon, between 0 and 90, and
+    /// `azimuth` degrees round from -z towards +x.
+    ///
+    /// Turbidity measures the haze, from 2 for a very clear sky to 10 for a hazy one, and is
+    /// clamped to that range, where the model was fitted.
+    pub fn new(elevation: f32, azimuth: f32, turbidity: f32) -> Self {
+        let elevation = elevation.clamp(0.0, 90.0).to_radians();
+        let azimuth = azimuth.to_radians();
+        let turbidity = turbidity.clamp(2.0, 10.0);
+        let sun_direction = Vec3(
+            elevation.cos() * azimuth.sin(),
+            elevation.sin(),
+            -elevation.cos() * azimuth.cos(),
+        );
+        let sun_zenith = PI / 2.0 - elevation;
+        let t = turbidity;
+        let perez = [
+            [
+                0.1787 * t - 1.4630,
+                -0.3554 * t + 0.4275,
+                -0.0227 * t + 5.3251,
+                0.1206 * t - 2.5771,
+                -0.0670 * t + 0.3703,
+            ],
+            [
+                -0.0193 * t - 0.2592,
+                -0.0665 * t + 0.0008,
+                -0.0004 * t + 0.2125,
+                -0.0641 * t - 0.8989,
+                -0.0033 * t + 0.0452,
+            ],
+            [
+                -0.0167 * t - 0.2608,
+                -0.0950 * t + 0.0092,
+                -0.0079 * t + 0.2102,
+                -0.0441 * t - 1.6537,
+                -0.0109 * t + 0.0529,
+            ],
+        ];
+        let chi = (4.0 / 9.0 - t / 120.0) * (PI - 2.0 * sun_zenith);
+        // In kcd/m², then in the sky's units.
+        let luminance = ((4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192) * 0.1;
+        let (s, s2, s3) = (sun_zenith, sun_zenith * sun_zenith, sun_zenith.powi(3));
+        let x = t * t * (0.00166 * s3 - 0.00375 * s2 + 0.00209 * s)
+            + t * (-0.02903 * s3 + 0.06377 * s2 - 0.03202 * s + 0.00394)
+            + (0.11693 * s3 - 0.21196 * s2 + 0.06052 * s + 0.25886);
+        let y = t * t * (0.00275 * s3 - 0.00610 * s2 + 0.00317 * s)
+            + t * (-0.04214 * s3 + 0.08970 * s2 - 0.04153 * s + 0.00516)
+            + (0.15346 * s3 - 0.26756 * s2 + 0.06670 * s + 0.26688);
+        let mut zenith = [luminance, x, y];
+        for (value, coefficients) in zenith.iter_mut().zip(perez.iter()) {
+            *value /= perez_distribution(coefficients, 1.0, sun_zenith);
+        }
+        let half_radius = (SUN_ANGULAR_RADIUS / 2.0).to_radians();
+        PhysicalSky {
+            sun_direction,
+            sun_cos_radius: SUN_ANGULAR_RADIUS.to_radians().cos(),
+            sun_one_minus_cos: 2.0 * half_radius.sin() * half_radius.sin(),
+            sun_radiance: SUN_LUMINANCE * sun_transmittance(sun_zenith, turbidity),
+            perez,
+            zenith,
+            intensity: 1.0,
+        }
+    }
+
+    /// The unit direction towards the middle of the sun.
+    pub fn sun_direction(&self) -> Vec3 {
+        self.sun_direction
+    }
+
+    /// The sky alone, without the sun, looking along a unit direction.
+    fn sky_radiance(&self, direction: &Vec3) -> Vec3 {
+        // Below the horizon, take the point on the horizon straight above.
+        let direction = &if direction.1 >= 0.0 {
+            *direction
+        } else if direction.0 != 0.0 || direction.2 != 0.0 {
+            Vec3(direction.0, 0.0, direction.2).unit_vector()
+        } else {
+            Vec3(1.0, 0.0, 0.0)
+        };
+        // Just above the horizon, as the distribution blows up at it.
+        let cos_zenith = direction.1.max(1e-3);
+        let cos_sun = Vec3::dot(direction, &self.sun_direction).clamp(-1.0, 1.0);
+        let mut values = [0.0; 3];
+        for (value, (zenith, coefficients)) in values
+            .iter_mut()
+            .zip(self.zenith.iter().zip(self.perez.iter()))
+        {
+            *value = zenith * perez_distribution(coefficients, cos_zenith, cos_sun.acos());
+        }
+        let [luminance, x, y] = values;
+        xyy_to_rgb(x, y, luminance)
+    }
+}
+
+impl Background for PhysicalSky {
+    fn radiance(&self, direction: &Vec3) -> Vec3 {
+        let direction = direction.unit_vector();
+        let mut radiance = self.sky_radiance(&direction);
+        if direction.1 > 0.0 && Vec3::dot(&direction, &self.sun_direction) >= self.sun_cos_radius {
+            radiance += self.sun_radiance;
+        }
+        self.intensity * radiance
+    }
+
+    fn can_sample(&self) -> bool {
+        self.intensity > 0.0
+    }
+
+    fn direction_pdf(&self, direction: &Vec3) -> f32 {
+        let sun = if Vec3::dot(&direction.unit_vector(), &self.sun_direction) >= self.sun_cos_radius
+        {
+            1.0 / (2.0 * PI * self.sun_one_minus_cos)
+        } else {
+            0.0
+        };
+        SUN_SAMPLE_SHARE * sun + (1.0 - SUN_SAMPLE_SHARE) / (4.0 * PI)
+    }
+
+    /// Aims at the sun's disk or anywhere in the sky, since apart from the sun the sky varies
+    /// too little to be worth sampling more closely.
+    fn sample_direction(&self, sampler: &mut Sampler) -> Vec3 {
+        let (axis, cos_theta) = if sampler.gen::<f32>() < SUN_SAMPLE_SHARE {
+            (
+                self.sun_direction,
+                1.0 - sampler.gen::<f32>() * self.sun_one_minus_cos,
+            )
+        } else {
+            (Vec3(0.0, 1.0, 0.0), 1.0 - 2.0 * sampler.gen::<f32>())
+        };
+        let phi = 2.0 * PI * sampler.gen::<f32>();
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let (tangent, bitangent) = orthonormal_basis(&axis);
+        sin_theta * phi.cos() * tangent + sin_theta * phi.sin() * bitangent + cos_theta * axis
+    }
+}
+
+/// The Perez sky distribution in a direction `sun_angle` radians from the sun, whose angle
+/// from the zenith has the cosine `cos_zenith`.
+fn perez_distribution(coefficients: &[f32; 5], cos_zenith: f32, sun_angle: f32) -> f32 {
+    let [a, b, c, d, e] = *coefficients;
+    let cos_sun = sun_angle.cos();
+    (1.0 + a * (b / cos_zenith).exp()) * (1.0 + c * (d * sun_angle).exp() + e * cos_sun * cos_sun)
+}
+
+/// The fraction of each of red, green and blue sunlight that gets through the atmosphere, from
+/// Rayleigh scattering by the air and Ångström's formula for the haze, taken at a
+/// representative wavelength for each.
+fn sun_transmittance(sun_zenith: f32, turbidity: f32) -> Vec3 {
+    // The relative length of the path through the air (Kasten and Young, 1989).
+    let zenith_degrees = sun_zenith.to_degrees().min(90.0);
+    let air_mass =
+        1.0 / (sun_zenith.cos().max(0.0) + 0.50572 * (96.07995 - zenith_degrees).powf(-1.6364));
+    let beta = 0.04608 * turbidity - 0.04586;
+    let transmit = |wavelength: f32| {
+        let rayleigh = 0.008735 * wavelength.powf(-4.08);
+        let haze = beta * wavelength.powf(-1.3);
+        (-air_mass * (rayleigh + haze)).exp()
+    };
+    // Wavelengths in micrometres.
+    Vec3(transmit(0.65), transmit(0.57), transmit(0.475))
+}
+
+/// Converts a luminance and chromaticity to linear sRGB, clipping colors out of its gamut.
+fn xyy_to_rgb(x: f32, y: f32, luminance: f32) -> Vec3 {
+    if y <= 0.0 {
+        return Vec3(0.0, 0.0, 0.0);
+    }
+    let big_x = x / y * luminance;
+    let big_z = (1.0 - x - y) / y * luminance;
+    Vec3(
+        (3.2406 * big_x - 1.5372 * luminance - 0.4986 * big_z).max(0.0),
+        (-0.9689 * big_x + 1.8758 * luminance + 0.0415 * big_z).max(0.0),
+        (0.0557 * big_x - 0.2040 * luminance + 1.0570 * big_z).max(0.0),
+    )
+}
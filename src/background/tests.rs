@@ -0,0 +1,188 @@
+#[cfg(test)]
+use crate::background::{Background, Constant, EnvironmentMap, Gradient, PhysicalSky};
+#[cfg(test)]
+use crate::framebuffer::Framebuffer;
+#[cfg(test)]
+use crate::sampler::Sampler;
+#[cfg(test)]
+use crate::vec3::{Vec3, Vector};
+#[cfg(test)]
+use rand::prelude::*;
+#[cfg(test)]
+use std::f32::consts::PI;
+
+#[cfg(test)]
+fn close(a: Vec3, b: Vec3) -> bool {
+    (a - b).length() < 1e-4
+}
+
+/// A dim gray panorama, 16 pixels round and 8 high, with one bright pixel just above the
+/// horizon, a little right of the middle.
+#[cfg(test)]
+fn sun_map(rotation: f32) -> EnvironmentMap {
+    let mut pixels = vec![Vec3(0.1, 0.1, 0.1); 16 * 8];
+    pixels[3 * 16 + 8] = Vec3(100.0, 100.0, 100.0);
+    EnvironmentMap::new(Framebuffer::from_pixels(16, 8, pixels), rotation, 2.0)
+}
+
+/// The direction through the middle of pixel (8, 3) of `sun_map`.
+#[cfg(test)]
+fn sun_direction() -> Vec3 {
+    let theta = 3.5 / 8.0 * PI;
+    let phi = (8.5 / 16.0 - 0.5) * 2.0 * PI;
+    Vec3(
+        theta.sin() * phi.sin(),
+        theta.cos(),
+        -theta.sin() * phi.cos(),
+    )
+}
+
+#[test]
+fn test_constant_and_gradient() {
+    let constant = Constant(Vec3(0.1, 0.2, 0.3));
+    assert!(constant.radiance(&Vec3(1.0, 2.0, 3.0)) == Vec3(0.1, 0.2, 0.3));
+    assert!(!constant.can_sample());
+
+    let sky = Gradient::default();
+    assert!(close(
+        sky.radiance(&Vec3(0.0, -2.0, 0.0)),
+        Vec3(1.0, 1.0, 1.0)
+    ));
+    assert!(close(
+        sky.radiance(&Vec3(0.0, 3.0, 0.0)),
+        Vec3(0.5, 0.7, 1.0)
+    ));
+    assert!(close(
+        sky.radiance(&Vec3(1.0, 0.0, 1.0)),
+        Vec3(0.75, 0.85, 1.0)
+    ));
+}
+
+#[test]
+fn test_environment_lookup() {
+    let map = sun_map(0.0);
+
+    // Pixel centers give back their radiance, scaled by the intensity.
+    assert!(close(
+        map.radiance(&sun_direction()),
+        Vec3(200.0, 200.0, 200.0)
+    ));
+    assert!(close(
+        map.radiance(&Vec3(0.0, -1.0, 0.0)),
+        Vec3(0.2, 0.2, 0.2)
+    ));
+    // The left and right edges meet behind the camera without a seam.
+    assert!(close(
+        map.radiance(&Vec3(0.0, 0.3, 1.0)),
+        Vec3(0.2, 0.2, 0.2)
+    ));
+
+    // Turning the map a quarter turn counter-clockwise, seen from above, moves the sun from
+    // just right of -z to just right of -x.
+    let turned = sun_map(90.0);
+    let sun = sun_direction();
+    let moved = Vec3(sun.2, sun.1, -sun.0);
+    assert!(close(turned.radiance(&moved), Vec3(200.0, 200.0, 200.0)));
+    assert!(close(turned.radiance(&sun), Vec3(0.2, 0.2, 0.2)));
+}
+
+#[test]
+fn test_environment_sampling() {
+    let map = sun_map(30.0);
+    let mut sampler = Sampler::new(3);
+    assert!(map.can_sample());
+
+    // Samples land where the density is positive, and often where the sun shows, though it
+    // covers well under a hundredth of the sphere.
+    let count = 4000;
+    let mut near_sun = 0;
+    for _ in 0..count {
+        let direction = map.sample_direction(&mut sampler);
+        assert!((direction.length() - 1.0).abs() < 1e-4);
+        assert!(map.direction_pdf(&direction) > 0.0);
+        near_sun += (map.radiance(&direction).0 > 1.0) as usize;
+    }
+    assert!(near_sun > count / 4, "{} of {}", near_sun, count);
+
+    // The density integrates to one over the sphere of directions, and weighing radiance by
+    // it estimates the same total light as uniform sampling does.
+    let uniform: Vec<Vec3> = (0..200_000)
+        .map(|_| {
+            let z = 1.0 - 2.0 * sampler.gen::<f32>();
+            let phi = 2.0 * PI * sampler.gen::<f32>();
+            let radius = (1.0 - z * z).sqrt();
+            Vec3(radius * phi.cos(), radius * phi.sin(), z)
+        })
+        .collect();
+    let sphere = 4.0 * PI / uniform.len() as f32;
+    let integral: f32 = uniform.iter().map(|d| map.direction_pdf(d)).sum::<f32>() * sphere;
+    assert!((integral - 1.0).abs() < 0.05, "integral {}", integral);
+    let expected: f32 = uniform.iter().map(|d| map.radiance(d).0).sum::<f32>() * sphere;
+    let estimate: f32 = (0..count)
+        .map(|_| {
+            let direction = map.sample_direction(&mut sampler);
+            map.radiance(&direction).0 / map.direction_pdf(&direction)
+        })
+        .sum::<f32>()
+        / count as f32;
+    assert!(
+        (estimate - expected).abs() < 0.05 * expected,
+        "{} vs {}",
+        estimate,
+        expected
+    );
+
+    // A black map has nothing worth sampling.
+    let black = EnvironmentMap::new(Framebuffer::new(4, 2), 0.0, 1.0);
+    assert!(!black.can_sample());
+}
+
+#[test]
+fn test_physical_sky() {
+    let noon = PhysicalSky::new(60.0, 90.0, 3.0);
+    let sun = noon.sun_direction();
+    assert!(close(sun, Vec3(0.5, 0.75f32.sqrt(), 0.0)));
+
+    // The sun outshines the sky by far, and the sky overhead is blue.
+    let zenith = noon.radiance(&Vec3(0.0, 1.0, 0.0));
+    assert!(noon.radiance(&sun).1 > 10_000.0 * zenith.1);
+    assert!(zenith.2 > zenith.0 && zenith.1 > 0.2 && zenith.1 < 5.0);
+    // Around the sun the sky is brighter than away from it.
+    let near_sun = noon.radiance(&(sun + Vec3(0.0, 0.0, 0.1)));
+    let away = noon.radiance(&Vec3(-0.5, 0.75f32.sqrt(), 0.0));
+    assert!(near_sun.1 > away.1);
+    // Below the horizon, the sky keeps its color at the horizon.
+    assert!(close(
+        noon.radiance(&Vec3(0.0, -0.5, -1.0)),
+        noon.radiance(&Vec3(0.0, 0.0, -1.0))
+    ));
+
+    // A low sun is dimmer and redder, through more air, and more so in haze.
+    let evening = PhysicalSky::new(3.0, 90.0, 3.0);
+    let hazy = PhysicalSky::new(3.0, 90.0, 8.0);
+    let color = |sky: &PhysicalSky| sky.radiance(&sky.sun_direction());
+    let (high, low, hazy) = (color(&noon), color(&evening), color(&hazy));
+    assert!(low.1 < high.1 && hazy.1 < low.1);
+    assert!(low.0 / low.2 > high.0 / high.2);
+
+    // Half the samples go to the sun, and the other half cover the sky.
+    let mut sampler = Sampler::new(5);
+    let count = 2000;
+    let on_sun = (0..count)
+        .filter(|_| {
+            let direction = noon.sample_direction(&mut sampler);
+            assert!(noon.direction_pdf(&direction) > 0.0);
+            noon.radiance(&direction).1 > 1000.0
+        })
+        .count();
+    assert!((on_sun as f32 / count as f32 - 0.5).abs() < 0.05);
+    let sky: f32 = (0..count)
+        .map(|_| {
+            let z = 1.0 - 2.0 * sampler.gen::<f32>();
+            let phi = 2.0 * PI * sampler.gen::<f32>();
+            let radius = (1.0 - z * z).sqrt();
+            noon.direction_pdf(&Vec3(radius * phi.cos(), radius * phi.sin(), z))
+        })
+        .sum();
+    assert!((sky / count as f32 * 4.0 * PI - 0.5).abs() < 0.01);
+}
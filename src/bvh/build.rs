@@ -0,0 +1,187 @@
+use super::{Node, NodeContents};
+use crate::aabb::Aabb;
+use crate::objects::Hittable;
+use std::cmp::Ordering;
+
+/// Relative cost of testing a ray against one more bounding box, against a cost of 1 for
+/// testing it against an object.
+const TRAVERSAL_COST: f32 = 0.125;
+
+/// How a node's objects are divided between its two children.
+#[derive(Copy, Clone, Debug)]
+pub enum SplitStrategy {
+    /// Split at the middle of the centroids' extent along the longest axis. Cheap to build, but
+    /// a few large objects can leave one side nearly empty.
+    Midpoint,
+    /// Split so that both children get the same number of objects, ordered by centroid along
+    /// the longest axis.
+    EqualCounts,
+    /// Bucket centroids into `bins` slices along each axis and pick the split with the lowest
+    /// surface area heuristic cost. Slowest to build, fastest to trace.
+    Sah { bins: usize },
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct BvhOptions {
+    pub split: SplitStrategy,
+    /// Nodes with at most this many objects are made into leaves. With `Sah`, larger leaves
+    /// are only made when no split is cheaper.
+    pub max_leaf_size: usize,
+}
+
+impl Default for BvhOptions {
+    fn default() -> Self {
+        BvhOptions {
+            split: SplitStrategy::Sah { bins: 16 },
+            max_leaf_size: 4,
+        }
+    }
+}
+
+type Entry = (Aabb, Box<dyn Hittable>);
+
+pub(super) fn build(objects: Vec<Entry>, options: &BvhOptions) -> Node {
+    let bounding_box = objects
+        .iter()
+        .fold(Aabb::empty(), |bounds, (object_box, _)| {
+            Aabb::surrounding(&bounds, object_box)
+        });
+    let centroid_bounds = objects
+        .iter()
+        .fold(Aabb::empty(), |bounds, (object_box, _)| {
+            let centroid = object_box.centroid();
+            Aabb::surrounding(&bounds, &Aabb::new(centroid, centroid))
+        });
+    let max_leaf_size = options.max_leaf_size.max(1);
+    if objects.len() <= 1 {
+        return leaf(bounding_box, objects);
+    }
+    let axis = centroid_bounds.longest_axis();
+    // Objects whose centroids all coincide can't be told apart by any split.
+    let extent = centroid_bounds.max[axis] - centroid_bounds.min[axis];
+
+    let (axis, left, right) = match options.split {
+        SplitStrategy::Sah { bins } if extent > 0.0 => {
+            let bins = bins.max(2);
+            match best_sah_split(&objects, &bounding_box, &centroid_bounds, bins) {
+                Some((split_axis, split_bin, cost))
+                    if cost < objects.len() as f32 || objects.len() > max_leaf_size =>
+                {
+                    let (left, right) = objects.into_iter().partition(|(object_box, _)| {
+                        bin_index(object_box, &centroid_bounds, split_axis, bins) < split_bin
+                    });
+                    (split_axis, left, right)
+                }
+                _ if objects.len() <= max_leaf_size => return leaf(bounding_box, objects),
+                _ => split_equal_counts(objects, axis),
+            }
+        }
+        _ if objects.len() <= max_leaf_size => return leaf(bounding_box, objects),
+        SplitStrategy::Midpoint if extent > 0.0 => {
+            let middle = centroid_bounds.centroid()[axis];
+            let (left, right): (Vec<Entry>, Vec<Entry>) = objects
+                .into_iter()
+                .partition(|(object_box, _)| object_box.centroid()[axis] < middle);
+            if left.is_empty() || right.is_empty() {
+                let mut objects = left;
+                objects.extend(right);
+                split_equal_counts(objects, axis)
+            } else {
+                (axis, left, right)
+            }
+        }
+        _ => split_equal_counts(objects, axis),
+    };
+
+    let left = build(left, options);
+    let right = build(right, options);
+    Node {
+        bounding_box,
+        light_count: left.light_count + right.light_count,
+        contents: NodeContents::Branch {
+            axis,
+            left: Box::new(left),
+            right: Box::new(right),
+        },
+    }
+}
+
+fn leaf(bounding_box: Aabb, objects: Vec<Entry>) -> Node {
+    Node {
+        bounding_box,
+        light_count: objects.iter().map(|(_, object)| object.light_count()).sum(),
+        contents: NodeContents::Leaf(objects.into_iter().map(|(_, object)| object).collect()),
+    }
+}
+
+fn split_equal_counts(mut objects: Vec<Entry>, axis: usize) -> (usize, Vec<Entry>, Vec<Entry>) {
+    let middle = objects.len() / 2;
+    objects.select_nth_unstable_by(middle, |(a, _), (b, _)| {
+        a.centroid()[axis]
+            .partial_cmp(&b.centroid()[axis])
+            .unwrap_or(Ordering::Equal)
+    });
+    let right = objects.split_off(middle);
+    (axis, objects, right)
+}
+
+fn bin_index(object_box: &Aabb, centroid_bounds: &Aabb, axis: usize, bins: usize) -> usize {
+    let extent = centroid_bounds.max[axis] - centroid_bounds.min[axis];
+    let offset = (object_box.centroid()[axis] - centroid_bounds.min[axis]) / extent;
+    ((offset * bins as f32) as usize).min(bins - 1)
+}
+
+/// Finds the axis and bin boundary with the lowest expected cost of tracing a ray through the
+/// two children, measured in object intersections. Objects in bins below the boundary go
+/// left.
+fn best_sah_split(
+    objects: &[Entry],
+    bounding_box: &Aabb,
+    centroid_bounds: &Aabb,
+    bins: usize,
+) -> Option<(usize, usize, f32)> {
+    let parent_area = bounding_box.surface_area();
+    let mut best: Option<(usize, usize, f32)> = None;
+    for axis in 0..3 {
+        if centroid_bounds.max[axis] - centroid_bounds.min[axis] <= 0.0 {
+            continue;
+        }
+        let mut bin_boxes = vec![Aabb::empty(); bins];
+        let mut bin_counts = vec![0usize; bins];
+        for (object_box, _) in objects {
+            let index = bin_index(object_box, centroid_bounds, axis, bins);
+            bin_boxes[index] = Aabb::surrounding(&bin_boxes[index], object_box);
+            bin_counts[index] += 1;
+        }
+
+        // right_costs[i] is the area-weighted count of everything in bins i and above.
+        let mut right_costs = vec![0.0; bins];
+        let mut right_box = Aabb::empty();
+        let mut right_count = 0;
+        for index in (1..bins).rev() {
+            right_box = Aabb::surrounding(&right_box, &bin_boxes[index]);
+            right_count += bin_counts[index];
+            right_costs[index] = right_box.surface_area() * right_count as f32;
+        }
+
+        let mut left_box = Aabb::empty();
+        let mut left_count = 0;
+        for split in 1..bins {
+            left_box = Aabb::surrounding(&left_box, &bin_boxes[split - 1]);
+            left_count += bin_counts[split - 1];
+            if left_count == 0 || left_count == objects.len() {
+                continue;
+            }
+            let weighted = left_box.surface_area() * left_count as f32 + right_costs[split];
+            let cost = if parent_area > 0.0 {
+                TRAVERSAL_COST + weighted / parent_area
+            } else {
+                TRAVERSAL_COST + objects.len() as f32
+            };
+            if best.is_none_or(|(_, _, best_cost)| cost < best_cost) {
+                best = Some((axis, split, cost));
+            }
+        }
+    }
+    best
+}
@@ -0,0 +1,337 @@
+mod build;
+mod tests;
+use crate::aabb::Aabb;
+use crate::objects::{HitRecord, Hittable};
+use crate::ray::Ray;
+use crate::sampler::Sampler;
+use crate::vec3::Vec3;
+pub use build::{BvhOptions, SplitStrategy};
+use std::fmt;
+
+/// A bounding volume hierarchy over a list of objects.
+///
+/// A drop-in replacement for `HittableList`: it finds the same closest hit, but only tests the
+/// objects whose bounding boxes the ray actually passes through. Unbounded objects can't be
+/// placed in the tree, so they are kept aside and tested against every ray.
+pub struct BvhNode {
+    root: Node,
+    unbounded: Vec<Box<dyn Hittable>>,
+}
+
+struct Node {
+    bounding_box: Aabb,
+    /// How many lights the objects under the node hold.
+    light_count: usize,
+    contents: NodeContents,
+}
+
+enum NodeContents {
+    Leaf(Vec<Box<dyn Hittable>>),
+    /// Children split along `axis`, with `left` holding the smaller centroids.
+    Branch {
+        axis: usize,
+        left: Box<Node>,
+        right: Box<Node>,
+    },
+}
+
+impl BvhNode {
+    /// Builds the hierarchy with the default options.
+    pub fn new(list: Vec<Box<dyn Hittable>>) -> Self {
+        Self::with_options(list, BvhOptions::default())
+    }
+
+    pub fn with_options(list: Vec<Box<dyn Hittable>>, options: BvhOptions) -> Self {
+        let mut bounded = Vec::with_capacity(list.len());
+        let mut unbounded = Vec::new();
+        for object in list {
+            let bounding_box = object.bounding_box();
+            if bounding_box.is_bounded() {
+                bounded.push((bounding_box, object));
+            } else {
+                unbounded.push(object);
+            }
+        }
+        BvhNode {
+            root: build::build(bounded, &options),
+            unbounded,
+        }
+    }
+
+    pub fn stats(&self) -> BvhStats {
+        let mut stats = BvhStats {
+            node_count: 0,
+            leaf_count: 0,
+            max_depth: 0,
+            object_count: 0,
+            min_leaf_size: usize::MAX,
+            max_leaf_size: 0,
+            unbounded_count: self.unbounded.len(),
+        };
+        self.root.collect_stats(&mut stats, 1);
+        stats
+    }
+}
+
+impl Node {
+    fn hit(
+        &self,
+        ray: &Ray,
+        inverse_direction: &Vec3,
+        distance_min: f32,
+        distance_max: f32,
+    ) -> Option<HitRecord<'_>> {
+        if !self.bounding_box.hit_with_inverse(
+            ray.origin(),
+            inverse_direction,
+            distance_min,
+            distance_max,
+        ) {
+            return None;
+        }
+        match &self.contents {
+            NodeContents::Leaf(objects) => {
+                let mut result = None;
+                let mut closest_so_far = distance_max;
+                for object in objects.iter() {
+                    if let Some(hit) = object.hit(ray, distance_min, closest_so_far) {
+                        closest_so_far = hit.distance;
+                        result = Some(hit);
+                    }
+                }
+                result
+            }
+            NodeContents::Branch { axis, left, right } => {
+                // Visit the child nearer the ray origin first, so that the farther one can
+                // usually be culled by the closer hit.
+                let (near, far) = if inverse_direction[*axis] < 0.0 {
+                    (right, left)
+                } else {
+                    (left, right)
+                };
+                let near_hit = near.hit(ray, inverse_direction, distance_min, distance_max);
+                let closest_so_far = near_hit.as_ref().map_or(distance_max, |hit| hit.distance);
+                far.hit(ray, inverse_direction, distance_min, closest_so_far)
+                    .or(near_hit)
+            }
+        }
+    }
+
+    fn direction_pdf(&self, ray: &Ray, inverse_direction: &Vec3) -> f32 {
+        if !self
+            .bounding_box
+            .hit_with_inverse(ray.origin(), inverse_direction, 0.001, f32::MAX)
+        {
+            return 0.0;
+        }
+        match &self.contents {
+            NodeContents::Leaf(objects) => objects
+                .iter()
+                .map(|object| object.direction_pdf(ray.origin(), ray.direction()))
+                .sum(),
+            NodeContents::Branch { left, right, .. } => {
+                left.direction_pdf(ray, inverse_direction)
+                    + right.direction_pdf(ray, inverse_direction)
+            }
+        }
+    }
+
+    /// Like `direction_pdf`, but over lights only, skipping the parts of the tree that have
+    /// none.
+    fn lights_direction_pdf(&self, ray: &Ray, inverse_direction: &Vec3) -> f32 {
+        if self.light_count == 0
+            || !self
+                .bounding_box
+                .hit_with_inverse(ray.origin(), inverse_direction, 0.001, f32::MAX)
+        {
+            return 0.0;
+        }
+        match &self.contents {
+            NodeContents::Leaf(objects) => objects
+                .iter()
+                .filter(|object| object.light_count() > 0)
+                .map(|object| object.lights_direction_pdf(ray.origin(), ray.direction()))
+                .sum(),
+            NodeContents::Branch { left, right, .. } => {
+                left.lights_direction_pdf(ray, inverse_direction)
+                    + right.lights_direction_pdf(ray, inverse_direction)
+            }
+        }
+    }
+
+    /// Finds light `index` by the counts kept in the nodes, so only one path down the tree is
+    /// followed.
+    fn sample_light_direction(&self, index: usize, origin: &Vec3, sampler: &mut Sampler) -> Vec3 {
+        match &self.contents {
+            NodeContents::Leaf(objects) => {
+                let mut index = index;
+                for object in objects.iter() {
+                    let count = object.light_count();
+                    if index < count {
+                        return object.sample_light_direction(index, origin, sampler);
+                    }
+                    index -= count;
+                }
+                panic!("light index out of range")
+            }
+            NodeContents::Branch { left, right, .. } => {
+                if index < left.light_count {
+                    left.sample_light_direction(index, origin, sampler)
+                } else {
+                    right.sample_light_direction(index - left.light_count, origin, sampler)
+                }
+            }
+        }
+    }
+
+    fn collect_stats(&self, stats: &mut BvhStats, depth: usize) {
+        stats.node_count += 1;
+        stats.max_depth = stats.max_depth.max(depth);
+        match &self.contents {
+            NodeContents::Leaf(objects) => {
+                stats.leaf_count += 1;
+                stats.object_count += objects.len();
+                stats.min_leaf_size = stats.min_leaf_size.min(objects.len());
+                stats.max_leaf_size = stats.max_leaf_size.max(objects.len());
+            }
+            NodeContents::Branch { left, right, .. } => {
+                left.collect_stats(stats, depth + 1);
+                right.collect_stats(stats, depth + 1);
+            }
+        }
+    }
+
+    fn collect_lights<'a>(&'a self, lights: &mut Vec<&'a dyn Hittable>) {
+        match &self.contents {
+            NodeContents::Leaf(objects) => {
+                for object in objects.iter() {
+                    object.collect_lights(lights);
+                }
+            }
+            NodeContents::Branch { left, right, .. } => {
+                left.collect_lights(lights);
+                right.collect_lights(lights);
+            }
+        }
+    }
+}
+
+impl Hittable for BvhNode {
+    fn hit(&self, ray: &Ray, distance_min: f32, distance_max: f32) -> Option<HitRecord<'_>> {
+        let mut result = self
+            .root
+            .hit(ray, &ray.inverse_direction(), distance_min, distance_max);
+        let mut closest_so_far = result.as_ref().map_or(distance_max, |hit| hit.distance);
+        for object in self.unbounded.iter() {
+            if let Some(hit) = object.hit(ray, distance_min, closest_so_far) {
+                closest_so_far = hit.distance;
+                result = Some(hit);
+            }
+        }
+        result
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        if self.unbounded.is_empty() {
+            self.root.bounding_box
+        } else {
+            Aabb::unbounded()
+        }
+    }
+
+    fn collect_lights<'a>(&'a self, lights: &mut Vec<&'a dyn Hittable>) {
+        self.root.collect_lights(lights);
+        for object in self.unbounded.iter() {
+            object.collect_lights(lights);
+        }
+    }
+
+    fn light_count(&self) -> usize {
+        self.root.light_count
+            + self
+                .unbounded
+                .iter()
+                .map(|object| object.light_count())
+                .sum::<usize>()
+    }
+
+    fn lights_direction_pdf(&self, origin: &Vec3, direction: &Vec3) -> f32 {
+        let ray = Ray::new(*origin, *direction);
+        let unbounded: f32 = self
+            .unbounded
+            .iter()
+            .filter(|object| object.light_count() > 0)
+            .map(|object| object.lights_direction_pdf(origin, direction))
+            .sum();
+        self.root
+            .lights_direction_pdf(&ray, &ray.inverse_direction())
+            + unbounded
+    }
+
+    fn sample_light_direction(&self, index: usize, origin: &Vec3, sampler: &mut Sampler) -> Vec3 {
+        if index < self.root.light_count {
+            return self.root.sample_light_direction(index, origin, sampler);
+        }
+        let mut index = index - self.root.light_count;
+        for object in self.unbounded.iter() {
+            let count = object.light_count();
+            if index < count {
+                return object.sample_light_direction(index, origin, sampler);
+            }
+            index -= count;
+        }
+        panic!("light index out of range")
+    }
+
+    /// The members' densities summed, visiting only the parts of the tree the direction
+    /// passes through. This suits objects that share one sampling density out among their
+    /// members, as a mesh does among its triangles.
+    fn direction_pdf(&self, origin: &Vec3, direction: &Vec3) -> f32 {
+        let ray = Ray::new(*origin, *direction);
+        let unbounded: f32 = self
+            .unbounded
+            .iter()
+            .map(|object| object.direction_pdf(origin, direction))
+            .sum();
+        self.root.direction_pdf(&ray, &ray.inverse_direction()) + unbounded
+    }
+}
+
+/// Shape of a built hierarchy, for comparing split strategies.
+#[derive(Copy, Clone, Debug)]
+pub struct BvhStats {
+    /// Interior nodes and leaves together.
+    pub node_count: usize,
+    pub leaf_count: usize,
+    /// Number of nodes on the longest path from the root to a leaf, counting both ends.
+    pub max_depth: usize,
+    /// Bounded objects stored in the tree.
+    pub object_count: usize,
+    pub min_leaf_size: usize,
+    pub max_leaf_size: usize,
+    /// Objects kept outside the tree because they have no finite bounding box.
+    pub unbounded_count: usize,
+}
+
+impl BvhStats {
+    pub fn average_leaf_size(&self) -> f32 {
+        self.object_count as f32 / self.leaf_count as f32
+    }
+}
+
+impl fmt::Display for BvhStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} objects ({} unbounded), {} nodes, {} leaves, depth {}, leaf size {}-{} (avg {:.2})",
+            self.object_count,
+            self.unbounded_count,
+            self.node_count,
+            self.leaf_count,
+            self.max_depth,
+            self.min_leaf_size,
+            self.max_leaf_size,
+            self.average_leaf_size()
+        )
+    }
+}
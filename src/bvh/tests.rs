@@ -0,0 +1,228 @@
+#[cfg(test)]
+use crate::aabb::Aabb;
+#[cfg(test)]
+use crate::bvh::{BvhNode, BvhOptions, SplitStrategy};
+#[cfg(test)]
+use crate::materials::Lambertian;
+#[cfg(test)]
+use crate::objects::{HitRecord, Hittable, HittableList, Sphere};
+#[cfg(test)]
+use crate::ray::Ray;
+#[cfg(test)]
+use crate::vec3::{Vec3, Vector};
+#[cfg(test)]
+use rand::prelude::*;
+
+#[cfg(test)]
+fn random_spheres(rng: &mut impl Rng, count: usize) -> Vec<Box<dyn Hittable>> {
+    (0..count)
+        .map(|_| {
+            let sphere: Box<dyn Hittable> = Box::new(Sphere {
+                center: Vec3(
+                    rng.gen_range(-10.0, 10.0),
+                    rng.gen_range(-10.0, 10.0),
+                    rng.gen_range(-10.0, 10.0),
+                ),
+                radius: rng.gen_range(0.1, 1.0),
+                material: Box::new(Lambertian::new(Vec3(0.5, 0.5, 0.5))),
+            });
+            sphere
+        })
+        .collect()
+}
+
+#[test]
+fn test_empty_bvh_is_never_hit() {
+    let bvh = BvhNode::new(Vec::new());
+    let ray = Ray::new(Vec3(0.0, 0.0, 0.0), Vec3(1.0, 0.0, 0.0));
+
+    assert!(bvh.hit(&ray, 0.0, f32::MAX).is_none());
+}
+
+#[cfg(test)]
+fn assert_bvh_matches_linear_list(options: BvhOptions) {
+    let mut rng = StdRng::seed_from_u64(7);
+    let spheres = random_spheres(&mut rng, 200);
+    let mut rng = StdRng::seed_from_u64(7);
+    let bvh = BvhNode::with_options(random_spheres(&mut rng, 200), options);
+    let list = HittableList::new(spheres);
+
+    for _ in 0..1000 {
+        let origin = Vec3(
+            rng.gen_range(-20.0, 20.0),
+            rng.gen_range(-20.0, 20.0),
+            -20.0,
+        );
+        let direction = Vec3(rng.gen_range(-1.0, 1.0), rng.gen_range(-1.0, 1.0), 1.0);
+        let ray = Ray::new(origin, direction);
+        let expected = list.hit(&ray, 0.001, f32::MAX).map(|hit| hit.distance);
+        let actual = bvh.hit(&ray, 0.001, f32::MAX).map(|hit| hit.distance);
+        assert!(expected == actual);
+    }
+}
+
+#[test]
+fn test_bvh_matches_linear_list() {
+    for split in [
+        SplitStrategy::Midpoint,
+        SplitStrategy::EqualCounts,
+        SplitStrategy::Sah { bins: 12 },
+    ]
+    .iter()
+    {
+        for max_leaf_size in 1..5 {
+            assert_bvh_matches_linear_list(BvhOptions {
+                split: *split,
+                max_leaf_size,
+            });
+        }
+    }
+}
+
+#[test]
+fn test_bvh_stats() {
+    let mut rng = StdRng::seed_from_u64(11);
+    let bvh = BvhNode::with_options(
+        random_spheres(&mut rng, 100),
+        BvhOptions {
+            split: SplitStrategy::EqualCounts,
+            max_leaf_size: 1,
+        },
+    );
+    let stats = bvh.stats();
+
+    assert!(stats.object_count == 100);
+    assert!(stats.leaf_count == 100);
+    assert!(stats.node_count == 199);
+    assert!(stats.max_depth == 8);
+    assert!(stats.min_leaf_size == 1 && stats.max_leaf_size == 1);
+
+    let mut rng = StdRng::seed_from_u64(11);
+    let stats = BvhNode::new(random_spheres(&mut rng, 100)).stats();
+    assert!(stats.object_count == 100);
+    assert!(stats.node_count == 2 * stats.leaf_count - 1);
+    assert!(stats.max_leaf_size <= BvhOptions::default().max_leaf_size);
+}
+
+#[test]
+fn test_bvh_with_coincident_objects() {
+    let objects: Vec<Box<dyn Hittable>> = (0..10)
+        .map(|i| {
+            let sphere: Box<dyn Hittable> = Box::new(Sphere {
+                center: Vec3(1.0, 2.0, 3.0),
+                radius: 0.1 * (i + 1) as f32,
+                material: Box::new(Lambertian::new(Vec3(0.5, 0.5, 0.5))),
+            });
+            sphere
+        })
+        .collect();
+    let bvh = BvhNode::new(objects);
+    let ray = Ray::new(Vec3(1.0, 2.0, -10.0), Vec3(0.0, 0.0, 1.0));
+    let hit = bvh.hit(&ray, 0.001, f32::MAX).map(|hit| hit.distance);
+
+    assert!(hit == Some(12.0));
+    assert!(bvh.stats().object_count == 10);
+}
+
+#[test]
+fn test_bvh_bounding_box_contains_all_objects() {
+    let mut rng = StdRng::seed_from_u64(3);
+    let list = HittableList::new(random_spheres(&mut rng, 50));
+    let mut rng = StdRng::seed_from_u64(3);
+    let bvh = BvhNode::new(random_spheres(&mut rng, 50));
+
+    assert!(bvh.bounding_box().min == list.bounding_box().min);
+    assert!(bvh.bounding_box().max == list.bounding_box().max);
+}
+
+/// The plane y = 0, standing in for an infinite primitive.
+#[cfg(test)]
+struct GroundPlane(Lambertian);
+
+#[cfg(test)]
+impl Hittable for GroundPlane {
+    fn hit(&self, ray: &Ray, distance_min: f32, distance_max: f32) -> Option<HitRecord<'_>> {
+        let distance = -ray.origin().y() / ray.direction().y();
+        if distance < distance_max && distance > distance_min {
+            return Some(HitRecord {
+                distance,
+                point: ray.point_at(distance),
+                normal: Vec3(0.0, 1.0, 0.0),
+                u: 0.0,
+                v: 0.0,
+                material: &self.0,
+            });
+        }
+        None
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        Aabb::unbounded()
+    }
+}
+
+#[test]
+fn test_bvh_with_unbounded_object() {
+    let mut rng = StdRng::seed_from_u64(5);
+    let mut objects = random_spheres(&mut rng, 20);
+    objects.push(Box::new(GroundPlane(Lambertian::new(Vec3(0.5, 0.5, 0.5)))));
+    let bvh = BvhNode::new(objects);
+
+    let far_away = Ray::new(Vec3(1000.0, 5.0, 1000.0), Vec3(0.0, -1.0, 0.0));
+    let hit = bvh.hit(&far_away, 0.001, f32::MAX).map(|hit| hit.distance);
+
+    assert!(hit == Some(5.0));
+    assert!(!bvh.bounding_box().is_bounded());
+}
+
+/// Compares build and trace times of the split strategies on a scene shaped like
+/// `random_scene`. Run with `cargo test --release -- --ignored --nocapture`.
+#[test]
+#[ignore]
+fn benchmark_split_strategies() {
+    use std::time::Instant;
+
+    for split in [
+        SplitStrategy::Midpoint,
+        SplitStrategy::EqualCounts,
+        SplitStrategy::Sah { bins: 16 },
+    ]
+    .iter()
+    {
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut objects = random_spheres(&mut rng, 10_000);
+        objects.push(Box::new(Sphere {
+            center: Vec3(0.0, -1000.0, 0.0),
+            radius: 1000.0,
+            material: Box::new(Lambertian::new(Vec3(0.5, 0.5, 0.5))),
+        }));
+        let options = BvhOptions {
+            split: *split,
+            ..BvhOptions::default()
+        };
+        let start = Instant::now();
+        let bvh = BvhNode::with_options(objects, options);
+        let build_time = start.elapsed();
+
+        let start = Instant::now();
+        let mut hits = 0;
+        for _ in 0..200_000 {
+            let origin = Vec3(rng.gen_range(-20.0, 20.0), 20.0, rng.gen_range(-20.0, 20.0));
+            let direction = Vec3(rng.gen_range(-1.0, 1.0), -1.0, rng.gen_range(-1.0, 1.0));
+            if bvh
+                .hit(&Ray::new(origin, direction), 0.001, f32::MAX)
+                .is_some()
+            {
+                hits += 1;
+            }
+        }
+        println!(
+            "{:?}: build {:?}, trace {:?} ({} hits)\n    {}",
+            split,
+            build_time,
+            start.elapsed(),
+            hits,
+            bvh.stats()
+        );
+    }
+}
@@ -1,4 +1,5 @@
 use crate::ray::Ray;
+use crate::sampler::Sampler;
 use crate::vec3::{Vec3, Vector};
 use rand::prelude::*;
 use std::f32;
@@ -11,7 +12,9 @@ pub struct Camera {
     lens_radius: f32,
     u: Vec3,
     v: Vec3,
-    w: Vec3,
+    /// The times at which the shutter opens and closes. Rays are spread evenly between them.
+    shutter_open: f32,
+    shutter_close: f32,
 }
 
 impl Camera {
@@ -48,27 +51,46 @@ impl Camera {
             lens_radius,
             u,
             v,
-            w,
+            shutter_open: 0.0,
+            shutter_close: 0.0,
         }
     }
 
-    pub fn get_ray(&self, u: f32, v: f32) -> Ray {
+    /// Keeps the shutter open from `open` to `close`, blurring anything that moves in between.
+    /// By default it opens and closes at time 0.
+    pub fn with_shutter(self, open: f32, close: f32) -> Self {
+        Self {
+            shutter_open: open,
+            shutter_close: close,
+            ..self
+        }
+    }
+
+    pub fn shutter(&self) -> (f32, f32) {
+        (self.shutter_open, self.shutter_close)
+    }
+
+    pub fn get_ray(&self, u: f32, v: f32, sampler: &mut Sampler) -> Ray {
         let horizontal_vector = u * self.horizontal;
         let vertical_vector = v * self.vertical;
-        let ray_direction = self.lens_radius * random_in_unit_disk();
+        let ray_direction = self.lens_radius * random_in_unit_disk(sampler);
         let offset = ray_direction.x() * self.u + ray_direction.y() * self.v;
         let direction =
             self.lower_left_corner + horizontal_vector + vertical_vector - self.origin - offset;
-        Ray::new(self.origin + offset, direction)
+        let time = if self.shutter_close > self.shutter_open {
+            self.shutter_open + sampler.gen::<f32>() * (self.shutter_close - self.shutter_open)
+        } else {
+            self.shutter_open
+        };
+        Ray::with_time(self.origin + offset, direction, time)
     }
 }
 
-fn random_in_unit_disk() -> Vec3 {
+fn random_in_unit_disk(sampler: &mut Sampler) -> Vec3 {
     let mut point: Vec3;
-    let mut rng = rand::thread_rng();
     while {
-        let x: f32 = rng.gen();
-        let y: f32 = rng.gen();
+        let x: f32 = sampler.gen();
+        let y: f32 = sampler.gen();
         point = 2.0 * Vec3(x, y, 0.0) - Vec3(1.0, 1.0, 0.0);
         Vec3::dot(&point, &point) >= 1.0
     } {}
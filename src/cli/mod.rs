@@ -0,0 +1,217 @@
+mod tests;
+use raytracing::bvh::{BvhOptions, SplitStrategy};
+use raytracing::image::ImageFormat;
+use raytracing::render::{LightingStrategy, RenderSettings};
+use raytracing::scenes::BUILTIN_SCENES;
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+pub const USAGE: &str = "\
+Usage: raytracing [OPTIONS]
+
+Options:
+  -W, --width <PIXELS>      Image width [default: 1200]
+  -H, --height <PIXELS>     Image height [default: 800]
+  -s, --samples <COUNT>     Samples per pixel [default: 10]
+  -d, --max-depth <COUNT>   Maximum bounces per path [default: 50]
+  -o, --output <PATH>       Output file [default: foo.png]
+  -f, --format <FORMAT>     Output format: png, ppm, exr, hdr or pfm
+                            [default: taken from the output file extension]
+      --seed <NUMBER>       Random seed; the same seed gives the same image [default: 0]
+      --frame <NUMBER>      Frame number, mixed into the seed [default: 0]
+  -j, --threads <COUNT>     Render threads, 0 for one per core [default: 0]
+      --tile-size <PIXELS>  Edge length of the tiles handed to threads [default: 32]
+      --scene <SCENE>       A built-in scene (random, spheres or cornell) or a .toml scene
+                            file
+                            [default: random]
+      --bvh <STRATEGY>      BVH split strategy: sah, midpoint or equal [default: sah]
+      --lighting <STRATEGY> How light from emitters is found: bsdf, lights, balance or
+                            power [default: power]
+      --stats               Print statistics about the scene's BVH
+  -h, --help                Print this help and exit
+";
+
+/// Everything needed to render and save one image.
+#[derive(Debug)]
+pub struct Options {
+    pub overrides: SettingsOverrides,
+    pub output: PathBuf,
+    pub format: ImageFormat,
+    pub scene: SceneSource,
+    pub bvh: BvhOptions,
+    /// Whether to report on the BVH before rendering.
+    pub stats: bool,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum SceneSource {
+    Builtin(String),
+    File(PathBuf),
+}
+
+/// Render settings given on the command line. They take precedence over a scene file's
+/// `[render]` section, which in turn takes precedence over the defaults.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct SettingsOverrides {
+    pub width: Option<usize>,
+    pub height: Option<usize>,
+    pub samples: Option<usize>,
+    pub max_depth: Option<usize>,
+    pub seed: Option<u64>,
+    pub frame: Option<u64>,
+    pub threads: Option<usize>,
+    pub tile_size: Option<usize>,
+    pub lighting: Option<LightingStrategy>,
+}
+
+impl SettingsOverrides {
+    pub fn apply(&self, settings: RenderSettings) -> RenderSettings {
+        RenderSettings {
+            width: self.width.unwrap_or(settings.width),
+            height: self.height.unwrap_or(settings.height),
+            samples: self.samples.unwrap_or(settings.samples),
+            max_depth: self.max_depth.unwrap_or(settings.max_depth),
+            seed: self.seed.unwrap_or(settings.seed),
+            frame: self.frame.unwrap_or(settings.frame),
+            threads: self.threads.unwrap_or(settings.threads),
+            tile_size: self.tile_size.unwrap_or(settings.tile_size),
+            lighting: self.lighting.unwrap_or(settings.lighting),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Command {
+    Render(Box<Options>),
+    Help,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct CliError(String);
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Parses the command line, not including the program name.
+///
+/// Options take their value either as the next argument or after an `=`, as in
+/// `--samples=100`.
+pub fn parse<I>(args: I) -> Result<Command, CliError>
+where
+    I: IntoIterator<Item = String>,
+{
+    let mut settings = SettingsOverrides::default();
+    let mut output = PathBuf::from("foo.png");
+    let mut format = None;
+    let mut scene = SceneSource::Builtin(String::from("random"));
+    let mut bvh = BvhOptions::default();
+    let mut stats = false;
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let (flag, inline_value) = match arg.find('=') {
+            Some(index) if arg.starts_with("--") => {
+                (arg[..index].to_string(), Some(arg[index + 1..].to_string()))
+            }
+            _ => (arg.clone(), None),
+        };
+        if flag == "-h" || flag == "--help" {
+            return Ok(Command::Help);
+        }
+        let mut value = || {
+            inline_value
+                .clone()
+                .or_else(|| args.next())
+                .ok_or_else(|| CliError(format!("{} needs a value", flag)))
+        };
+        match flag.as_str() {
+            "-W" | "--width" => settings.width = Some(positive(&flag, &value()?)?),
+            "-H" | "--height" => settings.height = Some(positive(&flag, &value()?)?),
+            "-s" | "--samples" => settings.samples = Some(positive(&flag, &value()?)?),
+            "-d" | "--max-depth" => settings.max_depth = Some(number(&flag, &value()?)?),
+            "-o" | "--output" => output = PathBuf::from(value()?),
+            "-f" | "--format" => {
+                let name = value()?;
+                format = Some(
+                    ImageFormat::from_name(&name)
+                        .ok_or_else(|| CliError(format!("unknown image format '{}'", name)))?,
+                );
+            }
+            "--seed" => settings.seed = Some(number(&flag, &value()?)?),
+            "--frame" => settings.frame = Some(number(&flag, &value()?)?),
+            "-j" | "--threads" => settings.threads = Some(number(&flag, &value()?)?),
+            "--tile-size" => settings.tile_size = Some(positive(&flag, &value()?)?),
+            "--scene" => {
+                let name = value()?;
+                scene = if BUILTIN_SCENES.contains(&name.as_str()) {
+                    SceneSource::Builtin(name)
+                } else if name.ends_with(".toml") {
+                    SceneSource::File(PathBuf::from(name))
+                } else {
+                    return Err(CliError(format!(
+                        "unknown scene '{}', expected a .toml file or one of: {}",
+                        name,
+                        BUILTIN_SCENES.join(", ")
+                    )));
+                };
+            }
+            "--bvh" => {
+                bvh.split = match value()?.as_str() {
+                    "sah" => SplitStrategy::Sah { bins: 16 },
+                    "midpoint" => SplitStrategy::Midpoint,
+                    "equal" => SplitStrategy::EqualCounts,
+                    other => {
+                        return Err(CliError(format!("unknown BVH strategy '{}'", other)));
+                    }
+                }
+            }
+            "--stats" if inline_value.is_none() => stats = true,
+            "--lighting" => {
+                let name = value()?;
+                settings.lighting =
+                    Some(LightingStrategy::from_name(&name).ok_or_else(|| {
+                        CliError(format!("unknown lighting strategy '{}'", name))
+                    })?);
+            }
+            _ => return Err(CliError(format!("unexpected argument '{}'", arg))),
+        }
+    }
+
+    // Catch a bad output name now rather than after a long render.
+    let format = match format.or_else(|| ImageFormat::from_path(&output)) {
+        Some(format) => format,
+        None => {
+            return Err(CliError(format!(
+                "can't tell the image format of '{}'; use a .png, .ppm, .exr, .hdr or .pfm \
+                 extension, or pass --format",
+                output.display()
+            )))
+        }
+    };
+
+    Ok(Command::Render(Box::new(Options {
+        overrides: settings,
+        output,
+        format,
+        scene,
+        bvh,
+        stats,
+    })))
+}
+
+fn number<T: FromStr>(flag: &str, value: &str) -> Result<T, CliError> {
+    value
+        .parse()
+        .map_err(|_| CliError(format!("{} expects a whole number, got '{}'", flag, value)))
+}
+
+fn positive(flag: &str, value: &str) -> Result<usize, CliError> {
+    match number(flag, value)? {
+        0 => Err(CliError(format!("{} must be at least 1", flag))),
+        count => Ok(count),
+    }
+}
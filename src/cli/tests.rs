@@ -0,0 +1,105 @@
+#[cfg(test)]
+use crate::cli::{parse, CliError, Command, Options, SceneSource};
+#[cfg(test)]
+use raytracing::image::ImageFormat;
+#[cfg(test)]
+use raytracing::render::{LightingStrategy, RenderSettings};
+
+#[cfg(test)]
+fn parse_options(args: &[&str]) -> Result<Options, CliError> {
+    match parse(args.iter().map(|arg| arg.to_string()))? {
+        Command::Render(options) => Ok(*options),
+        Command::Help => panic!("expected render options"),
+    }
+}
+
+#[test]
+fn test_defaults() {
+    let options = parse_options(&[]).unwrap();
+    let settings = options.overrides.apply(RenderSettings::default());
+
+    assert!(settings.width == 1200 && settings.height == 800);
+    assert!(settings.samples == 10 && settings.max_depth == 50);
+    assert!(options.format == ImageFormat::Png);
+    assert!(options.scene == SceneSource::Builtin("random".to_string()));
+    assert!(!options.stats);
+}
+
+#[test]
+fn test_parse_options() {
+    let options = parse_options(&[
+        "-W",
+        "320",
+        "--height=240",
+        "-s",
+        "64",
+        "--max-depth",
+        "8",
+        "-o",
+        "out.exr",
+        "--seed",
+        "7",
+        "-j",
+        "4",
+        "--scene",
+        "spheres",
+        "--lighting=balance",
+        "--stats",
+    ])
+    .unwrap();
+    let settings = options.overrides.apply(RenderSettings::default());
+
+    assert!(settings.width == 320 && settings.height == 240);
+    assert!(settings.samples == 64 && settings.max_depth == 8);
+    assert!(settings.seed == 7 && settings.threads == 4);
+    assert!(options.output.to_str() == Some("out.exr"));
+    assert!(options.format == ImageFormat::Exr);
+    assert!(options.scene == SceneSource::Builtin("spheres".to_string()));
+    assert!(settings.lighting == LightingStrategy::Balance);
+    assert!(options.stats);
+}
+
+#[test]
+fn test_format_overrides_extension() {
+    let options = parse_options(&["-o", "render.out", "--format", "pfm"]).unwrap();
+
+    assert!(options.format == ImageFormat::Pfm);
+}
+
+#[test]
+fn test_scene_file() {
+    let options = parse_options(&["--scene", "scenes/three_spheres.toml", "-s", "5"]).unwrap();
+    let settings = options.overrides.apply(RenderSettings {
+        samples: 100,
+        width: 400,
+        ..RenderSettings::default()
+    });
+
+    assert!(options.scene == SceneSource::File("scenes/three_spheres.toml".into()));
+    assert!(settings.samples == 5 && settings.width == 400);
+}
+
+#[test]
+fn test_help() {
+    let help = parse(vec![
+        "-s".to_string(),
+        "4".to_string(),
+        "--help".to_string(),
+    ]);
+
+    assert!(matches!(help, Ok(Command::Help)));
+}
+
+#[test]
+fn test_invalid_arguments() {
+    assert!(parse_options(&["--width", "0"]).is_err());
+    assert!(parse_options(&["--samples", "many"]).is_err());
+    assert!(parse_options(&["--height"]).is_err());
+    assert!(parse_options(&["--scene", "teapot"]).is_err());
+    assert!(parse_options(&["--format", "gif"]).is_err());
+    assert!(parse_options(&["-o", "render.gif"]).is_err());
+    assert!(parse_options(&["--bvh", "octree"]).is_err());
+    assert!(parse_options(&["--lighting", "photons"]).is_err());
+    assert!(parse_options(&["--frobnicate"]).is_err());
+    assert!(parse_options(&["--stats=yes"]).is_err());
+}
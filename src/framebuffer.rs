@@ -0,0 +1,65 @@
+use crate::vec3::Vec3;
+
+/// A rectangle of pixels, with `(x, y)` at its top-left corner.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Tile {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+/// Linear radiance for every pixel of a rendered or loaded image, stored row by row from the top.
+pub struct Framebuffer {
+    width: usize,
+    height: usize,
+    pixels: Vec<Vec3>,
+}
+
+impl Framebuffer {
+    pub fn new(width: usize, height: usize) -> Self {
+        Framebuffer {
+            width,
+            height,
+            pixels: vec![Vec3(0.0, 0.0, 0.0); width * height],
+        }
+    }
+
+    /// Wraps pixels given row by row from the top. Panics if there aren't `width * height`.
+    pub fn from_pixels(width: usize, height: usize, pixels: Vec<Vec3>) -> Self {
+        assert!(
+            pixels.len() == width * height,
+            "{} pixels for a {}x{} image",
+            pixels.len(),
+            width,
+            height
+        );
+        Framebuffer {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Copies a tile's pixels, given row by row from the tile's top-left corner, into place.
+    pub fn write_tile(&mut self, tile: &Tile, pixels: &[Vec3]) {
+        for row in 0..tile.height {
+            let start = (tile.y + row) * self.width + tile.x;
+            self.pixels[start..start + tile.width]
+                .copy_from_slice(&pixels[row * tile.width..(row + 1) * tile.width]);
+        }
+    }
+
+    /// Every pixel, row by row from the top.
+    pub fn pixels(&self) -> &[Vec3] {
+        &self.pixels
+    }
+}
@@ -0,0 +1,66 @@
+use crate::framebuffer::Framebuffer;
+use crate::vec3::Vec3;
+use ::exr::prelude::{
+    read as read_exr, Encoding, Image, ReadChannels, ReadLayers, SpecificChannels, Vec2,
+    WritableImage,
+};
+use std::io;
+
+/// Writes an uncompressed scanline OpenEXR image with 32-bit float R, G and B channels. The
+/// format has no way to store an image without pixels, so an empty framebuffer is an error.
+pub fn write(framebuffer: &Framebuffer, mut writer: impl io::Write) -> io::Result<()> {
+    let width = framebuffer.width();
+    if framebuffer.pixels().is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "an OpenEXR image needs at least one pixel",
+        ));
+    }
+    let channels = SpecificChannels::rgb(|position: Vec2<usize>| {
+        let pixel = framebuffer.pixels()[position.y() * width + position.x()];
+        (pixel.0, pixel.1, pixel.2)
+    });
+    let image = Image::from_encoded_channels(
+        (width, framebuffer.height()),
+        Encoding::UNCOMPRESSED,
+        channels,
+    );
+    // The table of scanline offsets comes before the scanlines but is only known after them,
+    // so the file is put together in memory, where it can be gone back over.
+    let mut bytes = io::Cursor::new(Vec::new());
+    image
+        .write()
+        .non_parallel()
+        .to_buffered(&mut bytes)
+        .map_err(io::Error::other)?;
+    writer.write_all(bytes.get_ref())?;
+    writer.flush()
+}
+
+/// Reads the red, green and blue channels of the first layer of an OpenEXR image, in any of
+/// the compressions and sample types the format allows. Alpha is ignored.
+pub fn read(bytes: &[u8]) -> Result<Framebuffer, String> {
+    let image = read_exr()
+        .no_deep_data()
+        .largest_resolution_level()
+        .rgba_channels(
+            |size: Vec2<usize>, _| (size.width(), vec![Vec3(0.0, 0.0, 0.0); size.area()]),
+            |(width, pixels): &mut (usize, Vec<Vec3>),
+             position: Vec2<usize>,
+             (red, green, blue, _): (f32, f32, f32, f32)| {
+                pixels[position.y() * *width + position.x()] = Vec3(red, green, blue)
+            },
+        )
+        .first_valid_layer()
+        .all_attributes()
+        .non_parallel()
+        .from_buffered(io::Cursor::new(bytes))
+        .map_err(|error| error.to_string())?;
+    let size = image.layer_data.size;
+    let (_, pixels) = image.layer_data.channel_data.pixels;
+    Ok(Framebuffer::from_pixels(
+        size.width(),
+        size.height(),
+        pixels,
+    ))
+}
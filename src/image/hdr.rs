@@ -0,0 +1,171 @@
+use crate::framebuffer::Framebuffer;
+use crate::vec3::Vec3;
+use std::io;
+
+/// The longest scanline that can be run-length encoded, as its width is stored in 15 bits.
+const MAX_ENCODED_WIDTH: usize = 0x7fff;
+
+/// Writes a Radiance RGBE (.hdr) image with flat, uncompressed scanlines.
+pub fn write(framebuffer: &Framebuffer, mut writer: impl io::Write) -> io::Result<()> {
+    write!(
+        writer,
+        "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {} +X {}\n",
+        framebuffer.height(),
+        framebuffer.width()
+    )?;
+    for pixel in framebuffer.pixels() {
+        writer.write_all(&to_rgbe(*pixel))?;
+    }
+    writer.flush()
+}
+
+/// Packs a colour into three 8-bit mantissas that share one exponent, taken from the
+/// brightest channel. Negative and NaN channels become 0, and channels too bright for the
+/// format, infinite ones included, become the largest value it holds.
+pub fn to_rgbe(color: Vec3) -> [u8; 4] {
+    let clamp = |channel: f32| {
+        if channel > 0.0 {
+            channel.min(f32::MAX)
+        } else {
+            0.0
+        }
+    };
+    let channels = [clamp(color.0), clamp(color.1), clamp(color.2)];
+    let brightest = channels[0].max(channels[1]).max(channels[2]);
+    if brightest < 1e-32 {
+        return [0, 0, 0, 0];
+    }
+    // brightest = mantissa * 2^exponent with the mantissa in [0.5, 1). The exponent byte stops
+    // at 2^127, where the mantissas saturate instead.
+    let exponent = (brightest.log2().floor() as i32 + 1).min(127);
+    let scale = 2f32.powi(8 - exponent);
+    let quantize = |channel: f32| (channel * scale).min(255.0) as u8;
+    [
+        quantize(channels[0]),
+        quantize(channels[1]),
+        quantize(channels[2]),
+        (exponent + 128).clamp(0, 255) as u8,
+    ]
+}
+
+/// Reads a Radiance RGBE (.hdr) image with flat or run-length encoded scanlines. Only the usual
+/// orientation, rows from the top and pixels from the left (`-Y height +X width`), is
+/// supported, as is only RGB, not XYZ.
+pub fn read(bytes: &[u8]) -> Result<Framebuffer, String> {
+    let mut rest = bytes;
+    let mut next_line = || {
+        let end = rest.iter().position(|&byte| byte == b'\n')?;
+        let line = &rest[..end];
+        rest = &rest[end + 1..];
+        Some(line)
+    };
+    loop {
+        let line = next_line().ok_or("the header never ends")?;
+        if line.is_empty() {
+            break;
+        }
+        if let Some(format) = line.strip_prefix(b"FORMAT=") {
+            if format != b"32-bit_rle_rgbe" {
+                return Err(format!(
+                    "the {} format isn't supported",
+                    String::from_utf8_lossy(format)
+                ));
+            }
+        }
+    }
+    let resolution = next_line().ok_or("the resolution is missing")?;
+    let resolution = String::from_utf8_lossy(resolution);
+    let fields: Vec<&str> = resolution.split_whitespace().collect();
+    let (height, width): (usize, usize) = match fields.as_slice() {
+        ["-Y", height, "+X", width] => match (height.parse(), width.parse()) {
+            (Ok(height), Ok(width)) => (height, width),
+            _ => return Err(format!("bad resolution \"{}\"", resolution)),
+        },
+        _ => return Err(format!("unsupported resolution \"{}\"", resolution)),
+    };
+
+    // A run packs at most 127 pixels of one component into two bytes, which no scanline can
+    // beat, so a header claiming more pixels than that is wrong about the file.
+    let too_large = || format!("a {}x{} image doesn't fit in the file", width, height);
+    let pixel_count = width.checked_mul(height).ok_or_else(too_large)?;
+    if pixel_count > rest.len().saturating_mul(127) / 8 {
+        return Err(too_large());
+    }
+
+    let mut pixels = Vec::with_capacity(pixel_count);
+    if pixel_count > 0 {
+        let mut scanline = vec![[0; 4]; width];
+        for _ in 0..height {
+            rest = read_scanline(rest, &mut scanline)?;
+            pixels.extend(scanline.iter().map(|rgbe| from_rgbe(*rgbe)));
+        }
+    }
+    Ok(Framebuffer::from_pixels(width, height, pixels))
+}
+
+/// Fills `scanline` from the start of `bytes`, returning the bytes after it.
+///
+/// Encoded scanlines start with 2, 2 and their width, and store each component in turn as
+/// runs: a count over 128 repeats the next byte `count - 128` times, and a smaller count is
+/// followed by that many literal bytes.
+fn read_scanline<'a>(bytes: &'a [u8], scanline: &mut [[u8; 4]]) -> Result<&'a [u8], String> {
+    let width = scanline.len();
+    let truncated = || String::from("the pixels end early");
+    let encoded = (8..=MAX_ENCODED_WIDTH).contains(&width)
+        && bytes.len() >= 4
+        && bytes[0] == 2
+        && bytes[1] == 2
+        && bytes[2] & 0x80 == 0;
+    if !encoded {
+        if bytes.len() < 4 * width {
+            return Err(truncated());
+        }
+        for (pixel, rgbe) in scanline.iter_mut().zip(bytes.chunks_exact(4)) {
+            pixel.copy_from_slice(rgbe);
+        }
+        return Ok(&bytes[4 * width..]);
+    }
+
+    if (bytes[2] as usize) << 8 | bytes[3] as usize != width {
+        return Err(String::from("a scanline has the wrong width"));
+    }
+    let mut rest = &bytes[4..];
+    for component in 0..4 {
+        let mut x = 0;
+        while x < width {
+            let (&count, tail) = rest.split_first().ok_or_else(truncated)?;
+            let (run, repeated) = if count > 128 {
+                (count as usize - 128, true)
+            } else {
+                (count as usize, false)
+            };
+            if run == 0 || x + run > width {
+                return Err(String::from("a run overflows its scanline"));
+            }
+            let values = if repeated { 1 } else { run };
+            if tail.len() < values {
+                return Err(truncated());
+            }
+            for (offset, pixel) in scanline[x..x + run].iter_mut().enumerate() {
+                pixel[component] = tail[if repeated { 0 } else { offset }];
+            }
+            x += run;
+            rest = &tail[values..];
+        }
+    }
+    Ok(rest)
+}
+
+/// The inverse of `to_rgbe`, taking each mantissa from the middle of its quantization step.
+pub fn from_rgbe(rgbe: [u8; 4]) -> Vec3 {
+    if rgbe[3] == 0 {
+        return Vec3(0.0, 0.0, 0.0);
+    }
+    let scale = 2f32.powi(rgbe[3] as i32 - 136);
+    let unquantize = |mantissa: u8| (mantissa as f32 + 0.5) * scale;
+    Vec3(
+        unquantize(rgbe[0]),
+        unquantize(rgbe[1]),
+        unquantize(rgbe[2]),
+    )
+}
@@ -0,0 +1,124 @@
+mod exr;
+mod hdr;
+mod pfm;
+mod png;
+mod ppm;
+mod tests;
+use crate::framebuffer::Framebuffer;
+use crate::import::ImportError;
+use std::fs::{self, File};
+use std::io;
+use std::io::BufWriter;
+use std::path::Path;
+
+/// The first bytes of every OpenEXR file.
+const EXR_MAGIC: [u8; 4] = [0x76, 0x2f, 0x31, 0x01];
+
+/// The file formats a framebuffer can be saved as.
+///
+/// PNG and PPM are tone-mapped to 8-bit sRGB for viewing. EXR, HDR and PFM keep the linear
+/// radiance unclamped, for compositing and tone-mapping in other tools.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ImageFormat {
+    /// 8-bit sRGB PNG.
+    Png,
+    /// 8-bit sRGB binary (P6) PPM, written without any dependencies.
+    Ppm,
+    /// Uncompressed OpenEXR with 32-bit float channels.
+    Exr,
+    /// Radiance RGBE.
+    Hdr,
+    /// Portable Float Map.
+    Pfm,
+}
+
+impl ImageFormat {
+    /// Picks the format from the file extension, ignoring case.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        Self::from_name(path.extension()?.to_str()?)
+    }
+
+    /// Looks a format up by its usual file extension, ignoring case.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "png" => Some(ImageFormat::Png),
+            "ppm" => Some(ImageFormat::Ppm),
+            "exr" => Some(ImageFormat::Exr),
+            "hdr" => Some(ImageFormat::Hdr),
+            "pfm" => Some(ImageFormat::Pfm),
+            _ => None,
+        }
+    }
+}
+
+/// Saves the framebuffer in the given format, whatever the file extension. Use
+/// `ImageFormat::from_path` to pick the format from the extension instead.
+pub fn save(framebuffer: &Framebuffer, path: &Path, format: ImageFormat) -> io::Result<()> {
+    let writer = BufWriter::new(File::create(path)?);
+    write(framebuffer, format, writer)
+}
+
+pub fn write(
+    framebuffer: &Framebuffer,
+    format: ImageFormat,
+    writer: impl io::Write,
+) -> io::Result<()> {
+    match format {
+        ImageFormat::Png => png::write(framebuffer, writer),
+        ImageFormat::Ppm => ppm::write(framebuffer, writer),
+        ImageFormat::Exr => exr::write(framebuffer, writer),
+        ImageFormat::Hdr => hdr::write(framebuffer, writer),
+        ImageFormat::Pfm => pfm::write(framebuffer, writer),
+    }
+}
+
+/// Loads a Radiance HDR or OpenEXR image, recognized by its contents rather than its
+/// extension, as linear radiance.
+pub fn load(path: &Path) -> Result<Framebuffer, ImportError> {
+    let bytes = fs::read(path).map_err(|error| ImportError::Io {
+        path: path.to_path_buf(),
+        error,
+    })?;
+    let image = if bytes.starts_with(b"#?") {
+        hdr::read(&bytes)
+    } else if bytes.starts_with(&EXR_MAGIC) {
+        exr::read(&bytes)
+    } else {
+        Err(String::from("not a Radiance HDR or OpenEXR image"))
+    };
+    image.map_err(|message| ImportError::Malformed {
+        path: path.to_path_buf(),
+        message,
+    })
+}
+
+/// The sRGB transfer function, mapping linear radiance in [0, 1] to display values in [0, 1].
+pub fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.003_130_8 {
+        12.92 * value
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// The inverse of `linear_to_srgb`, for reading 8-bit images back as linear values.
+pub fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.040_45 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Quantizes the framebuffer to 8-bit sRGB, row by row from the top, three bytes per pixel.
+/// Values outside [0, 1] saturate.
+pub fn to_srgb8(framebuffer: &Framebuffer) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(framebuffer.pixels().len() * 3);
+    for pixel in framebuffer.pixels() {
+        for channel in &[pixel.0, pixel.1, pixel.2] {
+            let encoded = linear_to_srgb(channel.clamp(0.0, 1.0));
+            bytes.push((encoded * 255.0).round() as u8);
+        }
+    }
+    bytes
+}
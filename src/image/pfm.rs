@@ -0,0 +1,26 @@
+use crate::framebuffer::Framebuffer;
+use std::io;
+
+/// Writes a colour Portable Float Map: a text header and then little-endian 32-bit floats,
+/// with rows running from the bottom of the image as the format requires.
+pub fn write(framebuffer: &Framebuffer, mut writer: impl io::Write) -> io::Result<()> {
+    // A negative scale marks the data as little-endian.
+    write!(
+        writer,
+        "PF\n{} {}\n-1.0\n",
+        framebuffer.width(),
+        framebuffer.height()
+    )?;
+    for row in framebuffer
+        .pixels()
+        .chunks(framebuffer.width().max(1))
+        .rev()
+    {
+        for pixel in row {
+            for channel in &[pixel.0, pixel.1, pixel.2] {
+                writer.write_all(&channel.to_le_bytes())?;
+            }
+        }
+    }
+    writer.flush()
+}
@@ -0,0 +1,20 @@
+use super::to_srgb8;
+use crate::framebuffer::Framebuffer;
+use ::png::{BitDepth, ColorType, Encoder, SrgbRenderingIntent};
+use std::io;
+
+/// Writes an 8-bit RGB PNG tagged as sRGB.
+pub fn write(framebuffer: &Framebuffer, writer: impl io::Write) -> io::Result<()> {
+    let mut encoder = Encoder::new(
+        writer,
+        framebuffer.width() as u32,
+        framebuffer.height() as u32,
+    );
+    encoder.set_color(ColorType::Rgb);
+    encoder.set_depth(BitDepth::Eight);
+    encoder.set_source_srgb(SrgbRenderingIntent::Perceptual);
+    let mut png_writer = encoder.write_header()?;
+    png_writer.write_image_data(&to_srgb8(framebuffer))?;
+    png_writer.finish()?;
+    Ok(())
+}
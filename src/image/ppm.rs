@@ -0,0 +1,15 @@
+use super::to_srgb8;
+use crate::framebuffer::Framebuffer;
+use std::io;
+
+/// Writes a binary (P6) PPM, which is a short text header followed by raw RGB bytes.
+pub fn write(framebuffer: &Framebuffer, mut writer: impl io::Write) -> io::Result<()> {
+    write!(
+        writer,
+        "P6\n{} {}\n255\n",
+        framebuffer.width(),
+        framebuffer.height()
+    )?;
+    writer.write_all(&to_srgb8(framebuffer))?;
+    writer.flush()
+}
@@ -0,0 +1,206 @@
+#[cfg(test)]
+use crate::framebuffer::{Framebuffer, Tile};
+#[cfg(test)]
+use crate::image::hdr::to_rgbe;
+#[cfg(test)]
+use crate::image::{exr, hdr, linear_to_srgb, load, save, to_srgb8, write, ImageFormat};
+#[cfg(test)]
+use crate::import::ImportError;
+#[cfg(test)]
+use crate::vec3::{Vec3, Vector};
+#[cfg(test)]
+use std::fs;
+#[cfg(test)]
+use std::path::Path;
+
+#[cfg(test)]
+fn two_by_one(left: Vec3, right: Vec3) -> Framebuffer {
+    let mut framebuffer = Framebuffer::new(2, 1);
+    let tile = Tile {
+        x: 0,
+        y: 0,
+        width: 2,
+        height: 1,
+    };
+    framebuffer.write_tile(&tile, &[left, right]);
+    framebuffer
+}
+
+#[test]
+fn test_format_from_extension() {
+    assert!(ImageFormat::from_path(Path::new("out/render.png")) == Some(ImageFormat::Png));
+    assert!(ImageFormat::from_path(Path::new("render.PPM")) == Some(ImageFormat::Ppm));
+    assert!(ImageFormat::from_path(Path::new("render.bmp")).is_none());
+    assert!(ImageFormat::from_path(Path::new("render")).is_none());
+}
+
+#[test]
+fn test_srgb_encoding() {
+    assert!(linear_to_srgb(0.0) == 0.0);
+    assert!((linear_to_srgb(1.0) - 1.0).abs() < 1e-6);
+    assert!((linear_to_srgb(0.214_041) - 0.5).abs() < 1e-4);
+
+    let framebuffer = two_by_one(Vec3(0.0, 0.2, 1.0), Vec3(-1.0, 300.0, f32::NAN));
+    assert!(to_srgb8(&framebuffer) == vec![0, 124, 255, 0, 255, 0]);
+}
+
+#[test]
+fn test_write_ppm() {
+    let framebuffer = two_by_one(Vec3(1.0, 0.0, 0.0), Vec3(0.0, 0.0, 1.0));
+    let mut bytes = Vec::new();
+    write(&framebuffer, ImageFormat::Ppm, &mut bytes).unwrap();
+
+    assert!(bytes == b"P6\n2 1\n255\n\xff\x00\x00\x00\x00\xff".to_vec());
+}
+
+#[test]
+fn test_write_png() {
+    let framebuffer = two_by_one(Vec3(1.0, 0.0, 0.0), Vec3(0.0, 0.0, 1.0));
+    let mut bytes = Vec::new();
+    write(&framebuffer, ImageFormat::Png, &mut bytes).unwrap();
+
+    let decoder = png::Decoder::new(bytes.as_slice());
+    let mut reader = decoder.read_info().unwrap();
+    let mut pixels = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut pixels).unwrap();
+    assert!(info.width == 2 && info.height == 1);
+    assert!(pixels[..6] == [255, 0, 0, 0, 0, 255]);
+    assert!(reader.info().srgb.is_some());
+}
+
+#[test]
+fn test_hdr_formats_from_extension() {
+    assert!(ImageFormat::from_path(Path::new("render.exr")) == Some(ImageFormat::Exr));
+    assert!(ImageFormat::from_path(Path::new("render.hdr")) == Some(ImageFormat::Hdr));
+    assert!(ImageFormat::from_path(Path::new("render.pfm")) == Some(ImageFormat::Pfm));
+}
+
+#[test]
+fn test_rgbe_encoding() {
+    assert!(to_rgbe(Vec3(0.0, 0.0, 0.0)) == [0, 0, 0, 0]);
+    assert!(to_rgbe(Vec3(1.0, 0.5, 0.25)) == [128, 64, 32, 129]);
+    // Values above 1 keep their range instead of clamping.
+    assert!(to_rgbe(Vec3(300.0, 0.0, -2.0)) == [150, 0, 0, 137]);
+    // Infinite radiance, from a firefly or a huge emitter, saturates rather than overflowing.
+    assert!(to_rgbe(Vec3(f32::INFINITY, 0.0, 0.0)) == [255, 0, 0, 255]);
+    assert!(to_rgbe(Vec3(f32::MAX, 1e30, f32::NAN)) == to_rgbe(Vec3(f32::INFINITY, 1e30, 0.0)));
+}
+
+#[test]
+fn test_write_pfm() {
+    let mut framebuffer = Framebuffer::new(1, 2);
+    let tile = Tile {
+        x: 0,
+        y: 0,
+        width: 1,
+        height: 2,
+    };
+    framebuffer.write_tile(&tile, &[Vec3(1.0, 2.0, 3.0), Vec3(4.0, 5.0, 6.0)]);
+    let mut bytes = Vec::new();
+    write(&framebuffer, ImageFormat::Pfm, &mut bytes).unwrap();
+
+    let header = b"PF\n1 2\n-1.0\n";
+    assert!(bytes.starts_with(header));
+    let floats: Vec<f32> = bytes[header.len()..]
+        .chunks(4)
+        .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+        .collect();
+    // The bottom row comes first.
+    assert!(floats == vec![4.0, 5.0, 6.0, 1.0, 2.0, 3.0]);
+
+    let mut bytes = Vec::new();
+    write(&Framebuffer::new(0, 3), ImageFormat::Pfm, &mut bytes).unwrap();
+    assert!(bytes == b"PF\n0 3\n-1.0\n");
+}
+
+#[test]
+fn test_write_exr() {
+    let framebuffer = two_by_one(Vec3(1.0, 2.0, 3.0), Vec3(40.0, 50.0, 60.0));
+    let mut bytes = Vec::new();
+    write(&framebuffer, ImageFormat::Exr, &mut bytes).unwrap();
+
+    assert!(bytes[..4] == 20_000_630u32.to_le_bytes());
+    assert!(exr::read(&bytes).unwrap().pixels() == framebuffer.pixels());
+    let mut empty = Vec::new();
+    assert!(write(&Framebuffer::new(0, 3), ImageFormat::Exr, &mut empty).is_err());
+}
+
+#[test]
+fn test_read_hdr() {
+    let framebuffer = two_by_one(Vec3(1.0, 0.5, 0.25), Vec3(300.0, 0.01, 0.0));
+    let mut bytes = Vec::new();
+    write(&framebuffer, ImageFormat::Hdr, &mut bytes).unwrap();
+    let read = hdr::read(&bytes).unwrap();
+
+    // Each channel comes back within a step of the brightest one's 8-bit mantissa.
+    assert!(read.width() == 2 && read.height() == 1);
+    for (read, written) in read.pixels().iter().zip(framebuffer.pixels()) {
+        let step = written.0.max(written.1).max(written.2) / 128.0;
+        assert!((*read - *written).length() < step, "{:?}", read);
+    }
+
+    // A run-length encoded scanline: a run of 128s, eight literal 64s, a run of 32s, and a
+    // run of exponents.
+    let mut encoded = b"#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y 1 +X 8\n".to_vec();
+    encoded.extend(&[2, 2, 0, 8, 136, 128, 8]);
+    encoded.extend(&[64; 8]);
+    encoded.extend(&[136, 32, 136, 129]);
+    let read = hdr::read(&encoded).unwrap();
+    assert!(read.width() == 8 && read.height() == 1);
+    assert!(read
+        .pixels()
+        .iter()
+        .all(|pixel| (*pixel - Vec3(1.0, 0.5, 0.25)).length() < 0.01));
+
+    let truncated = hdr::read(&encoded[..encoded.len() - 1]);
+    assert!(matches!(truncated, Err(message) if message == "the pixels end early"));
+    let xyz = b"#?RADIANCE\nFORMAT=32-bit_rle_xyze\n\n-Y 1 +X 1\n\0\0\0\0";
+    assert!(hdr::read(xyz).is_err());
+
+    // Sizes the bytes can't hold are refused before anything is allocated for them.
+    let overflowing = b"#?RADIANCE\n\n-Y 18446744073709551615 +X 2\n\0\0\0\0";
+    assert!(hdr::read(overflowing).is_err());
+    let huge = b"#?RADIANCE\n\n-Y 100000 +X 100000\n\x02\x02\0\x08";
+    assert!(matches!(hdr::read(huge), Err(message) if message.contains("doesn't fit")));
+    let empty = hdr::read(b"#?RADIANCE\n\n-Y 0 +X 18446744073709551615\n").unwrap();
+    assert!(empty.pixels().is_empty());
+}
+
+#[test]
+fn test_read_exr() {
+    let framebuffer = two_by_one(Vec3(1.0, 2.0, 3.0), Vec3(40.0, -0.5, 1e6));
+    let mut bytes = Vec::new();
+    write(&framebuffer, ImageFormat::Exr, &mut bytes).unwrap();
+    let read = exr::read(&bytes).unwrap();
+
+    assert!(read.width() == 2 && read.height() == 1);
+    assert!(read.pixels() == framebuffer.pixels());
+    assert!(exr::read(&bytes[..bytes.len() - 8]).is_err());
+}
+
+#[test]
+fn test_load() {
+    let directory = std::env::temp_dir().join(format!("raytracing-image-{}", std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    let framebuffer = two_by_one(Vec3(1.0, 2.0, 3.0), Vec3(4.0, 5.0, 6.0));
+    save(&framebuffer, &directory.join("pair.exr"), ImageFormat::Exr).unwrap();
+    save(&framebuffer, &directory.join("pair.hdr"), ImageFormat::Hdr).unwrap();
+    save(&framebuffer, &directory.join("pair.png"), ImageFormat::Png).unwrap();
+    // Recognized by content, so a misleading extension doesn't matter.
+    fs::rename(directory.join("pair.hdr"), directory.join("pair.dat")).unwrap();
+    let exr = load(&directory.join("pair.exr"));
+    let hdr = load(&directory.join("pair.dat"));
+    let png = load(&directory.join("pair.png"));
+    let missing = load(&directory.join("missing.exr"));
+    fs::remove_dir_all(&directory).unwrap();
+
+    assert!(exr.unwrap().pixels() == framebuffer.pixels());
+    assert!(hdr.unwrap().width() == 2);
+    match png {
+        Err(ImportError::Malformed { message, .. }) => {
+            assert!(message == "not a Radiance HDR or OpenEXR image")
+        }
+        _ => panic!("expected the PNG to be rejected"),
+    }
+    assert!(matches!(missing, Err(ImportError::Io { .. })));
+}
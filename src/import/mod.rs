@@ -0,0 +1,98 @@
+mod mtl;
+mod obj;
+mod ply;
+mod tests;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+pub use mtl::{parse_mtl, MtlMaterial};
+pub use obj::{load_obj, ObjGroup, ObjModel};
+pub use ply::{load_ply, PlyModel};
+
+/// Why a model file couldn't be read.
+#[derive(Debug)]
+pub enum ImportError {
+    Io {
+        path: PathBuf,
+        error: io::Error,
+    },
+    /// A malformed line, numbered from 1.
+    Invalid {
+        path: PathBuf,
+        line: usize,
+        message: String,
+    },
+    /// Malformed data in a binary file, where there are no lines to point at.
+    Malformed { path: PathBuf, message: String },
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ImportError::Io { path, error } => write!(f, "{}: {}", path.display(), error),
+            ImportError::Invalid {
+                path,
+                line,
+                message,
+            } => write!(f, "{}:{}: {}", path.display(), line, message),
+            ImportError::Malformed { path, message } => {
+                write!(f, "{}: {}", path.display(), message)
+            }
+        }
+    }
+}
+
+/// One line of a text format, for reporting errors against.
+struct Line<'a> {
+    path: &'a Path,
+    number: usize,
+}
+
+impl Line<'_> {
+    fn error(&self, message: String) -> ImportError {
+        ImportError::Invalid {
+            path: self.path.to_path_buf(),
+            line: self.number,
+            message,
+        }
+    }
+
+    /// Parses the next token as a `what`, failing if it's missing or malformed.
+    fn parse<'t, T: FromStr>(
+        &self,
+        tokens: &mut impl Iterator<Item = &'t str>,
+        what: &str,
+    ) -> Result<T, ImportError> {
+        let token = tokens
+            .next()
+            .ok_or_else(|| self.error(format!("missing {}", what)))?;
+        token
+            .parse()
+            .map_err(|_| self.error(format!("expected {}, got '{}'", what, token)))
+    }
+}
+
+/// The lines of `source` with comments and surrounding whitespace removed, numbered from 1.
+/// Blank lines are skipped, and a line ending in `\` is joined to the next.
+fn lines(source: &str) -> Vec<(usize, String)> {
+    let mut lines = Vec::new();
+    let mut pending: Option<(usize, String)> = None;
+    for (index, raw) in source.lines().enumerate() {
+        let text = raw.split('#').next().unwrap_or("").trim();
+        let (number, mut joined) = pending.take().unwrap_or((index + 1, String::new()));
+        if let Some(start) = text.strip_suffix('\\') {
+            joined.push_str(start);
+            joined.push(' ');
+            pending = Some((number, joined));
+            continue;
+        }
+        joined.push_str(text);
+        if !joined.trim().is_empty() {
+            lines.push((number, joined));
+        }
+    }
+    lines.extend(pending.filter(|(_, text)| !text.trim().is_empty()));
+    lines
+}
@@ -0,0 +1,157 @@
+use super::{lines, ImportError, Line};
+use crate::materials::{Dielectric, DiffuseLight, Lambertian, Material, Metal};
+use crate::textures::ImageTexture;
+use crate::vec3::Vec3;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// The statements of one `newmtl` entry that the renderer understands.
+#[derive(Clone, PartialEq)]
+pub struct MtlMaterial {
+    /// `Kd`
+    pub diffuse: Vec3,
+    /// `Ks`
+    pub specular: Vec3,
+    /// `Ke`
+    pub emission: Vec3,
+    /// `Ns`, the Phong exponent, from 0 (rough) to 1000 (mirror-like).
+    pub shininess: f32,
+    /// `Ni`
+    pub refractive_index: f32,
+    /// `d`, or 1 minus `Tr`: 1 is opaque, 0 fully transparent.
+    pub dissolve: f32,
+    /// `illum`
+    pub illumination: Option<u32>,
+    /// `map_Kd`, relative to the directory of the `.mtl` file.
+    pub diffuse_map: Option<PathBuf>,
+}
+
+impl Default for MtlMaterial {
+    fn default() -> Self {
+        MtlMaterial {
+            diffuse: Vec3(0.8, 0.8, 0.8),
+            specular: Vec3(0.0, 0.0, 0.0),
+            emission: Vec3(0.0, 0.0, 0.0),
+            shininess: 0.0,
+            refractive_index: 1.0,
+            dissolve: 1.0,
+            illumination: None,
+            diffuse_map: None,
+        }
+    }
+}
+
+impl MtlMaterial {
+    /// The closest of the renderer's materials: a light for anything emissive, glass for
+    /// anything see-through, metal where the specular color outweighs the diffuse one, and
+    /// diffuse otherwise.
+    ///
+    /// A diffuse surface with a `map_Kd` takes its color from the image instead of `Kd`, which
+    /// is loaded from `diffuse_map` as it stands: resolve it against the `.mtl` file's
+    /// directory first, as `load_obj` does.
+    pub fn to_material(&self) -> Result<Box<dyn Material>, ImportError> {
+        if largest(self.emission) > 0.0 {
+            return Ok(Box::new(DiffuseLight::new(self.emission)));
+        }
+        let transparent = self.dissolve < 1.0
+            || matches!(self.illumination, Some(4) | Some(6) | Some(7) | Some(9));
+        if transparent {
+            let refractive_index = if self.refractive_index > 1.0 {
+                self.refractive_index
+            } else {
+                1.5
+            };
+            return Ok(Box::new(Dielectric::new(refractive_index)));
+        }
+        if largest(self.specular) > largest(self.diffuse) {
+            // A common mapping from Phong exponent to roughness.
+            let fuzziness = (2.0 / (self.shininess.max(0.0) + 2.0)).sqrt();
+            return Ok(Box::new(Metal::new(self.specular, fuzziness)));
+        }
+        Ok(match &self.diffuse_map {
+            Some(path) => Box::new(Lambertian::new(ImageTexture::load(path)?)),
+            None => Box::new(Lambertian::new(self.diffuse)),
+        })
+    }
+}
+
+fn largest(color: Vec3) -> f32 {
+    color.0.max(color.1).max(color.2)
+}
+
+/// Parses a material library. `path` is only used in error messages.
+///
+/// Statements the renderer has no use for, such as `Ka` or `map_Bump`, are skipped.
+pub fn parse_mtl(source: &str, path: &Path) -> Result<BTreeMap<String, MtlMaterial>, ImportError> {
+    let mut materials = BTreeMap::new();
+    let mut current: Option<(String, MtlMaterial)> = None;
+    for (number, text) in lines(source) {
+        let line = Line { path, number };
+        let mut tokens = text.split_whitespace();
+        let keyword = tokens.next().unwrap_or("");
+        if keyword == "newmtl" {
+            let name = tokens.collect::<Vec<_>>().join(" ");
+            if name.is_empty() {
+                return Err(line.error(String::from("newmtl needs a name")));
+            }
+            materials.extend(current.take());
+            current = Some((name, MtlMaterial::default()));
+            continue;
+        }
+        let material = match &mut current {
+            Some((_, material)) => material,
+            None if is_material_statement(keyword) => {
+                return Err(line.error(format!("{} before any newmtl", keyword)))
+            }
+            None => continue,
+        };
+        match keyword {
+            "Kd" => material.diffuse = color(&line, &mut tokens)?,
+            "Ks" => material.specular = color(&line, &mut tokens)?,
+            "Ke" => material.emission = color(&line, &mut tokens)?,
+            "Ns" => material.shininess = line.parse(&mut tokens, "a shininess")?,
+            "Ni" => material.refractive_index = line.parse(&mut tokens, "a refractive index")?,
+            "d" => material.dissolve = line.parse(&mut tokens, "a dissolve factor")?,
+            "Tr" => {
+                let transparency: f32 = line.parse(&mut tokens, "a transparency")?;
+                material.dissolve = 1.0 - transparency;
+            }
+            "illum" => {
+                material.illumination = Some(line.parse(&mut tokens, "an illumination model")?)
+            }
+            "map_Kd" => {
+                // Options such as `-s 1 1 1` come before the file name.
+                let file = tokens
+                    .last()
+                    .ok_or_else(|| line.error(String::from("map_Kd needs a file name")))?;
+                material.diffuse_map = Some(PathBuf::from(file));
+            }
+            _ => {}
+        }
+    }
+    materials.extend(current);
+    Ok(materials)
+}
+
+fn is_material_statement(keyword: &str) -> bool {
+    matches!(
+        keyword,
+        "Kd" | "Ks" | "Ke" | "Ns" | "Ni" | "d" | "Tr" | "illum" | "map_Kd"
+    )
+}
+
+fn color<'t>(line: &Line, tokens: &mut impl Iterator<Item = &'t str>) -> Result<Vec3, ImportError> {
+    let red = line.parse(tokens, "a red component")?;
+    // A single value is a gray.
+    match tokens.next() {
+        None => Ok(Vec3(red, red, red)),
+        Some(green) => {
+            let mut rest = std::iter::once(green).chain(tokens);
+            Ok(Vec3(
+                red,
+                line.parse(&mut rest, "a green component")?,
+                line.parse(&mut rest, "a blue component")?,
+            ))
+        }
+    }
+}
@@ -0,0 +1,307 @@
+use super::mtl::{parse_mtl, MtlMaterial};
+use super::{lines, ImportError, Line};
+use crate::objects::{MeshData, TriangleMesh};
+use crate::vec3::{Vec3, Vector};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+
+/// The triangles of an OBJ file, one group per material.
+pub struct ObjModel {
+    pub groups: Vec<ObjGroup>,
+    /// File names from `mtllib` statements, relative to the OBJ file.
+    pub material_libraries: Vec<String>,
+}
+
+pub struct ObjGroup {
+    /// The `usemtl` name in effect for these faces, if there was one.
+    pub material: Option<String>,
+    pub data: MeshData,
+}
+
+/// A face corner: indices into the position, texture coordinate and normal lists.
+#[derive(Copy, Clone)]
+struct Corner {
+    position: usize,
+    uv: Option<usize>,
+    normal: Option<usize>,
+}
+
+struct Face {
+    corners: Vec<Corner>,
+    /// Smoothing group, `None` when smoothing is off.
+    smoothing: Option<u32>,
+}
+
+/// Where a mesh vertex's normal comes from.
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+enum NormalSource {
+    /// The group has no normals at all and is shaded flat.
+    None,
+    /// A `vn` from the file.
+    Given(usize),
+    /// The average over all faces of a smoothing group that share the position.
+    Smoothed(u32),
+    /// The normal of one triangle, numbered within its group.
+    Flat(usize),
+}
+
+/// Loads an OBJ file and the material libraries it names, giving one mesh per material.
+///
+/// Faces without a material, or whose material can't be found in any library, are given the
+/// MTL default of a light gray diffuse surface. Images named by `map_Kd` are loaded along with
+/// the libraries, and like them must exist.
+pub fn load_obj(path: &Path) -> Result<Vec<TriangleMesh>, ImportError> {
+    let model = ObjModel::load(path)?;
+    let directory = path.parent().unwrap_or_else(|| Path::new(""));
+    let mut materials = BTreeMap::new();
+    for library in &model.material_libraries {
+        let library_path = directory.join(library);
+        let library_directory = library_path.parent().unwrap_or_else(|| Path::new(""));
+        for (name, mut material) in parse_mtl(&read(&library_path)?, &library_path)? {
+            material.diffuse_map = material.diffuse_map.map(|map| library_directory.join(map));
+            materials.insert(name, material);
+        }
+    }
+
+    let fallback = MtlMaterial::default();
+    model
+        .groups
+        .into_iter()
+        .map(|group| {
+            let material = group
+                .material
+                .as_ref()
+                .and_then(|name| materials.get(name))
+                .unwrap_or(&fallback);
+            Ok(TriangleMesh::new(group.data, material.to_material()?))
+        })
+        .collect()
+}
+
+fn read(path: &Path) -> Result<String, ImportError> {
+    fs::read_to_string(path).map_err(|error| ImportError::Io {
+        path: path.to_path_buf(),
+        error,
+    })
+}
+
+impl ObjModel {
+    /// Reads the geometry of an OBJ file, without its materials.
+    pub fn load(path: &Path) -> Result<Self, ImportError> {
+        Self::parse(&read(path)?, path)
+    }
+
+    /// Parses the geometry of an OBJ file. `path` is only used in error messages.
+    ///
+    /// Polygons are split into fans of triangles, which is only right for convex ones. Vertex
+    /// normals are used where the file gives them; elsewhere faces in a smoothing group
+    /// (`s 1`) share averaged normals and the rest are flat. Points, lines and statements the
+    /// renderer has no use for are skipped.
+    pub fn parse(source: &str, path: &Path) -> Result<Self, ImportError> {
+        let mut positions = Vec::new();
+        let mut uvs = Vec::new();
+        let mut normals = Vec::new();
+        let mut faces: Vec<(Option<String>, Vec<Face>)> = Vec::new();
+        let mut material_libraries = Vec::new();
+        let mut material: Option<String> = None;
+        let mut smoothing = None;
+
+        for (number, text) in lines(source) {
+            let line = Line { path, number };
+            let mut tokens = text.split_whitespace();
+            match tokens.next().unwrap_or("") {
+                "v" => positions.push(Vec3(
+                    line.parse(&mut tokens, "an x coordinate")?,
+                    line.parse(&mut tokens, "a y coordinate")?,
+                    line.parse(&mut tokens, "a z coordinate")?,
+                )),
+                "vt" => {
+                    let u = line.parse(&mut tokens, "a u coordinate")?;
+                    let v = match tokens.next() {
+                        Some(token) => line.parse(&mut std::iter::once(token), "a v coordinate")?,
+                        None => 0.0,
+                    };
+                    uvs.push([u, v]);
+                }
+                "vn" => normals.push(Vec3(
+                    line.parse(&mut tokens, "an x component")?,
+                    line.parse(&mut tokens, "a y component")?,
+                    line.parse(&mut tokens, "a z component")?,
+                )),
+                "f" => {
+                    let corners = tokens
+                        .map(|token| {
+                            parse_corner(&line, token, positions.len(), uvs.len(), normals.len())
+                        })
+                        .collect::<Result<Vec<_>, _>>()?;
+                    if corners.len() < 3 {
+                        return Err(line.error(format!(
+                            "a face needs at least 3 vertices, got {}",
+                            corners.len()
+                        )));
+                    }
+                    let face = Face { corners, smoothing };
+                    match faces.iter_mut().find(|(name, _)| *name == material) {
+                        Some((_, group)) => group.push(face),
+                        None => faces.push((material.clone(), vec![face])),
+                    }
+                }
+                "usemtl" => {
+                    let name = tokens.collect::<Vec<_>>().join(" ");
+                    if name.is_empty() {
+                        return Err(line.error(String::from("usemtl needs a material name")));
+                    }
+                    material = Some(name);
+                }
+                "mtllib" => material_libraries.extend(tokens.map(String::from)),
+                "s" => {
+                    smoothing = match tokens.next() {
+                        Some("off") | Some("0") => None,
+                        Some("on") => Some(1),
+                        Some(token) => Some(line.parse(
+                            &mut std::iter::once(token),
+                            "a smoothing group number or 'off'",
+                        )?),
+                        None => return Err(line.error(String::from("missing smoothing group"))),
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let groups = faces
+            .into_iter()
+            .map(|(material, faces)| ObjGroup {
+                material,
+                data: build_group(&faces, &positions, &uvs, &normals),
+            })
+            .collect();
+        Ok(ObjModel {
+            groups,
+            material_libraries,
+        })
+    }
+}
+
+/// Parses a face corner, `v`, `v/vt`, `v//vn` or `v/vt/vn`, into zero-based indices. Negative
+/// indices count back from the latest element.
+fn parse_corner(
+    line: &Line,
+    token: &str,
+    position_count: usize,
+    uv_count: usize,
+    normal_count: usize,
+) -> Result<Corner, ImportError> {
+    let mut parts = token.split('/');
+    let index = |part: Option<&str>, count: usize, what: &str| -> Result<_, ImportError> {
+        let part = match part {
+            None | Some("") => return Ok(None),
+            Some(part) => part,
+        };
+        let index: i64 = part
+            .parse()
+            .map_err(|_| line.error(format!("expected a {} index, got '{}'", what, part)))?;
+        let resolved = if index < 0 {
+            count as i64 + index
+        } else {
+            index - 1
+        };
+        if index == 0 || resolved < 0 || resolved >= count as i64 {
+            return Err(line.error(format!(
+                "{} index {} is out of range, there are {} so far",
+                what, index, count
+            )));
+        }
+        Ok(Some(resolved as usize))
+    };
+    let position = index(parts.next(), position_count, "vertex")?
+        .ok_or_else(|| line.error(format!("face corner '{}' has no vertex index", token)))?;
+    let uv = index(parts.next(), uv_count, "texture coordinate")?;
+    let normal = index(parts.next(), normal_count, "normal")?;
+    if parts.next().is_some() {
+        return Err(line.error(format!("malformed face corner '{}'", token)));
+    }
+    Ok(Corner {
+        position,
+        uv,
+        normal,
+    })
+}
+
+/// Triangulates a group's faces and gives each distinct corner its own mesh vertex.
+fn build_group(faces: &[Face], positions: &[Vec3], uvs: &[[f32; 2]], normals: &[Vec3]) -> MeshData {
+    let triangles: Vec<([Corner; 3], Option<u32>)> = faces
+        .iter()
+        .flat_map(|face| {
+            (1..face.corners.len() - 1).map(move |index| {
+                (
+                    [
+                        face.corners[0],
+                        face.corners[index],
+                        face.corners[index + 1],
+                    ],
+                    face.smoothing,
+                )
+            })
+        })
+        .collect();
+    let face_normal = |corners: &[Corner; 3]| {
+        let [a, b, c] = corners.map(|corner| positions[corner.position]);
+        // Left unnormalized so that larger faces weigh more in smoothed normals.
+        Vec3::cross(&(b - a), &(c - a))
+    };
+
+    let has_normals = triangles.iter().any(|(corners, smoothing)| {
+        smoothing.is_some() || corners.iter().any(|corner| corner.normal.is_some())
+    });
+    let has_uvs = triangles
+        .iter()
+        .any(|(corners, _)| corners.iter().any(|corner| corner.uv.is_some()));
+    let mut smoothed: HashMap<(usize, u32), Vec3> = HashMap::new();
+    for (corners, smoothing) in &triangles {
+        if let Some(group) = smoothing {
+            let normal = face_normal(corners);
+            for corner in corners.iter().filter(|corner| corner.normal.is_none()) {
+                *smoothed
+                    .entry((corner.position, *group))
+                    .or_insert(Vec3(0.0, 0.0, 0.0)) += normal;
+            }
+        }
+    }
+
+    let mut data = MeshData::default();
+    let mut vertices: HashMap<(usize, Option<usize>, NormalSource), u32> = HashMap::new();
+    for (triangle_index, (corners, smoothing)) in triangles.iter().enumerate() {
+        let mut triangle = [0; 3];
+        for (slot, corner) in triangle.iter_mut().zip(corners) {
+            let source = match (corner.normal, smoothing) {
+                _ if !has_normals => NormalSource::None,
+                (Some(normal), _) => NormalSource::Given(normal),
+                (None, Some(group)) => NormalSource::Smoothed(*group),
+                (None, None) => NormalSource::Flat(triangle_index),
+            };
+            *slot = *vertices
+                .entry((corner.position, corner.uv, source))
+                .or_insert_with(|| {
+                    data.positions.push(positions[corner.position]);
+                    if has_uvs {
+                        data.uvs.push(corner.uv.map_or([0.0, 0.0], |uv| uvs[uv]));
+                    }
+                    match source {
+                        NormalSource::None => {}
+                        NormalSource::Given(normal) => data.normals.push(normals[normal]),
+                        NormalSource::Smoothed(group) => data
+                            .normals
+                            .push(smoothed[&(corner.position, group)].unit_vector()),
+                        NormalSource::Flat(_) => {
+                            data.normals.push(face_normal(corners).unit_vector())
+                        }
+                    }
+                    (data.positions.len() - 1) as u32
+                });
+        }
+        data.triangles.push(triangle);
+    }
+    data
+}
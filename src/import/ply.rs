@@ -0,0 +1,494 @@
+use super::ImportError;
+use crate::materials::Lambertian;
+use crate::objects::{Hittable, MeshData, Sphere, TriangleMesh};
+use crate::vec3::Vec3;
+use std::convert::TryInto;
+use std::fs;
+use std::path::Path;
+
+/// The vertices, and faces if it has any, of a PLY file.
+pub struct PlyModel {
+    /// Positions, plus normals (`nx`, `ny`, `nz`) and texture coordinates (`u`, `v` or `s`,
+    /// `t`) where every vertex has them. Faces are split into fans of triangles.
+    pub mesh: MeshData,
+    /// Vertex colors (`red`, `green`, `blue`) scaled to 0-1, or empty.
+    pub colors: Vec<Vec3>,
+}
+
+/// Loads a PLY file as a triangle mesh if it has faces, and as a cloud of spheres of the
+/// given radius if it doesn't.
+///
+/// Points are colored with their vertex color. A mesh is given a single diffuse material,
+/// colored with the average of its vertex colors.
+pub fn load_ply(path: &Path, point_radius: f32) -> Result<Vec<Box<dyn Hittable>>, ImportError> {
+    let model = PlyModel::load(path)?;
+    let gray = Vec3(0.8, 0.8, 0.8);
+    if model.mesh.triangles.is_empty() {
+        let colors = model.colors;
+        return Ok(model
+            .mesh
+            .positions
+            .into_iter()
+            .enumerate()
+            .map(|(index, center)| {
+                let color = colors.get(index).copied().unwrap_or(gray);
+                let point: Box<dyn Hittable> = Box::new(Sphere {
+                    center,
+                    radius: point_radius,
+                    material: Box::new(Lambertian::new(color)),
+                });
+                point
+            })
+            .collect());
+    }
+    let color = if model.colors.is_empty() {
+        gray
+    } else {
+        let sum = model
+            .colors
+            .iter()
+            .fold(Vec3(0.0, 0.0, 0.0), |sum, color| sum + *color);
+        sum / model.colors.len() as f32
+    };
+    let mesh = TriangleMesh::new(model.mesh, Box::new(Lambertian::new(color)));
+    Ok(vec![Box::new(mesh)])
+}
+
+impl PlyModel {
+    pub fn load(path: &Path) -> Result<Self, ImportError> {
+        let bytes = fs::read(path).map_err(|error| ImportError::Io {
+            path: path.to_path_buf(),
+            error,
+        })?;
+        Self::parse(&bytes, path)
+    }
+
+    /// Parses an ASCII or binary PLY file. `path` is only used in error messages.
+    ///
+    /// Elements other than `vertex` and `face`, and properties the renderer has no use for,
+    /// are read past and dropped.
+    pub fn parse(bytes: &[u8], path: &Path) -> Result<Self, ImportError> {
+        let (header, body_start) = parse_header(bytes, path)?;
+        let mut body = match header.format {
+            Format::Ascii => {
+                let text = std::str::from_utf8(&bytes[body_start..]).map_err(|_| {
+                    ImportError::Malformed {
+                        path: path.to_path_buf(),
+                        message: String::from("ASCII body is not valid text"),
+                    }
+                })?;
+                Body::Ascii {
+                    lines: text.lines(),
+                    line: header.line_count,
+                }
+            }
+            Format::BinaryLittleEndian | Format::BinaryBigEndian => Body::Binary {
+                data: &bytes[body_start..],
+                big_endian: header.format == Format::BinaryBigEndian,
+            },
+        };
+
+        let mut model = PlyModel {
+            mesh: MeshData::default(),
+            colors: Vec::new(),
+        };
+        let mut normals = Vec::new();
+        let mut uvs = Vec::new();
+        for element in &header.elements {
+            let find = |names: &[&str]| {
+                element
+                    .properties
+                    .iter()
+                    .position(|property| names.contains(&property.name.as_str()))
+            };
+            let position = [find(&["x"]), find(&["y"]), find(&["z"])];
+            let normal = [find(&["nx"]), find(&["ny"]), find(&["nz"])];
+            let uv = [
+                find(&["u", "s", "texture_u", "texture_s"]),
+                find(&["v", "t", "texture_v", "texture_t"]),
+            ];
+            let color = [find(&["red"]), find(&["green"]), find(&["blue"])];
+            let indices = find(&["vertex_indices", "vertex_index"]);
+            if element.name == "vertex" && position.contains(&None) {
+                return Err(ImportError::Malformed {
+                    path: path.to_path_buf(),
+                    message: String::from("vertices need x, y and z properties"),
+                });
+            }
+
+            for index in 0..element.count {
+                let values = body.read_element(element, index, path)?;
+                if element.name == "vertex" {
+                    if let [Some(x), Some(y), Some(z)] = position {
+                        model.mesh.positions.push(vector(&values, [x, y, z]));
+                    }
+                    if let [Some(x), Some(y), Some(z)] = normal {
+                        normals.push(vector(&values, [x, y, z]));
+                    }
+                    if let [Some(u), Some(v)] = uv {
+                        uvs.push([scalar(&values[u]), scalar(&values[v])]);
+                    }
+                    if let [Some(red), Some(green), Some(blue)] = color {
+                        // Integer colors run to 255, floating-point ones to 1.
+                        let scale = match element.properties[red].kind {
+                            Kind::Scalar(kind) if kind.is_integer() => 1.0 / 255.0,
+                            _ => 1.0,
+                        };
+                        model
+                            .colors
+                            .push(vector(&values, [red, green, blue]) * scale);
+                    }
+                } else if element.name == "face" {
+                    let corners = match indices.map(|column| &values[column]) {
+                        Some(Value::List(corners)) => corners,
+                        _ => continue,
+                    };
+                    let vertex_count = model.mesh.positions.len();
+                    let corners = corners
+                        .iter()
+                        .map(|&corner| {
+                            if corner >= 0.0 && (corner as usize) < vertex_count {
+                                Ok(corner as u32)
+                            } else {
+                                Err(body.error(
+                                    path,
+                                    format!(
+                                        "face {} refers to vertex {}, but there are only {}",
+                                        index, corner, vertex_count
+                                    ),
+                                ))
+                            }
+                        })
+                        .collect::<Result<Vec<_>, _>>()?;
+                    if corners.len() < 3 {
+                        return Err(body.error(
+                            path,
+                            format!("face {} has only {} vertices", index, corners.len()),
+                        ));
+                    }
+                    for corner in 1..corners.len() - 1 {
+                        model.mesh.triangles.push([
+                            corners[0],
+                            corners[corner],
+                            corners[corner + 1],
+                        ]);
+                    }
+                }
+            }
+        }
+        model.mesh.normals = normals;
+        model.mesh.uvs = uvs;
+        Ok(model)
+    }
+}
+
+#[derive(Copy, Clone, PartialEq)]
+enum Format {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+#[derive(Copy, Clone, PartialEq)]
+enum Scalar {
+    Int8,
+    Uint8,
+    Int16,
+    Uint16,
+    Int32,
+    Uint32,
+    Float32,
+    Float64,
+}
+
+impl Scalar {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "char" | "int8" => Some(Scalar::Int8),
+            "uchar" | "uint8" => Some(Scalar::Uint8),
+            "short" | "int16" => Some(Scalar::Int16),
+            "ushort" | "uint16" => Some(Scalar::Uint16),
+            "int" | "int32" => Some(Scalar::Int32),
+            "uint" | "uint32" => Some(Scalar::Uint32),
+            "float" | "float32" => Some(Scalar::Float32),
+            "double" | "float64" => Some(Scalar::Float64),
+            _ => None,
+        }
+    }
+
+    fn size(self) -> usize {
+        match self {
+            Scalar::Int8 | Scalar::Uint8 => 1,
+            Scalar::Int16 | Scalar::Uint16 => 2,
+            Scalar::Int32 | Scalar::Uint32 | Scalar::Float32 => 4,
+            Scalar::Float64 => 8,
+        }
+    }
+
+    fn is_integer(self) -> bool {
+        !matches!(self, Scalar::Float32 | Scalar::Float64)
+    }
+
+    fn decode(self, bytes: &[u8], big_endian: bool) -> f64 {
+        macro_rules! decode {
+            ($type:ty) => {{
+                let bytes = bytes.try_into().expect("sliced to the scalar's size");
+                if big_endian {
+                    <$type>::from_be_bytes(bytes) as f64
+                } else {
+                    <$type>::from_le_bytes(bytes) as f64
+                }
+            }};
+        }
+        match self {
+            Scalar::Int8 => decode!(i8),
+            Scalar::Uint8 => decode!(u8),
+            Scalar::Int16 => decode!(i16),
+            Scalar::Uint16 => decode!(u16),
+            Scalar::Int32 => decode!(i32),
+            Scalar::Uint32 => decode!(u32),
+            Scalar::Float32 => decode!(f32),
+            Scalar::Float64 => decode!(f64),
+        }
+    }
+}
+
+#[derive(Copy, Clone)]
+enum Kind {
+    Scalar(Scalar),
+    /// A count of type `count` followed by that many `item`s.
+    List {
+        count: Scalar,
+        item: Scalar,
+    },
+}
+
+struct Property {
+    name: String,
+    kind: Kind,
+}
+
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+struct Header {
+    format: Format,
+    elements: Vec<Element>,
+    /// Lines up to and including `end_header`, for numbering the lines of an ASCII body.
+    line_count: usize,
+}
+
+enum Value {
+    Scalar(f64),
+    List(Vec<f64>),
+}
+
+fn scalar(value: &Value) -> f32 {
+    match value {
+        Value::Scalar(value) => *value as f32,
+        Value::List(_) => 0.0,
+    }
+}
+
+fn vector(values: &[Value], columns: [usize; 3]) -> Vec3 {
+    let [x, y, z] = columns.map(|column| scalar(&values[column]));
+    Vec3(x, y, z)
+}
+
+/// Reads the header, returning it and the offset of the first byte after `end_header`.
+fn parse_header(bytes: &[u8], path: &Path) -> Result<(Header, usize), ImportError> {
+    let error = |line: usize, message: String| ImportError::Invalid {
+        path: path.to_path_buf(),
+        line,
+        message,
+    };
+    let mut format = None;
+    let mut elements: Vec<Element> = Vec::new();
+    let mut offset = 0;
+    let mut number = 0;
+    loop {
+        let end = match bytes[offset..].iter().position(|&byte| byte == b'\n') {
+            Some(end) => offset + end,
+            None => return Err(error(number + 1, String::from("header has no end_header"))),
+        };
+        number += 1;
+        let line = String::from_utf8_lossy(&bytes[offset..end]);
+        offset = end + 1;
+        let mut tokens = line.split_whitespace();
+        let keyword = tokens.next().unwrap_or("");
+        if number == 1 {
+            if keyword != "ply" {
+                return Err(error(1, String::from("not a PLY file")));
+            }
+            continue;
+        }
+        match keyword {
+            "format" => {
+                format = Some(match tokens.next() {
+                    Some("ascii") => Format::Ascii,
+                    Some("binary_little_endian") => Format::BinaryLittleEndian,
+                    Some("binary_big_endian") => Format::BinaryBigEndian,
+                    other => {
+                        return Err(error(
+                            number,
+                            format!("unknown format '{}'", other.unwrap_or("")),
+                        ))
+                    }
+                })
+            }
+            "element" => {
+                let name = tokens.next().unwrap_or("");
+                let count = tokens.next().and_then(|count| count.parse().ok());
+                match count {
+                    Some(count) if !name.is_empty() => elements.push(Element {
+                        name: name.to_string(),
+                        count,
+                        properties: Vec::new(),
+                    }),
+                    _ => return Err(error(number, format!("malformed element '{}'", line))),
+                }
+            }
+            "property" => {
+                let parts: Vec<&str> = tokens.collect();
+                let scalar = |name: &str| {
+                    Scalar::from_name(name)
+                        .ok_or_else(|| error(number, format!("unknown property type '{}'", name)))
+                };
+                let (kind, name) = match parts.as_slice() {
+                    ["list", count, item, name] => (
+                        Kind::List {
+                            count: scalar(count)?,
+                            item: scalar(item)?,
+                        },
+                        name,
+                    ),
+                    [kind, name] => (Kind::Scalar(scalar(kind)?), name),
+                    _ => return Err(error(number, format!("malformed property '{}'", line))),
+                };
+                match elements.last_mut() {
+                    Some(element) => element.properties.push(Property {
+                        name: name.to_string(),
+                        kind,
+                    }),
+                    None => return Err(error(number, String::from("property before any element"))),
+                }
+            }
+            "end_header" => break,
+            // comment, obj_info
+            _ => {}
+        }
+    }
+    let format = format.ok_or_else(|| error(number, String::from("header has no format line")))?;
+    Ok((
+        Header {
+            format,
+            elements,
+            line_count: number,
+        },
+        offset,
+    ))
+}
+
+enum Body<'a> {
+    /// One element per line, with `line` the number of the last line read.
+    Ascii {
+        lines: std::str::Lines<'a>,
+        line: usize,
+    },
+    Binary {
+        data: &'a [u8],
+        big_endian: bool,
+    },
+}
+
+impl Body<'_> {
+    fn error(&self, path: &Path, message: String) -> ImportError {
+        match self {
+            Body::Ascii { line, .. } => ImportError::Invalid {
+                path: path.to_path_buf(),
+                line: *line,
+                message,
+            },
+            Body::Binary { .. } => ImportError::Malformed {
+                path: path.to_path_buf(),
+                message,
+            },
+        }
+    }
+
+    fn read_element(
+        &mut self,
+        element: &Element,
+        index: usize,
+        path: &Path,
+    ) -> Result<Vec<Value>, ImportError> {
+        let ends_early = || format!("file ends in {} {}", element.name, index);
+        match self {
+            Body::Ascii { lines, line } => {
+                let text = loop {
+                    *line += 1;
+                    match lines.next() {
+                        Some(text) if text.trim().is_empty() => continue,
+                        Some(text) => break text,
+                        None => return Err(self.error(path, ends_early())),
+                    }
+                };
+                let mut tokens = text.split_whitespace();
+                let mut next = || -> Result<f64, String> {
+                    let token = tokens
+                        .next()
+                        .ok_or_else(|| format!("{} {} has too few values", element.name, index))?;
+                    token
+                        .parse()
+                        .map_err(|_| format!("expected a number, got '{}'", token))
+                };
+                let values = element
+                    .properties
+                    .iter()
+                    .map(|property| match property.kind {
+                        Kind::Scalar(_) => next().map(Value::Scalar),
+                        Kind::List { .. } => {
+                            let count = next()? as usize;
+                            (0..count)
+                                .map(|_| next())
+                                .collect::<Result<_, _>>()
+                                .map(Value::List)
+                        }
+                    })
+                    .collect::<Result<Vec<_>, String>>();
+                values.map_err(|message| self.error(path, message))
+            }
+            Body::Binary { data, big_endian } => {
+                let big_endian = *big_endian;
+                let mut take = |scalar: Scalar| {
+                    if data.len() < scalar.size() {
+                        return None;
+                    }
+                    let (bytes, rest) = data.split_at(scalar.size());
+                    *data = rest;
+                    Some(scalar.decode(bytes, big_endian))
+                };
+                let mut values = Vec::with_capacity(element.properties.len());
+                for property in &element.properties {
+                    let value = match property.kind {
+                        Kind::Scalar(scalar) => take(scalar).map(Value::Scalar),
+                        Kind::List { count, item } => take(count).and_then(|count| {
+                            (0..count as usize)
+                                .map(|_| take(item))
+                                .collect::<Option<_>>()
+                                .map(Value::List)
+                        }),
+                    };
+                    match value {
+                        Some(value) => values.push(value),
+                        None => return Err(self.error(path, ends_early())),
+                    }
+                }
+                Ok(values)
+            }
+        }
+    }
+}
@@ -0,0 +1,367 @@
+#[cfg(test)]
+use crate::framebuffer::{Framebuffer, Tile};
+#[cfg(test)]
+use crate::image::{self, ImageFormat};
+#[cfg(test)]
+use crate::import::{load_obj, load_ply, parse_mtl, ImportError, ObjModel, PlyModel};
+#[cfg(test)]
+use crate::objects::Hittable;
+#[cfg(test)]
+use crate::ray::Ray;
+#[cfg(test)]
+use crate::sampler::Sampler;
+#[cfg(test)]
+use crate::vec3::{Vec3, Vector};
+#[cfg(test)]
+use std::fs;
+#[cfg(test)]
+use std::path::Path;
+
+#[cfg(test)]
+const CUBE: &str = "\
+# A unit cube with quad faces.
+mtllib cube.mtl
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+v 0 0 1
+v 1 0 1
+v 1 1 1
+v 0 1 1
+usemtl red
+f 1 4 3 2
+f 5 6 7 8
+f 1 2 6 5
+usemtl glass
+f 2 3 7 6
+f 3 4 8 7
+f 4 1 5 8
+";
+
+#[cfg(test)]
+fn parse(source: &str) -> Result<ObjModel, ImportError> {
+    ObjModel::parse(source, Path::new("test.obj"))
+}
+
+#[cfg(test)]
+fn error_line(source: &str) -> usize {
+    match parse(source) {
+        Err(ImportError::Invalid { line, .. }) => line,
+        Err(error) => panic!("unexpected error: {}", error),
+        Ok(_) => panic!("expected an error"),
+    }
+}
+
+#[test]
+fn test_parse_cube() {
+    let model = parse(CUBE).unwrap();
+
+    assert!(model.material_libraries == vec![String::from("cube.mtl")]);
+    assert!(model.groups.len() == 2);
+    assert!(model.groups[0].material.as_deref() == Some("red"));
+    let red = &model.groups[0].data;
+    assert!(red.triangles.len() == 6);
+    // Flat faces with no vn lines leave the normals to the mesh.
+    assert!(red.normals.is_empty() && red.uvs.is_empty());
+    assert!(red.positions.len() == 8);
+    assert!(red.triangles[0] == [0, 1, 2] && red.triangles[1] == [0, 2, 3]);
+}
+
+#[test]
+fn test_corner_formats() {
+    let source = "\
+v 0 0 0
+v 1 0 0
+v 0 1 0
+vt 0 0
+vt 1 0
+vt 0 1
+vn 0 0 1
+f 1/1/1 2/2/1 3/3/1
+f -3//-1 -2//-1 -1//-1
+f 1/1 2/2 3/3
+";
+    let model = parse(source).unwrap();
+    let data = &model.groups[0].data;
+
+    assert!(data.triangles.len() == 3);
+    assert!(data.uvs.len() == data.positions.len() && data.normals.len() == data.positions.len());
+    // The first two faces share their corners' normals, but not their texture coordinates.
+    assert!(data.triangles[0] != data.triangles[1]);
+    assert!(data.uvs[data.triangles[0][2] as usize] == [0.0, 1.0]);
+    assert!(data
+        .normals
+        .iter()
+        .all(|normal| *normal == Vec3(0.0, 0.0, 1.0)));
+}
+
+#[test]
+fn test_smoothing_groups() {
+    // Two faces folded along the y axis, first smoothed and then flat.
+    let faces = "\
+v 0 0 0
+v 0 1 0
+v 1 0 1
+v -1 0 1
+f 1 2 3
+f 1 4 2
+";
+    let smooth = parse(&format!("s 1\n{}", faces)).unwrap();
+    let flat = parse(&format!("s 1\ns off\n{}", faces)).unwrap();
+    let smooth = &smooth.groups[0].data;
+    let flat = &flat.groups[0].data;
+
+    // Smoothed faces share the fold's vertices and average their normals there.
+    assert!(smooth.positions.len() == 4);
+    assert!((smooth.normals[0] - Vec3(0.0, 0.0, -1.0)).length() < 1e-6);
+    assert!(flat.positions.len() == 4 && flat.normals.is_empty());
+}
+
+#[test]
+fn test_malformed_lines() {
+    assert!(error_line("v 0 0 0\nv 1 zero 0\n") == 2);
+    assert!(error_line("v 0 0 0\nv 1 0 0\n\nf 1 2\n") == 4);
+    assert!(error_line("v 0 0 0\nf 1 2 3\n") == 2);
+    assert!(error_line("v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1/x 2 3\n") == 4);
+    assert!(error_line("v 0 0 0\nv 1 0 0\nv 0 1 0\nf 0 1 2\n") == 4);
+    assert!(error_line("v 0 0 \\\n 0\nv 1 0 0 \\\n\nf 1 2 3 4") == 5);
+
+    let error = parse("v 0 0 0\nf 1 2 3\n").err().unwrap().to_string();
+    assert!(error == "test.obj:2: vertex index 2 is out of range, there are 1 so far");
+}
+
+#[test]
+fn test_mtl_materials() {
+    let source = "\
+newmtl red
+Kd 0.8 0.1 0.1
+Ka 1 1 1
+map_Kd -s 2 2 1 red.png
+
+newmtl gold
+Kd 0.1 0.1 0.1
+Ks 0.9 0.7 0.3
+Ns 1000
+
+newmtl glass
+Kd 1 1 1
+Ni 1.45
+d 0.2
+";
+    let materials = parse_mtl(source, Path::new("test.mtl")).unwrap();
+    let red = &materials["red"];
+
+    assert!(materials.len() == 3);
+    assert!(red.diffuse == Vec3(0.8, 0.1, 0.1));
+    assert!(red.diffuse_map.as_deref() == Some(Path::new("red.png")));
+    assert!(materials["gold"].shininess == 1000.0);
+    assert!(materials["glass"].dissolve == 0.2);
+
+    match parse_mtl("Kd 1 1 1\n", Path::new("test.mtl")) {
+        Err(ImportError::Invalid { line: 1, .. }) => {}
+        _ => panic!("expected an error for Kd before newmtl"),
+    }
+    match parse_mtl("newmtl a\nNs shiny\n", Path::new("test.mtl")) {
+        Err(ImportError::Invalid { line: 2, .. }) => {}
+        _ => panic!("expected an error for a bad Ns"),
+    }
+}
+
+#[test]
+fn test_load_obj_with_materials() {
+    let directory = std::env::temp_dir().join(format!("raytracing-obj-{}", std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    fs::write(directory.join("cube.obj"), CUBE).unwrap();
+    fs::write(
+        directory.join("cube.mtl"),
+        "newmtl red\nKd 0.8 0.1 0.1\nnewmtl glass\nNi 1.5\nd 0\n",
+    )
+    .unwrap();
+    let meshes = load_obj(&directory.join("cube.obj"));
+    let missing = load_obj(&directory.join("sphere.obj"));
+    fs::remove_dir_all(&directory).unwrap();
+
+    let meshes = meshes.unwrap();
+    assert!(meshes.len() == 2);
+    assert!(
+        meshes
+            .iter()
+            .map(|mesh| mesh.triangle_count())
+            .sum::<usize>()
+            == 12
+    );
+    let ray = Ray::new(Vec3(0.5, 0.5, -2.0), Vec3(0.0, 0.0, 1.0));
+    assert!(meshes[0].hit(&ray, 0.001, f32::MAX).unwrap().distance == 2.0);
+    match missing {
+        Err(ImportError::Io { path, .. }) => assert!(path.ends_with("sphere.obj")),
+        _ => panic!("expected an I/O error"),
+    }
+}
+
+#[test]
+fn test_load_obj_with_texture() {
+    let directory =
+        std::env::temp_dir().join(format!("raytracing-obj-texture-{}", std::process::id()));
+    fs::create_dir_all(directory.join("textures")).unwrap();
+    fs::write(directory.join("cube.obj"), CUBE).unwrap();
+    fs::write(
+        directory.join("cube.mtl"),
+        "newmtl red\nmap_Kd textures/red.png\n",
+    )
+    .unwrap();
+    let mut framebuffer = Framebuffer::new(1, 1);
+    let tile = Tile {
+        x: 0,
+        y: 0,
+        width: 1,
+        height: 1,
+    };
+    framebuffer.write_tile(&tile, &[Vec3(1.0, 0.0, 0.0)]);
+    let texture = directory.join("textures").join("red.png");
+    image::save(&framebuffer, &texture, ImageFormat::Png).unwrap();
+    let meshes = load_obj(&directory.join("cube.obj"));
+    fs::remove_file(&texture).unwrap();
+    let missing = load_obj(&directory.join("cube.obj"));
+    fs::remove_dir_all(&directory).unwrap();
+
+    let meshes = meshes.unwrap();
+    let ray = Ray::new(Vec3(0.5, 0.5, -2.0), Vec3(0.0, 0.0, 1.0));
+    let hit = meshes[0].hit(&ray, 0.001, f32::MAX).unwrap();
+    let bsdf = hit.material.bsdf(&hit).unwrap();
+    let wo = Vec3(0.0, 0.0, 1.0);
+    let sample = bsdf.sample(&wo, &mut Sampler::new(1)).unwrap();
+    assert!(sample.weight == Vec3(1.0, 0.0, 0.0));
+    match missing {
+        Err(ImportError::Io { path, .. }) => assert!(path.ends_with("textures/red.png")),
+        _ => panic!("expected the texture to be missing"),
+    }
+}
+
+#[cfg(test)]
+const PLY_SQUARE: &str = "\
+ply
+format ascii 1.0
+comment A unit square in one quad face, with an edge element to skip.
+element vertex 4
+property float x
+property float y
+property float z
+property float nx
+property float ny
+property float nz
+element edge 1
+property int vertex1
+property int vertex2
+element face 1
+property list uchar int vertex_indices
+end_header
+0 0 0 0 0 1
+1 0 0 0 0 1
+1 1 0 0 0 1
+0 1 0 0 0 1
+0 1
+4 0 1 2 3
+";
+
+#[cfg(test)]
+fn parse_ply(bytes: &[u8]) -> Result<PlyModel, ImportError> {
+    PlyModel::parse(bytes, Path::new("test.ply"))
+}
+
+#[cfg(test)]
+fn binary_points(big_endian: bool) -> Vec<u8> {
+    let format = if big_endian {
+        "binary_big_endian"
+    } else {
+        "binary_little_endian"
+    };
+    let mut bytes = format!(
+        "ply\nformat {} 1.0\nelement vertex 2\nproperty float x\nproperty float y\n\
+         property double z\nproperty uchar red\nproperty uchar green\nproperty uchar blue\n\
+         end_header\n",
+        format
+    )
+    .into_bytes();
+    let points: [(f32, f32, f64, [u8; 3]); 2] = [
+        (1.0, 2.0, 3.0, [255, 0, 51]),
+        (-1.0, 0.5, 0.25, [0, 255, 102]),
+    ];
+    for &(x, y, z, color) in &points {
+        if big_endian {
+            bytes.extend(&x.to_be_bytes());
+            bytes.extend(&y.to_be_bytes());
+            bytes.extend(&z.to_be_bytes());
+        } else {
+            bytes.extend(&x.to_le_bytes());
+            bytes.extend(&y.to_le_bytes());
+            bytes.extend(&z.to_le_bytes());
+        }
+        bytes.extend(&color);
+    }
+    bytes
+}
+
+#[test]
+fn test_ply_ascii_mesh() {
+    let model = parse_ply(PLY_SQUARE.as_bytes()).unwrap();
+
+    assert!(model.mesh.positions.len() == 4 && model.mesh.normals.len() == 4);
+    assert!(model.mesh.triangles == vec![[0, 1, 2], [0, 2, 3]]);
+    assert!(model.mesh.positions[2] == Vec3(1.0, 1.0, 0.0));
+    assert!(model.colors.is_empty() && model.mesh.uvs.is_empty());
+}
+
+#[test]
+fn test_ply_binary_points() {
+    for &big_endian in &[false, true] {
+        let model = parse_ply(&binary_points(big_endian)).unwrap();
+
+        assert!(model.mesh.triangles.is_empty());
+        assert!(model.mesh.positions[0] == Vec3(1.0, 2.0, 3.0));
+        assert!(model.mesh.positions[1] == Vec3(-1.0, 0.5, 0.25));
+        assert!((model.colors[0] - Vec3(1.0, 0.0, 0.2)).length() < 1e-6);
+        assert!((model.colors[1] - Vec3(0.0, 1.0, 0.4)).length() < 1e-6);
+    }
+}
+
+#[test]
+fn test_ply_errors() {
+    let truncated = binary_points(false);
+    match parse_ply(&truncated[..truncated.len() - 4]) {
+        Err(ImportError::Malformed { message, .. }) => assert!(message == "file ends in vertex 1"),
+        _ => panic!("expected a truncated file"),
+    }
+    let bad_type = PLY_SQUARE.replace("property float nx", "property quad nx");
+    match parse_ply(bad_type.as_bytes()) {
+        Err(ImportError::Invalid { line: 8, .. }) => {}
+        _ => panic!("expected an unknown property type"),
+    }
+    let bad_value = PLY_SQUARE.replace("1 1 0 0 0 1", "1 one 0 0 0 1");
+    match parse_ply(bad_value.as_bytes()) {
+        Err(ImportError::Invalid { line: 19, .. }) => {}
+        _ => panic!("expected a bad vertex"),
+    }
+    let bad_index = PLY_SQUARE.replace("4 0 1 2 3", "4 0 1 2 4");
+    assert!(parse_ply(bad_index.as_bytes()).is_err());
+    assert!(parse_ply(b"OBJ\n").is_err());
+}
+
+#[test]
+fn test_load_ply() {
+    let directory = std::env::temp_dir().join(format!("raytracing-ply-{}", std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    fs::write(directory.join("square.ply"), PLY_SQUARE).unwrap();
+    fs::write(directory.join("points.ply"), binary_points(true)).unwrap();
+    let square = load_ply(&directory.join("square.ply"), 0.1);
+    let points = load_ply(&directory.join("points.ply"), 0.1);
+    fs::remove_dir_all(&directory).unwrap();
+
+    let square = square.unwrap();
+    let points = points.unwrap();
+    assert!(square.len() == 1 && points.len() == 2);
+    let ray = Ray::new(Vec3(0.5, 0.5, 1.0), Vec3(0.0, 0.0, -1.0));
+    assert!(square[0].hit(&ray, 0.001, f32::MAX).unwrap().distance == 1.0);
+    let bounding_box = points[0].bounding_box();
+    assert!((bounding_box.max - Vec3(1.1, 2.1, 3.1)).length() < 1e-6);
+}
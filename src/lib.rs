@@ -0,0 +1,72 @@
+//! A path tracer for scenes of spheres, moving spheres, triangle meshes, quads, boxes, disks
+//! and planes, placed directly or as transformed instances, with a tiled multi-threaded
+//! renderer and PNG, PPM, OpenEXR, Radiance HDR and PFM output.
+//!
+//! ```
+//! use raytracing::background::Gradient;
+//! use raytracing::{Camera, Lambertian, RenderSettings, Renderer, Scene, Sphere, Vec3};
+//!
+//! let scene = Scene {
+//!     camera: Camera::new(
+//!         Vec3(0.0, 0.0, 0.0),
+//!         Vec3(0.0, 0.0, -1.0),
+//!         Vec3(0.0, 1.0, 0.0),
+//!         90.0,
+//!         2.0,
+//!         0.0,
+//!         1.0,
+//!     ),
+//!     objects: vec![Box::new(Sphere {
+//!         center: Vec3(0.0, 0.0, -1.0),
+//!         radius: 0.5,
+//!         material: Box::new(Lambertian::new(Vec3(0.5, 0.5, 0.5))),
+//!     })],
+//!     lights: Vec::new(),
+//!     background: Box::new(Gradient::default()),
+//! };
+//! let settings = RenderSettings {
+//!     width: 20,
+//!     height: 10,
+//!     samples: 4,
+//!     ..RenderSettings::default()
+//! };
+//! let framebuffer = Renderer::new(settings).render_scene(scene);
+//! assert_eq!(framebuffer.pixels().len(), 200);
+//! ```
+
+pub mod aabb;
+pub mod background;
+pub mod bvh;
+pub mod camera;
+pub mod framebuffer;
+pub mod image;
+pub mod import;
+pub mod lights;
+pub mod materials;
+pub mod objects;
+pub mod ray;
+pub mod render;
+pub mod sampler;
+pub mod scenes;
+pub mod textures;
+pub mod vec3;
+
+pub use background::{Background, EnvironmentMap, PhysicalSky};
+pub use camera::Camera;
+pub use framebuffer::Framebuffer;
+pub use lights::{DirectionalLight, Light, PointLight, SpotLight};
+pub use materials::{
+    Bsdf, BsdfFlags, BsdfSample, Dielectric, DiffuseLight, Lambertian, Material, Metal,
+};
+pub use objects::{
+    Cuboid, Disk, HitRecord, Hittable, HittableList, MeshData, MovingSphere, Plane, Quad, Sphere,
+    Transformed, Triangle, TriangleMesh,
+};
+pub use ray::Ray;
+pub use render::{RenderSettings, Renderer};
+pub use sampler::Sampler;
+pub use scenes::Scene;
+pub use textures::{
+    Checker, ColorRamp, ImageTexture, NoisePattern, NoiseTexture, Perlin, Texture, WrapMode,
+};
+pub use vec3::{Matrix4, Vec3, Vector};
@@ -0,0 +1,143 @@
+mod tests;
+use crate::vec3::{Vec3, Vector};
+
+/// A light with no size, shining from a single point or from a single direction.
+///
+/// Paths can never hit such a light, so it isn't part of the world: instead, every hit whose
+/// material can be evaluated checks each light with a shadow ray. Shared between render
+/// threads, so implementors must be `Send + Sync`.
+pub trait Light: Send + Sync {
+    /// The light reaching `point`, ignoring anything in the way, or `None` if the light
+    /// doesn't shine on it at all.
+    fn illuminate(&self, point: &Vec3) -> Option<LightSample>;
+}
+
+/// Light from a `Light` reaching a point.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct LightSample {
+    /// Unit direction from the point towards the light.
+    pub direction: Vec3,
+    /// How far along `direction` the light is. Infinite for directional lights.
+    pub distance: f32,
+    /// The irradiance on a surface facing the light.
+    pub irradiance: Vec3,
+}
+
+/// Shines equally in every direction from a point, falling off with the square of the
+/// distance.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct PointLight {
+    pub position: Vec3,
+    pub color: Vec3,
+    /// Radiant intensity, per unit solid angle: the irradiance at a distance of 1.
+    pub intensity: f32,
+}
+
+impl Light for PointLight {
+    fn illuminate(&self, point: &Vec3) -> Option<LightSample> {
+        shine_from(&self.position, point, self.intensity * self.color)
+    }
+}
+
+/// A point light that only shines into a cone, fading out towards its edge.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SpotLight {
+    position: Vec3,
+    /// Unit direction the cone points in.
+    direction: Vec3,
+    color: Vec3,
+    intensity: f32,
+    /// Cosines of the angles from the axis where the light starts to fade, and where it's
+    /// gone.
+    cos_inner: f32,
+    cos_outer: f32,
+}
+
+impl SpotLight {
+    /// A spot at `position` pointing towards `target`, lighting a cone `angle` degrees either
+    /// side of its axis. The last `falloff` degrees of the cone fade smoothly to dark.
+    /// `intensity` is as for `PointLight`, along the axis.
+    pub fn new(
+        position: Vec3,
+        target: Vec3,
+        color: Vec3,
+        intensity: f32,
+        angle: f32,
+        falloff: f32,
+    ) -> Self {
+        let angle = angle.clamp(0.0, 180.0);
+        SpotLight {
+            position,
+            direction: (target - position).unit_vector(),
+            color,
+            intensity,
+            cos_inner: (angle - falloff.clamp(0.0, angle)).to_radians().cos(),
+            cos_outer: angle.to_radians().cos(),
+        }
+    }
+
+    /// How much of the light reaches `direction`, a unit vector from the light: 1 inside the
+    /// inner cone, 0 outside the outer one, and a smooth step in between.
+    fn falloff(&self, direction: &Vec3) -> f32 {
+        let cos = Vec3::dot(direction, &self.direction);
+        if cos >= self.cos_inner {
+            return 1.0;
+        }
+        let t = ((cos - self.cos_outer) / (self.cos_inner - self.cos_outer)).clamp(0.0, 1.0);
+        t * t * (3.0 - 2.0 * t)
+    }
+}
+
+impl Light for SpotLight {
+    fn illuminate(&self, point: &Vec3) -> Option<LightSample> {
+        let sample = shine_from(&self.position, point, self.intensity * self.color)?;
+        let falloff = self.falloff(&-sample.direction);
+        if falloff <= 0.0 {
+            return None;
+        }
+        Some(LightSample {
+            irradiance: falloff * sample.irradiance,
+            ..sample
+        })
+    }
+}
+
+/// Parallel light from infinitely far away, like sunlight, the same everywhere.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct DirectionalLight {
+    /// The way the light travels, which needn't be of unit length.
+    pub direction: Vec3,
+    pub color: Vec3,
+    /// The irradiance on a surface facing the light.
+    pub intensity: f32,
+}
+
+impl Light for DirectionalLight {
+    #[allow(unused_variables)]
+    fn illuminate(&self, point: &Vec3) -> Option<LightSample> {
+        if self.direction.squared_length() == 0.0 {
+            return None;
+        }
+        Some(LightSample {
+            direction: -self.direction.unit_vector(),
+            distance: f32::INFINITY,
+            irradiance: self.intensity * self.color,
+        })
+    }
+}
+
+/// Light with radiant intensity `intensity` from `position`, reaching `point` with the
+/// inverse square falloff.
+fn shine_from(position: &Vec3, point: &Vec3, intensity: Vec3) -> Option<LightSample> {
+    let offset = position - point;
+    let squared_distance = offset.squared_length();
+    if squared_distance == 0.0 {
+        return None;
+    }
+    let distance = squared_distance.sqrt();
+    Some(LightSample {
+        direction: offset / distance,
+        distance,
+        irradiance: intensity / squared_distance,
+    })
+}
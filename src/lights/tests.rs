@@ -0,0 +1,70 @@
+#[cfg(test)]
+use crate::lights::{DirectionalLight, Light, PointLight, SpotLight};
+#[cfg(test)]
+use crate::vec3::{Vec3, Vector};
+
+#[cfg(test)]
+fn close(a: Vec3, b: Vec3) -> bool {
+    (a - b).length() < 1e-5
+}
+
+#[test]
+fn test_point_light() {
+    let light = PointLight {
+        position: Vec3(0.0, 2.0, 0.0),
+        color: Vec3(1.0, 0.5, 0.25),
+        intensity: 8.0,
+    };
+    let sample = light.illuminate(&Vec3(0.0, 0.0, 0.0)).unwrap();
+
+    assert!(close(sample.direction, Vec3(0.0, 1.0, 0.0)));
+    assert!(sample.distance == 2.0);
+    // The inverse square law.
+    assert!(close(sample.irradiance, Vec3(2.0, 1.0, 0.5)));
+    let far = light.illuminate(&Vec3(0.0, -2.0, 0.0)).unwrap();
+    assert!(close(far.irradiance, Vec3(0.5, 0.25, 0.125)));
+    assert!(light.illuminate(&Vec3(0.0, 2.0, 0.0)).is_none());
+}
+
+#[test]
+fn test_spot_light() {
+    // Pointing straight down, with a cone 30 degrees wide whose outer 10 degrees fade.
+    let light = SpotLight::new(
+        Vec3(0.0, 1.0, 0.0),
+        Vec3(0.0, 0.0, 0.0),
+        Vec3(1.0, 1.0, 1.0),
+        1.0,
+        30.0,
+        10.0,
+    );
+    let at_angle = |degrees: f32| {
+        let radians = degrees.to_radians();
+        let point = Vec3(radians.tan(), 0.0, 0.0);
+        light
+            .illuminate(&point)
+            .map(|sample| sample.irradiance.0 * (point - Vec3(0.0, 1.0, 0.0)).squared_length())
+    };
+
+    assert!((at_angle(0.0).unwrap() - 1.0).abs() < 1e-5);
+    assert!((at_angle(19.0).unwrap() - 1.0).abs() < 1e-5);
+    // The fade is a smooth step in the cosine of the angle, so about a half halfway through.
+    assert!((at_angle(25.0).unwrap() - 0.5).abs() < 0.1);
+    assert!(at_angle(29.0).unwrap() < at_angle(27.0).unwrap());
+    assert!(at_angle(31.0).is_none());
+    assert!(light.illuminate(&Vec3(0.0, 2.0, 0.0)).is_none());
+}
+
+#[test]
+fn test_directional_light() {
+    let light = DirectionalLight {
+        direction: Vec3(0.0, -3.0, 0.0),
+        color: Vec3(1.0, 1.0, 1.0),
+        intensity: 2.0,
+    };
+    for point in &[Vec3(0.0, 0.0, 0.0), Vec3(100.0, -50.0, 3.0)] {
+        let sample = light.illuminate(point).unwrap();
+        assert!(close(sample.direction, Vec3(0.0, 1.0, 0.0)));
+        assert!(sample.distance == f32::INFINITY);
+        assert!(sample.irradiance == Vec3(2.0, 2.0, 2.0));
+    }
+}
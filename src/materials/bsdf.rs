@@ -0,0 +1,108 @@
+use crate::sampler::Sampler;
+use crate::vec3::{orthonormal_basis, Vec3, Vector};
+use std::ops::BitOr;
+
+/// How a surface scatters light at one point, in the local shading frame of that point.
+///
+/// Directions are unit vectors in `Frame` coordinates, where the surface normal is +z. Both
+/// point away from the surface: `wo` back along the ray that arrived, towards the eye, and `wi`
+/// towards where the light comes from. Either may lie below the surface, which is how rays
+/// from behind and refraction show up.
+pub trait Bsdf {
+    /// The kinds of lobe the BSDF has.
+    fn flags(&self) -> BsdfFlags;
+
+    /// Picks a direction for light to arrive from, in proportion to how much of it would leave
+    /// along `wo`. `None` if the sample is lost, absorbed by the surface.
+    fn sample(&self, wo: &Vec3, sampler: &mut Sampler) -> Option<BsdfSample>;
+
+    /// The fraction of light arriving from `wi` that leaves along `wo`, cosine term included.
+    /// Always zero for specular lobes, which only ever scatter into directions they pick.
+    fn eval(&self, wo: &Vec3, wi: &Vec3) -> Vec3;
+
+    /// The probability density, per unit solid angle, of `sample` picking `wi`. Zero for
+    /// specular lobes.
+    fn pdf(&self, wo: &Vec3, wi: &Vec3) -> f32;
+}
+
+/// A direction picked by `Bsdf::sample`.
+#[derive(Copy, Clone, Debug)]
+pub struct BsdfSample {
+    /// Unit direction in the shading frame.
+    pub direction: Vec3,
+    /// `eval` over `pdf` for the sampled direction: what the light arriving from it is scaled
+    /// by. For specular lobes, whose `eval` and `pdf` are both infinite, the ratio on its own.
+    pub weight: Vec3,
+    /// The density of the sample, or zero if it came from a specular lobe.
+    pub pdf: f32,
+    /// The kind of lobe the sample came from.
+    pub flags: BsdfFlags,
+}
+
+/// A set of BSDF lobe kinds.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct BsdfFlags(u8);
+
+impl BsdfFlags {
+    /// Scatters to the side of the surface the light came from.
+    pub const REFLECTION: Self = BsdfFlags(1);
+    /// Scatters through the surface.
+    pub const TRANSMISSION: Self = BsdfFlags(1 << 1);
+    /// Spreads light over the whole hemisphere.
+    pub const DIFFUSE: Self = BsdfFlags(1 << 2);
+    /// Spreads light around a preferred direction.
+    pub const GLOSSY: Self = BsdfFlags(1 << 3);
+    /// Sends light into a single direction (a delta distribution), like a mirror or clear glass.
+    pub const SPECULAR: Self = BsdfFlags(1 << 4);
+
+    pub fn contains(self, other: BsdfFlags) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Whether every lobe is specular, so that there is nothing for light sampling to
+    /// evaluate.
+    pub fn is_delta(self) -> bool {
+        let spread = (BsdfFlags::DIFFUSE | BsdfFlags::GLOSSY).0;
+        self.contains(BsdfFlags::SPECULAR) && self.0 & spread == 0
+    }
+}
+
+impl BitOr for BsdfFlags {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        BsdfFlags(self.0 | other.0)
+    }
+}
+
+/// An orthonormal basis around a surface normal, which becomes +z in local coordinates.
+#[derive(Copy, Clone, Debug)]
+pub struct Frame {
+    pub tangent: Vec3,
+    pub bitangent: Vec3,
+    pub normal: Vec3,
+}
+
+impl Frame {
+    /// `normal` must be of unit length.
+    pub fn new(normal: &Vec3) -> Self {
+        let (tangent, bitangent) = orthonormal_basis(normal);
+        Frame {
+            tangent,
+            bitangent,
+            normal: *normal,
+        }
+    }
+
+    pub fn to_local(&self, vector: &Vec3) -> Vec3 {
+        Vec3(
+            Vec3::dot(vector, &self.tangent),
+            Vec3::dot(vector, &self.bitangent),
+            Vec3::dot(vector, &self.normal),
+        )
+    }
+
+    pub fn to_world(&self, vector: &Vec3) -> Vec3 {
+        vector.x() * self.tangent + vector.y() * self.bitangent + vector.z() * self.normal
+    }
+}
@@ -0,0 +1,346 @@
+mod bsdf;
+mod tests;
+use crate::objects::*;
+use crate::ray::Ray;
+use crate::sampler::Sampler;
+use crate::textures::Texture;
+use crate::vec3::{Vec3, Vector};
+pub use bsdf::{Bsdf, BsdfFlags, BsdfSample, Frame};
+use rand::prelude::*;
+use std::f32;
+
+/// What a surface is made of: how it scatters light, and what light it gives off. Shared
+/// between render threads, so implementors must be `Send + Sync`.
+pub trait Material: Send + Sync {
+    /// How the surface scatters light at the hit, built for each hit so that it can vary over
+    /// the surface. `None` for surfaces that scatter no light at all.
+    fn bsdf(&self, hit_record: &HitRecord) -> Option<Box<dyn Bsdf + '_>>;
+
+    /// Light given off at the hit point towards where `ray` came from. Most materials give
+    /// off none.
+    #[allow(unused_variables)]
+    fn emitted(&self, ray: &Ray, hit_record: &HitRecord) -> Vec3 {
+        Vec3(0.0, 0.0, 0.0)
+    }
+
+    /// Whether surfaces of this material give off light, and so are worth sampling directly.
+    fn is_emissive(&self) -> bool {
+        false
+    }
+}
+
+/// The side of the surface `wo` is on, as the sign of the local z axis there. Surfaces that
+/// look the same from both sides do their work in that hemisphere.
+fn side(wo: &Vec3) -> f32 {
+    if wo.z() < 0.0 {
+        -1.0
+    } else {
+        1.0
+    }
+}
+
+trait Reflect {
+    fn reflect(vector: &Vec3, unit_vector: &Vec3) -> Vec3 {
+        vector - &(2.0 * Vec3::dot(vector, unit_vector) * unit_vector)
+    }
+}
+
+pub struct Lambertian {
+    /*
+     * Albedo is the measure of the diffuse reflection of solar radiation out of the
+     * total solar radiation received by an astronomical body. It is dimensionless
+     * and measured on a scale from 0 to 1.
+     */
+    albedo: Box<dyn Texture>,
+}
+
+impl Lambertian {
+    /// `albedo` is a `Vec3` for a uniform color, or any other texture.
+    pub fn new(albedo: impl Texture + 'static) -> Self {
+        Lambertian {
+            albedo: Box::new(albedo),
+        }
+    }
+}
+
+impl Material for Lambertian {
+    fn bsdf(&self, hit_record: &HitRecord) -> Option<Box<dyn Bsdf + '_>> {
+        Some(Box::new(LambertianBsdf {
+            albedo: texture_at(&*self.albedo, hit_record),
+            frame: Frame::new(&hit_record.normal),
+        }))
+    }
+}
+
+/// A Lambertian surface at one point, where its albedo is known.
+#[derive(Copy, Clone)]
+struct LambertianBsdf {
+    albedo: Vec3,
+    /// The shading frame at the point, which the renderer builds the same way.
+    frame: Frame,
+}
+
+impl Bsdf for LambertianBsdf {
+    fn flags(&self) -> BsdfFlags {
+        BsdfFlags::DIFFUSE | BsdfFlags::REFLECTION
+    }
+
+    fn sample(&self, wo: &Vec3, sampler: &mut Sampler) -> Option<BsdfSample> {
+        let normal = Vec3(0.0, 0.0, side(wo));
+        // A normal plus a point on the unit sphere gives directions distributed by their cosine
+        // with the normal, which is what `eval` weighs light by. The point is picked in world
+        // space, as it was before scattering moved into the shading frame, so that a seed
+        // still renders the same image.
+        let offset = random_in_unit_sphere(sampler).unit_vector();
+        let mut direction = normal + self.frame.to_local(&offset);
+        if direction.squared_length() < 1e-8 {
+            direction = normal;
+        }
+        let direction = direction.unit_vector();
+        Some(BsdfSample {
+            direction,
+            weight: self.albedo,
+            pdf: self.pdf(wo, &direction),
+            flags: self.flags(),
+        })
+    }
+
+    fn eval(&self, wo: &Vec3, wi: &Vec3) -> Vec3 {
+        self.albedo * self.pdf(wo, wi)
+    }
+
+    fn pdf(&self, wo: &Vec3, wi: &Vec3) -> f32 {
+        (wi.z() * side(wo)).max(0.0) * f32::consts::FRAC_1_PI
+    }
+}
+
+pub struct Metal {
+    albedo: Box<dyn Texture>,
+    fuzziness: f32,
+}
+
+impl Metal {
+    /// `albedo` is a `Vec3` for a uniform color, or any other texture.
+    pub fn new(albedo: impl Texture + 'static, fuzziness: f32) -> Self {
+        let f = if fuzziness < 1.0 { fuzziness } else { 1.0 };
+        Metal {
+            albedo: Box::new(albedo),
+            fuzziness: f,
+        }
+    }
+}
+
+impl Material for Metal {
+    fn bsdf(&self, hit_record: &HitRecord) -> Option<Box<dyn Bsdf + '_>> {
+        Some(Box::new(MetalBsdf {
+            albedo: texture_at(&*self.albedo, hit_record),
+            fuzziness: self.fuzziness,
+            frame: Frame::new(&hit_record.normal),
+        }))
+    }
+}
+
+/// A metal surface at one point, where its albedo is known.
+#[derive(Copy, Clone)]
+struct MetalBsdf {
+    albedo: Vec3,
+    fuzziness: f32,
+    /// As in `LambertianBsdf`.
+    frame: Frame,
+}
+
+impl Reflect for MetalBsdf {}
+
+impl Bsdf for MetalBsdf {
+    /// Specular for a perfect mirror, which only ever reflects into one direction.
+    fn flags(&self) -> BsdfFlags {
+        if self.fuzziness == 0.0 {
+            BsdfFlags::SPECULAR | BsdfFlags::REFLECTION
+        } else {
+            BsdfFlags::GLOSSY | BsdfFlags::REFLECTION
+        }
+    }
+
+    fn sample(&self, wo: &Vec3, sampler: &mut Sampler) -> Option<BsdfSample> {
+        let reflected = Self::reflect(&-wo, &Vec3(0.0, 0.0, side(wo)));
+        // Fuzzed in world space, like `LambertianBsdf::sample`.
+        let offset = self.frame.to_local(&random_in_unit_sphere(sampler));
+        let scattered = reflected + self.fuzziness * offset;
+        if scattered.z() * side(wo) <= 0.0 {
+            return None;
+        }
+        let direction = scattered.unit_vector();
+        Some(BsdfSample {
+            direction,
+            weight: self.albedo,
+            pdf: self.pdf(wo, &direction),
+            flags: self.flags(),
+        })
+    }
+
+    fn eval(&self, wo: &Vec3, wi: &Vec3) -> Vec3 {
+        if wi.z() * side(wo) <= 0.0 {
+            return Vec3(0.0, 0.0, 0.0);
+        }
+        self.albedo * self.pdf(wo, wi)
+    }
+
+    /// `sample` picks a point uniformly in a ball of radius `fuzziness` around the tip of the
+    /// unit mirror direction. The density of a direction is then the part of the ball's volume
+    /// along it, found from where a ray that way enters and leaves the ball.
+    fn pdf(&self, wo: &Vec3, wi: &Vec3) -> f32 {
+        if self.fuzziness == 0.0 {
+            return 0.0;
+        }
+        let reflected = Self::reflect(&-wo, &Vec3(0.0, 0.0, side(wo)));
+        let cosine = Vec3::dot(&wi.unit_vector(), &reflected);
+        let discriminant = cosine * cosine - 1.0 + self.fuzziness * self.fuzziness;
+        if discriminant <= 0.0 {
+            return 0.0;
+        }
+        let exit = cosine + discriminant.sqrt();
+        let entry = (cosine - discriminant.sqrt()).max(0.0);
+        if exit <= 0.0 {
+            return 0.0;
+        }
+        (exit.powi(3) - entry.powi(3)) / (4.0 * f32::consts::PI * self.fuzziness.powi(3))
+    }
+}
+
+trait Refract {
+    fn refract(vector: &Vec3, vector2: &Vec3, refractive_index: f32) -> Option<Vec3> {
+        let unit_vector = vector.unit_vector();
+        let dt = Vec3::dot(&unit_vector, vector2);
+        let discriminant = 1.0 - refractive_index * refractive_index * (1.0 - dt * dt);
+        if discriminant > 0.0 {
+            let refracted =
+                refractive_index * (unit_vector - dt * vector2) - discriminant.sqrt() * vector2;
+            return Some(refracted);
+        }
+        None
+    }
+}
+
+#[derive(Copy, Clone)]
+pub struct Dielectric {
+    refractive_index: f32,
+}
+
+impl Dielectric {
+    pub fn new(refractive_index: f32) -> Self {
+        Self { refractive_index }
+    }
+
+    fn schlick(cosine: f32, refractive_index: f32) -> f32 {
+        let mut r0 = (1.0 - refractive_index) / (1.0 + refractive_index);
+        r0 = r0 * r0;
+        r0 + (1.0 - r0) * (1.0 - cosine).powi(5)
+    }
+}
+
+impl Refract for Dielectric {}
+impl Reflect for Dielectric {}
+impl Material for Dielectric {
+    #[allow(unused_variables)]
+    fn bsdf(&self, hit_record: &HitRecord) -> Option<Box<dyn Bsdf + '_>> {
+        Some(Box::new(*self))
+    }
+}
+
+impl Bsdf for Dielectric {
+    fn flags(&self) -> BsdfFlags {
+        BsdfFlags::SPECULAR | BsdfFlags::REFLECTION | BsdfFlags::TRANSMISSION
+    }
+
+    /// Reflects or refracts, picking between them by the Fresnel reflectance.
+    fn sample(&self, wo: &Vec3, sampler: &mut Sampler) -> Option<BsdfSample> {
+        let direction = -wo;
+        let normal = Vec3(0.0, 0.0, 1.0);
+        let outward_normal: Vec3;
+        let refractive_index: f32;
+        let mut cosine: f32;
+        if direction.z() > 0.0 {
+            outward_normal = -normal;
+            refractive_index = self.refractive_index;
+            cosine = direction.z();
+            cosine = 1.0 - self.refractive_index * self.refractive_index * (1.0 - cosine * cosine);
+        } else {
+            outward_normal = normal;
+            refractive_index = 1.0 / self.refractive_index;
+            cosine = -direction.z();
+        }
+
+        let mut reflect_probability = 1.0;
+        let refracted = Self::refract(&direction, &outward_normal, refractive_index);
+        if refracted.is_some() {
+            reflect_probability = Self::schlick(cosine, refractive_index);
+        }
+
+        let random_num: f32 = sampler.gen();
+        let (scattered, lobe) = if random_num < reflect_probability {
+            (Self::reflect(&direction, &normal), BsdfFlags::REFLECTION)
+        } else {
+            (refracted.unwrap(), BsdfFlags::TRANSMISSION)
+        };
+        Some(BsdfSample {
+            direction: scattered.unit_vector(),
+            weight: Vec3(1.0, 1.0, 1.0),
+            pdf: 0.0,
+            flags: BsdfFlags::SPECULAR | lobe,
+        })
+    }
+
+    #[allow(unused_variables)]
+    fn eval(&self, wo: &Vec3, wi: &Vec3) -> Vec3 {
+        Vec3(0.0, 0.0, 0.0)
+    }
+
+    #[allow(unused_variables)]
+    fn pdf(&self, wo: &Vec3, wi: &Vec3) -> f32 {
+        0.0
+    }
+}
+
+/// A surface that gives off the same light in every direction, from both of its sides, and
+/// reflects none.
+pub struct DiffuseLight {
+    emit: Box<dyn Texture>,
+}
+
+impl DiffuseLight {
+    /// `emit` is a `Vec3` for a uniform color, or any other texture.
+    pub fn new(emit: impl Texture + 'static) -> Self {
+        DiffuseLight {
+            emit: Box::new(emit),
+        }
+    }
+}
+
+impl Material for DiffuseLight {
+    #[allow(unused_variables)]
+    fn bsdf(&self, hit_record: &HitRecord) -> Option<Box<dyn Bsdf + '_>> {
+        None
+    }
+
+    #[allow(unused_variables)]
+    fn emitted(&self, ray: &Ray, hit_record: &HitRecord) -> Vec3 {
+        texture_at(&*self.emit, hit_record)
+    }
+
+    fn is_emissive(&self) -> bool {
+        true
+    }
+}
+
+fn texture_at(texture: &dyn Texture, hit_record: &HitRecord) -> Vec3 {
+    texture.value(hit_record.u, hit_record.v, &hit_record.point)
+}
+
+fn random_in_unit_sphere(sampler: &mut Sampler) -> Vec3 {
+    let mut point: Vec3;
+    while {
+        point = 2.0 * Vec3(sampler.gen(), sampler.gen(), sampler.gen()) - Vec3(1.0, 1.0, 1.0);
+        point.squared_length() >= 1.0
+    } {}
+    point
+}
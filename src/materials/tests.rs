@@ -0,0 +1,171 @@
+#[cfg(test)]
+use crate::materials::{
+    Bsdf, BsdfFlags, Dielectric, DiffuseLight, Frame, Lambertian, LambertianBsdf, Material,
+    MetalBsdf,
+};
+#[cfg(test)]
+use crate::objects::HitRecord;
+#[cfg(test)]
+use crate::sampler::Sampler;
+#[cfg(test)]
+use crate::textures::Checker;
+#[cfg(test)]
+use crate::vec3::{Vec3, Vector};
+#[cfg(test)]
+use rand::prelude::*;
+
+/// Light leaving at 45 degrees to the normal, in the local frame.
+#[cfg(test)]
+fn outgoing() -> Vec3 {
+    Vec3(-1.0, 0.0, 1.0).unit_vector()
+}
+
+#[cfg(test)]
+fn uniform_direction(sampler: &mut Sampler) -> Vec3 {
+    let z = 1.0 - 2.0 * sampler.gen::<f32>();
+    let phi = 2.0 * std::f32::consts::PI * sampler.gen::<f32>();
+    let radius = (1.0 - z * z).sqrt();
+    Vec3(radius * phi.cos(), radius * phi.sin(), z)
+}
+
+#[test]
+fn test_pdf_matches_sample() {
+    let bsdfs: Vec<Box<dyn Bsdf>> = vec![
+        Box::new(LambertianBsdf {
+            albedo: Vec3(0.5, 0.6, 0.7),
+            frame: Frame::new(&Vec3(0.6, 0.0, 0.8)),
+        }),
+        Box::new(MetalBsdf {
+            albedo: Vec3(0.9, 0.8, 0.7),
+            fuzziness: 0.5,
+            frame: Frame::new(&Vec3(0.6, 0.0, 0.8)),
+        }),
+        Box::new(MetalBsdf {
+            albedo: Vec3(0.9, 0.8, 0.7),
+            fuzziness: 1.0,
+            frame: Frame::new(&Vec3(0.6, 0.0, 0.8)),
+        }),
+    ];
+    let mut sampler = Sampler::new(3);
+
+    for bsdf in bsdfs.iter() {
+        // From either side, sampled directions have a density, and eval over pdf is the
+        // sample's weight.
+        for wo in &[outgoing(), -outgoing()] {
+            for _ in 0..100 {
+                let sample = match bsdf.sample(wo, &mut sampler) {
+                    Some(sample) => sample,
+                    None => continue,
+                };
+                let pdf = bsdf.pdf(wo, &sample.direction);
+                assert!(pdf > 0.0 && pdf == sample.pdf);
+                assert!(sample.direction.z() * wo.z() > 0.0);
+                assert!((bsdf.eval(wo, &sample.direction) / pdf - sample.weight).length() < 1e-4);
+            }
+        }
+        // The density integrates to one over the sphere of directions.
+        let count = 40_000;
+        let total: f32 = (0..count)
+            .map(|_| bsdf.pdf(&outgoing(), &uniform_direction(&mut sampler)))
+            .sum();
+        let integral = total / count as f32 * 4.0 * std::f32::consts::PI;
+        assert!((integral - 1.0).abs() < 0.05, "integral {}", integral);
+    }
+}
+
+#[test]
+fn test_specular_flags() {
+    let rough = MetalBsdf {
+        albedo: Vec3(0.9, 0.9, 0.9),
+        fuzziness: 0.3,
+        frame: Frame::new(&Vec3(0.0, 0.0, 1.0)),
+    };
+    let mirror = MetalBsdf {
+        albedo: Vec3(0.9, 0.9, 0.9),
+        fuzziness: 0.0,
+        frame: Frame::new(&Vec3(0.0, 0.0, 1.0)),
+    };
+    let glass = Dielectric::new(1.5);
+
+    assert!(!rough.flags().is_delta() && rough.flags().contains(BsdfFlags::GLOSSY));
+    assert!(mirror.flags().is_delta());
+    assert!(glass.flags().is_delta() && glass.flags().contains(BsdfFlags::TRANSMISSION));
+    // A mirror coat over a diffuse base still has a lobe that light sampling can evaluate.
+    assert!(!(BsdfFlags::SPECULAR | BsdfFlags::DIFFUSE | BsdfFlags::REFLECTION).is_delta());
+    let wi = Vec3(1.0, 0.0, 1.0).unit_vector();
+    assert!(mirror.eval(&outgoing(), &wi) == Vec3(0.0, 0.0, 0.0));
+    assert!(mirror.pdf(&outgoing(), &wi) == 0.0);
+    // The mirror sends light leaving along wo in from the mirror direction.
+    let mut sampler = Sampler::new(1);
+    let sample = mirror.sample(&outgoing(), &mut sampler).unwrap();
+    assert!((sample.direction - wi).length() < 1e-6);
+}
+
+#[test]
+fn test_dielectric_refracts() {
+    let glass = Dielectric::new(1.5);
+    let mut sampler = Sampler::new(5);
+    let mut refracted = 0;
+    for _ in 0..1000 {
+        let sample = glass.sample(&outgoing(), &mut sampler).unwrap();
+        assert!(sample.weight == Vec3(1.0, 1.0, 1.0));
+        if sample.flags.contains(BsdfFlags::TRANSMISSION) {
+            refracted += 1;
+            // Snell's law, with the light coming from inside the glass.
+            let sine_out = (1.0 - outgoing().z() * outgoing().z()).sqrt();
+            let sine_in = (1.0 - sample.direction.z() * sample.direction.z()).sqrt();
+            assert!(sample.direction.z() < 0.0);
+            assert!((sine_out - 1.5 * sine_in).abs() < 1e-5);
+        }
+    }
+    // Most light gets through at 45 degrees.
+    assert!(refracted > 900 && refracted < 1000);
+}
+
+#[test]
+fn test_frame_round_trip() {
+    let frame = Frame::new(&Vec3(1.0, 2.0, 2.0).unit_vector());
+    let vector = Vec3(0.3, -1.2, 2.5);
+
+    assert!((frame.to_local(&frame.normal) - Vec3(0.0, 0.0, 1.0)).length() < 1e-6);
+    assert!((frame.to_world(&frame.to_local(&vector)) - vector).length() < 1e-5);
+}
+
+#[test]
+fn test_lights_do_not_scatter() {
+    let light = DiffuseLight::new(Vec3(1.0, 1.0, 1.0));
+    let hit_record = HitRecord {
+        distance: 1.0,
+        point: Vec3(0.0, 0.0, 0.0),
+        normal: Vec3(0.0, 1.0, 0.0),
+        u: 0.0,
+        v: 0.0,
+        material: &light,
+    };
+
+    assert!(light.bsdf(&hit_record).is_none());
+    assert!(Lambertian::new(Vec3(0.5, 0.5, 0.5))
+        .bsdf(&hit_record)
+        .is_some());
+}
+
+#[test]
+fn test_textured_albedo() {
+    let checker = Lambertian::new(Checker::new(Vec3(0.9, 0.9, 0.9), Vec3(0.1, 0.2, 0.3), 1.0));
+    let mut sampler = Sampler::new(2);
+    let mut weight_at = |point: Vec3| {
+        let hit_record = HitRecord {
+            distance: 1.0,
+            point,
+            normal: Vec3(0.0, 0.0, 1.0),
+            u: 0.0,
+            v: 0.0,
+            material: &checker,
+        };
+        let bsdf = checker.bsdf(&hit_record).unwrap();
+        bsdf.sample(&outgoing(), &mut sampler).unwrap().weight
+    };
+
+    assert!(weight_at(Vec3(0.5, 0.5, 0.5)) == Vec3(0.9, 0.9, 0.9));
+    assert!(weight_at(Vec3(1.5, 0.5, 0.5)) == Vec3(0.1, 0.2, 0.3));
+}
@@ -0,0 +1,225 @@
+use super::triangle::{self, TriangleHit};
+use super::{HitRecord, Hittable};
+use crate::aabb::Aabb;
+use crate::bvh::{BvhNode, BvhOptions};
+use crate::materials::Material;
+use crate::ray::Ray;
+use crate::sampler::Sampler;
+use crate::vec3::{Vec3, Vector};
+use rand::prelude::*;
+use std::sync::Arc;
+
+/// Vertex buffers and the triangles that index into them.
+///
+/// Every buffer is indexed by the same vertex number, so a vertex that needs a different normal
+/// or texture coordinate on two faces has to be stored twice.
+#[derive(Clone, Default)]
+pub struct MeshData {
+    pub positions: Vec<Vec3>,
+    /// One per position, or empty to shade with the flat face normals.
+    pub normals: Vec<Vec3>,
+    /// One `[u, v]` per position, or empty.
+    pub uvs: Vec<[f32; 2]>,
+    /// Vertex numbers of each triangle, counter-clockwise seen from the front.
+    pub triangles: Vec<[u32; 3]>,
+}
+
+/// Triangles sharing one set of vertex buffers and one material, with a BVH of their own.
+pub struct TriangleMesh {
+    shared: Arc<Shared>,
+    bvh: BvhNode,
+    /// Running total of the triangles' areas, for picking them in proportion to their size.
+    cumulative_areas: Vec<f32>,
+}
+
+struct Shared {
+    data: MeshData,
+    material: Box<dyn Material>,
+    total_area: f32,
+}
+
+/// One triangle of a mesh, as stored in the mesh's BVH.
+struct MeshTriangle {
+    shared: Arc<Shared>,
+    index: usize,
+}
+
+impl TriangleMesh {
+    /// Panics if `normals` or `uvs` is neither empty nor as long as `positions`, or if a
+    /// triangle refers to a vertex that doesn't exist.
+    pub fn new(data: MeshData, material: Box<dyn Material>) -> Self {
+        let vertex_count = data.positions.len();
+        assert!(
+            data.normals.is_empty() || data.normals.len() == vertex_count,
+            "mesh has {} normals for {} vertices",
+            data.normals.len(),
+            vertex_count
+        );
+        assert!(
+            data.uvs.is_empty() || data.uvs.len() == vertex_count,
+            "mesh has {} texture coordinates for {} vertices",
+            data.uvs.len(),
+            vertex_count
+        );
+        assert!(
+            data.triangles
+                .iter()
+                .flatten()
+                .all(|&vertex| (vertex as usize) < vertex_count),
+            "mesh triangle refers to a vertex past the {} it has",
+            vertex_count
+        );
+
+        let cumulative_areas: Vec<f32> = (0..data.triangles.len())
+            .scan(0.0, |total, index| {
+                *total += triangle::area(&vertices(&data, index));
+                Some(*total)
+            })
+            .collect();
+        let total_area = cumulative_areas.last().copied().unwrap_or(0.0);
+        let shared = Arc::new(Shared {
+            data,
+            material,
+            total_area,
+        });
+        let triangles = (0..shared.data.triangles.len())
+            .map(|index| {
+                let triangle: Box<dyn Hittable> = Box::new(MeshTriangle {
+                    shared: Arc::clone(&shared),
+                    index,
+                });
+                triangle
+            })
+            .collect();
+        TriangleMesh {
+            bvh: BvhNode::with_options(triangles, BvhOptions::default()),
+            shared,
+            cumulative_areas,
+        }
+    }
+
+    pub fn data(&self) -> &MeshData {
+        &self.shared.data
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.shared.data.triangles.len()
+    }
+
+    fn total_area(&self) -> f32 {
+        self.shared.total_area
+    }
+}
+
+impl Hittable for TriangleMesh {
+    fn hit(&self, ray: &Ray, distance_min: f32, distance_max: f32) -> Option<HitRecord<'_>> {
+        self.bvh.hit(ray, distance_min, distance_max)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bvh.bounding_box()
+    }
+
+    fn collect_lights<'a>(&'a self, lights: &mut Vec<&'a dyn Hittable>) {
+        if self.light_count() > 0 {
+            lights.push(self);
+        }
+    }
+
+    fn light_count(&self) -> usize {
+        usize::from(self.shared.material.is_emissive() && self.total_area() > 0.0)
+    }
+
+    /// A direction can lead to points on several triangles, on the near and far side of a
+    /// closed mesh, so the densities through all of them are summed.
+    fn direction_pdf(&self, origin: &Vec3, direction: &Vec3) -> f32 {
+        self.bvh.direction_pdf(origin, direction)
+    }
+
+    fn sample_direction(&self, origin: &Vec3, sampler: &mut Sampler) -> Vec3 {
+        let target = sampler.gen::<f32>() * self.total_area();
+        let index = self
+            .cumulative_areas
+            .partition_point(|&total| total <= target)
+            .min(self.cumulative_areas.len() - 1);
+        triangle::sample_point(&vertices(&self.shared.data, index), sampler) - origin
+    }
+}
+
+fn vertices(data: &MeshData, index: usize) -> [Vec3; 3] {
+    let [a, b, c] = data.triangles[index];
+    [
+        data.positions[a as usize],
+        data.positions[b as usize],
+        data.positions[c as usize],
+    ]
+}
+
+impl MeshTriangle {
+    fn vertices(&self) -> [Vec3; 3] {
+        vertices(&self.shared.data, self.index)
+    }
+
+    /// The interpolated vertex normal, or the face normal if the mesh has none.
+    fn normal(&self, vertices: &[Vec3; 3], hit: &TriangleHit) -> Vec3 {
+        let normals = &self.shared.data.normals;
+        if normals.is_empty() {
+            return triangle::geometric_normal(vertices);
+        }
+        let [a, b, c] = self.shared.data.triangles[self.index];
+        let [wa, wb, wc] = hit.barycentric;
+        (wa * normals[a as usize] + wb * normals[b as usize] + wc * normals[c as usize])
+            .unit_vector()
+    }
+
+    /// The interpolated texture coordinates, or the barycentric coordinates of the second and
+    /// third vertex if the mesh has none.
+    fn uv(&self, hit: &TriangleHit) -> (f32, f32) {
+        let uvs = &self.shared.data.uvs;
+        let [wa, wb, wc] = hit.barycentric;
+        if uvs.is_empty() {
+            return (wb, wc);
+        }
+        let [a, b, c] = self.shared.data.triangles[self.index];
+        let [a, b, c] = [uvs[a as usize], uvs[b as usize], uvs[c as usize]];
+        (
+            wa * a[0] + wb * b[0] + wc * c[0],
+            wa * a[1] + wb * b[1] + wc * c[1],
+        )
+    }
+}
+
+impl Hittable for MeshTriangle {
+    fn hit(&self, ray: &Ray, distance_min: f32, distance_max: f32) -> Option<HitRecord<'_>> {
+        let vertices = self.vertices();
+        let hit = triangle::intersect(ray, &vertices, distance_min, distance_max)?;
+        let (u, v) = self.uv(&hit);
+        Some(HitRecord {
+            distance: hit.distance,
+            point: ray.point_at(hit.distance),
+            normal: self.normal(&vertices, &hit),
+            u,
+            v,
+            material: &*self.shared.material,
+        })
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        triangle::bounding_box(&self.vertices())
+    }
+
+    /// The density of sampling the whole mesh by area, counting only the points on this
+    /// triangle. It takes the face's own normal, whatever the shading normals are.
+    fn direction_pdf(&self, origin: &Vec3, direction: &Vec3) -> f32 {
+        let vertices = self.vertices();
+        match triangle::intersect(&Ray::new(*origin, *direction), &vertices, 0.001, f32::MAX) {
+            Some(hit) => triangle::solid_angle_pdf(
+                direction,
+                hit.distance,
+                &triangle::geometric_normal(&vertices),
+                self.shared.total_area,
+            ),
+            None => 0.0,
+        }
+    }
+}
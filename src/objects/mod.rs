@@ -0,0 +1,329 @@
+mod mesh;
+mod planar;
+mod tests;
+mod transformed;
+mod triangle;
+use crate::aabb::Aabb;
+use crate::materials::Material;
+use crate::ray::Ray;
+use crate::sampler::Sampler;
+use crate::vec3::{orthonormal_basis, Vec3, Vector};
+pub use mesh::{MeshData, TriangleMesh};
+pub use planar::{Cuboid, Disk, Plane, Quad};
+use rand::prelude::*;
+use std::f32;
+pub use transformed::Transformed;
+pub use triangle::{Triangle, TriangleHit};
+
+pub struct HitRecord<'a> {
+    pub distance: f32,
+    pub point: Vec3,
+    pub normal: Vec3,
+    /// Texture coordinates of the point hit.
+    pub u: f32,
+    pub v: f32,
+    pub material: &'a dyn Material,
+}
+
+impl HitRecord<'_> {
+    /// The normal, flipped if need be to face back along `direction`. Flat surfaces such as
+    /// triangles can be hit from behind, where the stored normal points away from the ray.
+    pub fn normal_against(&self, direction: &Vec3) -> Vec3 {
+        if Vec3::dot(direction, &self.normal) > 0.0 {
+            -self.normal
+        } else {
+            self.normal
+        }
+    }
+}
+
+/// Anything a ray can hit. Scenes are shared between render threads, so implementors must be
+/// `Send + Sync`.
+pub trait Hittable: Send + Sync {
+    fn hit(&self, ray: &Ray, distance_min: f32, distance_max: f32) -> Option<HitRecord<'_>>;
+    fn bounding_box(&self) -> Aabb;
+
+    /// Adds every emissive shape in this object to `lights`. Collections pass the call on to
+    /// their members.
+    #[allow(unused_variables)]
+    fn collect_lights<'a>(&'a self, lights: &mut Vec<&'a dyn Hittable>) {}
+
+    /// The probability density, per unit solid angle, of `sample_direction` picking
+    /// `direction` from `origin`. Zero where the direction misses the shape, and everywhere for
+    /// shapes that can't be sampled.
+    #[allow(unused_variables)]
+    fn direction_pdf(&self, origin: &Vec3, direction: &Vec3) -> f32 {
+        0.0
+    }
+
+    /// A direction from `origin` towards a random point of the shape, not necessarily of unit
+    /// length. Shapes that can't be sampled return an arbitrary direction, which their zero
+    /// `direction_pdf` then gives no weight.
+    #[allow(unused_variables)]
+    fn sample_direction(&self, origin: &Vec3, sampler: &mut Sampler) -> Vec3 {
+        Vec3(1.0, 0.0, 0.0)
+    }
+
+    /// How many lights `collect_lights` adds. Collections count their members' once, when they
+    /// are built, so that sampling through them never has to gather the lights.
+    fn light_count(&self) -> usize {
+        let mut lights = Vec::new();
+        self.collect_lights(&mut lights);
+        lights.len()
+    }
+
+    /// `direction_pdf` summed over the lights `collect_lights` adds. Only asked of objects
+    /// that hold lights: one that is its own light gives its own density, and collections
+    /// pass the call on to the members holding lights.
+    fn lights_direction_pdf(&self, origin: &Vec3, direction: &Vec3) -> f32 {
+        self.direction_pdf(origin, direction)
+    }
+
+    /// `sample_direction` of the light that `collect_lights` adds at `index`, which must be
+    /// less than `light_count`.
+    #[allow(unused_variables)]
+    fn sample_light_direction(&self, index: usize, origin: &Vec3, sampler: &mut Sampler) -> Vec3 {
+        self.sample_direction(origin, sampler)
+    }
+}
+
+pub struct Sphere {
+    pub center: Vec3,
+    pub radius: f32,
+    pub material: Box<dyn Material>,
+}
+
+impl Hittable for Sphere {
+    fn hit(&self, ray: &Ray, distance_min: f32, distance_max: f32) -> Option<HitRecord<'_>> {
+        hit_sphere(
+            &self.center,
+            self.radius,
+            &*self.material,
+            ray,
+            distance_min,
+            distance_max,
+        )
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let radius = self.radius.abs();
+        let extent = Vec3(radius, radius, radius);
+        Aabb::new(self.center - extent, self.center + extent)
+    }
+
+    fn collect_lights<'a>(&'a self, lights: &mut Vec<&'a dyn Hittable>) {
+        if self.light_count() > 0 {
+            lights.push(self);
+        }
+    }
+
+    fn light_count(&self) -> usize {
+        usize::from(self.material.is_emissive())
+    }
+
+    fn direction_pdf(&self, origin: &Vec3, direction: &Vec3) -> f32 {
+        let cos_max = match self.visible_cone(origin) {
+            Some(cos_max) => cos_max,
+            None => return 0.25 * f32::consts::FRAC_1_PI,
+        };
+        if self
+            .hit(&Ray::new(*origin, *direction), 0.001, f32::MAX)
+            .is_none()
+        {
+            return 0.0;
+        }
+        1.0 / (2.0 * f32::consts::PI * (1.0 - cos_max))
+    }
+
+    /// Picks a direction in the cone the sphere fills as seen from `origin`, which wastes no
+    /// samples on its far side. From inside, any direction will do.
+    fn sample_direction(&self, origin: &Vec3, sampler: &mut Sampler) -> Vec3 {
+        let (axis, cos_theta) = match self.visible_cone(origin) {
+            Some(cos_max) => (
+                (self.center - origin).unit_vector(),
+                1.0 + sampler.gen::<f32>() * (cos_max - 1.0),
+            ),
+            None => (Vec3(0.0, 0.0, 1.0), 1.0 - 2.0 * sampler.gen::<f32>()),
+        };
+        let phi = 2.0 * f32::consts::PI * sampler.gen::<f32>();
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let (tangent, bitangent) = orthonormal_basis(&axis);
+        sin_theta * phi.cos() * tangent + sin_theta * phi.sin() * bitangent + cos_theta * axis
+    }
+}
+
+impl Sphere {
+    /// The cosine of the half-angle of the cone the sphere fills as seen from `origin`, or
+    /// `None` from inside it.
+    fn visible_cone(&self, origin: &Vec3) -> Option<f32> {
+        let squared_distance = (self.center - origin).squared_length();
+        let squared_radius = self.radius * self.radius;
+        if squared_distance <= squared_radius {
+            return None;
+        }
+        Some((1.0 - squared_radius / squared_distance).sqrt())
+    }
+}
+
+/// Where a ray first enters a sphere, if it does.
+fn hit_sphere<'a>(
+    center: &Vec3,
+    radius: f32,
+    material: &'a dyn Material,
+    ray: &Ray,
+    distance_min: f32,
+    distance_max: f32,
+) -> Option<HitRecord<'a>> {
+    let origin_offset = ray.origin() - center;
+    let a = Vec3::dot(ray.direction(), ray.direction());
+    let b = Vec3::dot(&origin_offset, ray.direction());
+    let c = Vec3::dot(&origin_offset, &origin_offset) - radius * radius;
+
+    let discriminant = b * b - a * c;
+    if discriminant > 0.0 {
+        let distance = (-b - discriminant.sqrt()) / a;
+        if distance < distance_max && distance > distance_min {
+            let point = ray.point_at(distance);
+            let (u, v) = sphere_uv(&((point - center) / radius.abs()));
+            let hit_record = HitRecord {
+                distance,
+                point,
+                normal: (point - center) / radius,
+                u,
+                v,
+                material,
+            };
+            return Some(hit_record);
+        }
+    }
+    None
+}
+
+/// A sphere gliding in a straight line from `center0` at `time0` to `center1` at `time1`, and
+/// holding still before and after. Seen through an open shutter it blurs along its path.
+///
+/// Moving shapes aren't sampled as lights, since where they are depends on the ray's time;
+/// an emissive one is still found by the paths that hit it.
+pub struct MovingSphere {
+    pub center0: Vec3,
+    pub center1: Vec3,
+    pub time0: f32,
+    pub time1: f32,
+    pub radius: f32,
+    pub material: Box<dyn Material>,
+}
+
+impl MovingSphere {
+    pub fn center(&self, time: f32) -> Vec3 {
+        if self.time1 <= self.time0 {
+            return self.center0;
+        }
+        let along = ((time - self.time0) / (self.time1 - self.time0)).clamp(0.0, 1.0);
+        self.center0 + along * (self.center1 - self.center0)
+    }
+}
+
+impl Hittable for MovingSphere {
+    fn hit(&self, ray: &Ray, distance_min: f32, distance_max: f32) -> Option<HitRecord<'_>> {
+        hit_sphere(
+            &self.center(ray.time()),
+            self.radius,
+            &*self.material,
+            ray,
+            distance_min,
+            distance_max,
+        )
+    }
+
+    /// Covers the whole path, so that the sphere is found at any time.
+    fn bounding_box(&self) -> Aabb {
+        let radius = self.radius.abs();
+        let extent = Vec3(radius, radius, radius);
+        Aabb::surrounding(
+            &Aabb::new(self.center0 - extent, self.center0 + extent),
+            &Aabb::new(self.center1 - extent, self.center1 + extent),
+        )
+    }
+}
+
+/// Longitude and latitude of a point on the unit sphere, scaled to [0, 1]. `u` runs around the
+/// y axis starting from -x, and `v` from the bottom pole to the top one.
+fn sphere_uv(point: &Vec3) -> (f32, f32) {
+    let theta = (-point.1).clamp(-1.0, 1.0).acos();
+    let phi = (-point.2).atan2(point.0) + f32::consts::PI;
+    (
+        phi * 0.5 * f32::consts::FRAC_1_PI,
+        theta * f32::consts::FRAC_1_PI,
+    )
+}
+
+pub struct HittableList {
+    list: Vec<Box<dyn Hittable>>,
+    /// How many lights each member holds.
+    light_counts: Vec<usize>,
+}
+
+impl HittableList {
+    pub fn new(list: Vec<Box<dyn Hittable>>) -> Self {
+        let light_counts = list.iter().map(|object| object.light_count()).collect();
+        HittableList { list, light_counts }
+    }
+
+    /// The members holding lights, with how many each holds.
+    fn lit(&self) -> impl Iterator<Item = (&dyn Hittable, usize)> {
+        self.list
+            .iter()
+            .zip(self.light_counts.iter())
+            .filter(|(_, &count)| count > 0)
+            .map(|(object, &count)| (&**object, count))
+    }
+}
+
+impl Hittable for HittableList {
+    fn hit(&self, ray: &Ray, distance_min: f32, distance_max: f32) -> Option<HitRecord<'_>> {
+        let mut result = None;
+        let mut closest_so_far = distance_max;
+        for object in self.list.iter() {
+            if let Some(i) = object.hit(ray, distance_min, closest_so_far) {
+                closest_so_far = i.distance;
+                result = Some(i)
+            }
+        }
+        result
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.list
+            .iter()
+            .fold(Aabb::empty(), |bounding_box, object| {
+                Aabb::surrounding(&bounding_box, &object.bounding_box())
+            })
+    }
+
+    fn collect_lights<'a>(&'a self, lights: &mut Vec<&'a dyn Hittable>) {
+        for object in self.list.iter() {
+            object.collect_lights(lights);
+        }
+    }
+
+    fn light_count(&self) -> usize {
+        self.light_counts.iter().sum()
+    }
+
+    fn lights_direction_pdf(&self, origin: &Vec3, direction: &Vec3) -> f32 {
+        self.lit()
+            .map(|(object, _)| object.lights_direction_pdf(origin, direction))
+            .sum()
+    }
+
+    fn sample_light_direction(&self, index: usize, origin: &Vec3, sampler: &mut Sampler) -> Vec3 {
+        let mut index = index;
+        for (object, count) in self.lit() {
+            if index < count {
+                return object.sample_light_direction(index, origin, sampler);
+            }
+            index -= count;
+        }
+        panic!("light index out of range")
+    }
+}
@@ -0,0 +1,371 @@
+use super::triangle::solid_angle_pdf;
+use super::{HitRecord, Hittable};
+use crate::aabb::Aabb;
+use crate::materials::Material;
+use crate::ray::Ray;
+use crate::sampler::Sampler;
+use crate::vec3::{orthonormal_basis, Vec3, Vector};
+use rand::prelude::*;
+use std::f32::consts::PI;
+
+/// The parallelogram spanned by two edges from a corner, as geometry alone.
+#[derive(Copy, Clone, Debug)]
+struct Parallelogram {
+    corner: Vec3,
+    u: Vec3,
+    v: Vec3,
+    normal: Vec3,
+    /// `u × v` over its squared length, which turns a point in the plane into its coordinates
+    /// along the two edges.
+    w: Vec3,
+    area: f32,
+}
+
+impl Parallelogram {
+    fn new(corner: Vec3, u: Vec3, v: Vec3) -> Self {
+        let cross = Vec3::cross(&u, &v);
+        let squared_length = cross.squared_length();
+        Parallelogram {
+            corner,
+            u,
+            v,
+            normal: cross.unit_vector(),
+            w: cross / squared_length,
+            area: squared_length.sqrt(),
+        }
+    }
+
+    /// The distance to where the ray crosses the parallelogram, and the point's coordinates
+    /// along `u` and `v`, each between 0 and 1.
+    fn intersect(
+        &self,
+        ray: &Ray,
+        distance_min: f32,
+        distance_max: f32,
+    ) -> Option<(f32, f32, f32)> {
+        let distance = plane_distance(ray, &self.corner, &self.normal)?;
+        if !(distance > distance_min && distance < distance_max) {
+            return None;
+        }
+        let offset = ray.point_at(distance) - self.corner;
+        let alpha = Vec3::dot(&self.w, &Vec3::cross(&offset, &self.v));
+        let beta = Vec3::dot(&self.w, &Vec3::cross(&self.u, &offset));
+        if !((0.0..=1.0).contains(&alpha) && (0.0..=1.0).contains(&beta)) {
+            return None;
+        }
+        Some((distance, alpha, beta))
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let corners = [
+            self.corner,
+            self.corner + self.u,
+            self.corner + self.v,
+            self.corner + self.u + self.v,
+        ];
+        corners.iter().fold(Aabb::empty(), |bounds, corner| {
+            Aabb::surrounding(&bounds, &Aabb::new(*corner, *corner))
+        })
+    }
+
+    fn sample_point(&self, sampler: &mut Sampler) -> Vec3 {
+        self.corner + sampler.gen::<f32>() * self.u + sampler.gen::<f32>() * self.v
+    }
+
+    /// The solid angle density of picking `direction` from `origin` with `sample_point`, or
+    /// zero if the direction misses.
+    fn direction_pdf(&self, origin: &Vec3, direction: &Vec3) -> f32 {
+        match self.intersect(&Ray::new(*origin, *direction), 0.001, f32::MAX) {
+            Some((distance, _, _)) => solid_angle_pdf(direction, distance, &self.normal, self.area),
+            None => 0.0,
+        }
+    }
+}
+
+/// The distance along the ray to the plane through `point` with the given normal, if the
+/// ray isn't parallel to it.
+fn plane_distance(ray: &Ray, point: &Vec3, normal: &Vec3) -> Option<f32> {
+    let denominator = Vec3::dot(normal, ray.direction());
+    if denominator == 0.0 || denominator.is_nan() {
+        return None;
+    }
+    Some(Vec3::dot(normal, &(point - ray.origin())) / denominator)
+}
+
+/// A parallelogram, or with square corners a rectangle, spanned by the edges `u` and `v` from
+/// a corner. It faces the side from which `u` turns counter-clockwise to `v`, and has texture
+/// coordinates running from 0 to 1 along each edge.
+pub struct Quad {
+    shape: Parallelogram,
+    material: Box<dyn Material>,
+}
+
+impl Quad {
+    pub fn new(corner: Vec3, u: Vec3, v: Vec3, material: Box<dyn Material>) -> Self {
+        Quad {
+            shape: Parallelogram::new(corner, u, v),
+            material,
+        }
+    }
+}
+
+impl Hittable for Quad {
+    fn hit(&self, ray: &Ray, distance_min: f32, distance_max: f32) -> Option<HitRecord<'_>> {
+        let (distance, u, v) = self.shape.intersect(ray, distance_min, distance_max)?;
+        Some(HitRecord {
+            distance,
+            point: ray.point_at(distance),
+            normal: self.shape.normal,
+            u,
+            v,
+            material: &*self.material,
+        })
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.shape.bounding_box()
+    }
+
+    fn collect_lights<'a>(&'a self, lights: &mut Vec<&'a dyn Hittable>) {
+        if self.light_count() > 0 {
+            lights.push(self);
+        }
+    }
+
+    fn light_count(&self) -> usize {
+        usize::from(self.material.is_emissive() && self.shape.area > 0.0)
+    }
+
+    fn direction_pdf(&self, origin: &Vec3, direction: &Vec3) -> f32 {
+        self.shape.direction_pdf(origin, direction)
+    }
+
+    fn sample_direction(&self, origin: &Vec3, sampler: &mut Sampler) -> Vec3 {
+        self.shape.sample_point(sampler) - origin
+    }
+}
+
+/// An axis-aligned box between two opposite corners, made of six quads facing outwards.
+pub struct Cuboid {
+    faces: [Parallelogram; 6],
+    material: Box<dyn Material>,
+}
+
+impl Cuboid {
+    /// Any two opposite corners will do; they needn't be the minimum and maximum.
+    pub fn new(a: Vec3, b: Vec3, material: Box<dyn Material>) -> Self {
+        let min = Vec3(a.0.min(b.0), a.1.min(b.1), a.2.min(b.2));
+        let max = Vec3(a.0.max(b.0), a.1.max(b.1), a.2.max(b.2));
+        let dx = Vec3(max.0 - min.0, 0.0, 0.0);
+        let dy = Vec3(0.0, max.1 - min.1, 0.0);
+        let dz = Vec3(0.0, 0.0, max.2 - min.2);
+        Cuboid {
+            faces: [
+                Parallelogram::new(min, dz, dy),
+                Parallelogram::new(Vec3(max.0, min.1, min.2), dy, dz),
+                Parallelogram::new(min, dx, dz),
+                Parallelogram::new(Vec3(min.0, max.1, min.2), dz, dx),
+                Parallelogram::new(min, dy, dx),
+                Parallelogram::new(Vec3(min.0, min.1, max.2), dx, dy),
+            ],
+            material,
+        }
+    }
+
+    fn total_area(&self) -> f32 {
+        self.faces.iter().map(|face| face.area).sum()
+    }
+}
+
+impl Hittable for Cuboid {
+    fn hit(&self, ray: &Ray, distance_min: f32, distance_max: f32) -> Option<HitRecord<'_>> {
+        let mut closest = None;
+        let mut closest_distance = distance_max;
+        for face in self.faces.iter() {
+            if let Some((distance, u, v)) = face.intersect(ray, distance_min, closest_distance) {
+                closest_distance = distance;
+                closest = Some((face, u, v));
+            }
+        }
+        let (face, u, v) = closest?;
+        Some(HitRecord {
+            distance: closest_distance,
+            point: ray.point_at(closest_distance),
+            normal: face.normal,
+            u,
+            v,
+            material: &*self.material,
+        })
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.faces.iter().fold(Aabb::empty(), |bounds, face| {
+            Aabb::surrounding(&bounds, &face.bounding_box())
+        })
+    }
+
+    fn collect_lights<'a>(&'a self, lights: &mut Vec<&'a dyn Hittable>) {
+        if self.light_count() > 0 {
+            lights.push(self);
+        }
+    }
+
+    fn light_count(&self) -> usize {
+        usize::from(self.material.is_emissive() && self.total_area() > 0.0)
+    }
+
+    /// Faces are picked in proportion to their area, and a direction can lead to a point on
+    /// the near face and one on the far face, so both count.
+    fn direction_pdf(&self, origin: &Vec3, direction: &Vec3) -> f32 {
+        let total_area = self.total_area();
+        self.faces
+            .iter()
+            .map(|face| face.area / total_area * face.direction_pdf(origin, direction))
+            .sum()
+    }
+
+    fn sample_direction(&self, origin: &Vec3, sampler: &mut Sampler) -> Vec3 {
+        let mut target = sampler.gen::<f32>() * self.total_area();
+        let face = self
+            .faces
+            .iter()
+            .find(|face| {
+                target -= face.area;
+                target < 0.0
+            })
+            .unwrap_or(&self.faces[5]);
+        face.sample_point(sampler) - origin
+    }
+}
+
+/// A flat disk, facing the way its normal points. Texture coordinates are polar: `u` runs
+/// once around the center and `v` from the center out to the rim.
+pub struct Disk {
+    center: Vec3,
+    normal: Vec3,
+    radius: f32,
+    material: Box<dyn Material>,
+}
+
+impl Disk {
+    /// `normal` may have any length but zero.
+    pub fn new(center: Vec3, normal: Vec3, radius: f32, material: Box<dyn Material>) -> Self {
+        Disk {
+            center,
+            normal: normal.unit_vector(),
+            radius,
+            material,
+        }
+    }
+
+    fn area(&self) -> f32 {
+        PI * self.radius * self.radius
+    }
+
+    fn intersect(&self, ray: &Ray, distance_min: f32, distance_max: f32) -> Option<(f32, Vec3)> {
+        let distance = plane_distance(ray, &self.center, &self.normal)?;
+        if !(distance > distance_min && distance < distance_max) {
+            return None;
+        }
+        let offset = ray.point_at(distance) - self.center;
+        if offset.squared_length() > self.radius * self.radius {
+            return None;
+        }
+        Some((distance, offset))
+    }
+}
+
+impl Hittable for Disk {
+    fn hit(&self, ray: &Ray, distance_min: f32, distance_max: f32) -> Option<HitRecord<'_>> {
+        let (distance, offset) = self.intersect(ray, distance_min, distance_max)?;
+        let (tangent, bitangent) = orthonormal_basis(&self.normal);
+        let angle = Vec3::dot(&offset, &bitangent).atan2(Vec3::dot(&offset, &tangent));
+        Some(HitRecord {
+            distance,
+            point: ray.point_at(distance),
+            normal: self.normal,
+            u: angle / (2.0 * PI) + 0.5,
+            v: offset.length() / self.radius,
+            material: &*self.material,
+        })
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        // How far the rim reaches along each axis.
+        let reach = |axis: f32| self.radius * (1.0 - axis * axis).max(0.0).sqrt();
+        let extent = Vec3(
+            reach(self.normal.0),
+            reach(self.normal.1),
+            reach(self.normal.2),
+        );
+        Aabb::new(self.center - extent, self.center + extent)
+    }
+
+    fn collect_lights<'a>(&'a self, lights: &mut Vec<&'a dyn Hittable>) {
+        if self.light_count() > 0 {
+            lights.push(self);
+        }
+    }
+
+    fn light_count(&self) -> usize {
+        usize::from(self.material.is_emissive() && self.radius > 0.0)
+    }
+
+    fn direction_pdf(&self, origin: &Vec3, direction: &Vec3) -> f32 {
+        match self.intersect(&Ray::new(*origin, *direction), 0.001, f32::MAX) {
+            Some((distance, _)) => solid_angle_pdf(direction, distance, &self.normal, self.area()),
+            None => 0.0,
+        }
+    }
+
+    fn sample_direction(&self, origin: &Vec3, sampler: &mut Sampler) -> Vec3 {
+        let (tangent, bitangent) = orthonormal_basis(&self.normal);
+        let radius = self.radius * sampler.gen::<f32>().sqrt();
+        let angle = 2.0 * PI * sampler.gen::<f32>();
+        self.center + radius * angle.cos() * tangent + radius * angle.sin() * bitangent - origin
+    }
+}
+
+/// An infinite plane through a point, facing the way its normal points. Its texture
+/// coordinates are distances along two axes in the plane, so image textures repeat every
+/// unit. Too big to sample as a light.
+pub struct Plane {
+    point: Vec3,
+    normal: Vec3,
+    material: Box<dyn Material>,
+}
+
+impl Plane {
+    /// `normal` may have any length but zero.
+    pub fn new(point: Vec3, normal: Vec3, material: Box<dyn Material>) -> Self {
+        Plane {
+            point,
+            normal: normal.unit_vector(),
+            material,
+        }
+    }
+}
+
+impl Hittable for Plane {
+    fn hit(&self, ray: &Ray, distance_min: f32, distance_max: f32) -> Option<HitRecord<'_>> {
+        let distance = plane_distance(ray, &self.point, &self.normal)?;
+        if !(distance > distance_min && distance < distance_max) {
+            return None;
+        }
+        let point = ray.point_at(distance);
+        let (tangent, bitangent) = orthonormal_basis(&self.normal);
+        let offset = point - self.point;
+        Some(HitRecord {
+            distance,
+            point,
+            normal: self.normal,
+            u: Vec3::dot(&offset, &tangent),
+            v: Vec3::dot(&offset, &bitangent),
+            material: &*self.material,
+        })
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        Aabb::unbounded()
+    }
+}
@@ -0,0 +1,650 @@
+#[cfg(test)]
+use crate::bvh::BvhNode;
+#[cfg(test)]
+use crate::materials::{DiffuseLight, Lambertian};
+#[cfg(test)]
+use crate::objects::{
+    Cuboid, Disk, Hittable, HittableList, MeshData, MovingSphere, Plane, Quad, Sphere, Transformed,
+    Triangle, TriangleMesh,
+};
+#[cfg(test)]
+use crate::ray::Ray;
+#[cfg(test)]
+use crate::sampler::Sampler;
+#[cfg(test)]
+use crate::vec3::{Matrix4, Vec3, Vector};
+#[cfg(test)]
+use rand::prelude::*;
+#[cfg(test)]
+use std::sync::Arc;
+
+#[cfg(test)]
+fn gray() -> Box<Lambertian> {
+    Box::new(Lambertian::new(Vec3(0.5, 0.5, 0.5)))
+}
+
+#[test]
+fn test_triangle_hit() {
+    let triangle = Triangle::new(
+        Vec3(0.0, 0.0, -2.0),
+        Vec3(2.0, 0.0, -2.0),
+        Vec3(0.0, 2.0, -2.0),
+        gray(),
+    );
+    let ray = Ray::new(Vec3(0.5, 0.5, 0.0), Vec3(0.0, 0.0, -1.0));
+    let hit = triangle.intersect(&ray, 0.001, f32::MAX).unwrap();
+
+    assert!(hit.distance == 2.0);
+    assert!(hit.barycentric == [0.5, 0.25, 0.25]);
+    let record = triangle.hit(&ray, 0.001, f32::MAX).unwrap();
+    assert!(record.normal == Vec3(0.0, 0.0, 1.0));
+
+    let outside = Ray::new(Vec3(1.5, 1.5, 0.0), Vec3(0.0, 0.0, -1.0));
+    assert!(triangle.hit(&outside, 0.001, f32::MAX).is_none());
+    assert!(triangle.hit(&ray, 0.001, 1.5).is_none());
+}
+
+#[test]
+fn test_triangle_hit_from_behind() {
+    let triangle = Triangle::new(
+        Vec3(0.0, 0.0, -2.0),
+        Vec3(2.0, 0.0, -2.0),
+        Vec3(0.0, 2.0, -2.0),
+        gray(),
+    );
+    let ray = Ray::new(Vec3(0.5, 0.5, -4.0), Vec3(0.0, 0.0, 1.0));
+    let record = triangle.hit(&ray, 0.001, f32::MAX).unwrap();
+
+    assert!(record.distance == 2.0);
+    assert!(record.normal == Vec3(0.0, 0.0, 1.0));
+    assert!(record.normal_against(ray.direction()) == Vec3(0.0, 0.0, -1.0));
+}
+
+#[test]
+fn test_shared_edge_is_watertight() {
+    let corners = [
+        Vec3(-1.0, -1.0, -2.3),
+        Vec3(1.3, -0.7, -2.0),
+        Vec3(0.9, 1.1, -2.7),
+        Vec3(-1.2, 0.8, -2.1),
+    ];
+    let first = Triangle::new(corners[0], corners[1], corners[2], gray());
+    let second = Triangle::new(corners[0], corners[2], corners[3], gray());
+    let origin = Vec3(0.1, 0.2, 0.3);
+
+    // Aim straight at points on the shared diagonal, where rounding decides which triangle
+    // is hit. It must be at least one of them.
+    for step in 1..1000 {
+        let along = step as f32 / 1000.0;
+        let target = corners[0] + along * (corners[2] - corners[0]);
+        let ray = Ray::new(origin, target - origin);
+        let hits = first.intersect(&ray, 0.0, f32::MAX).is_some() as usize
+            + second.intersect(&ray, 0.0, f32::MAX).is_some() as usize;
+        assert!(hits >= 1, "ray at {} slipped through the edge", along);
+    }
+}
+
+#[cfg(test)]
+fn random_soup(sampler: &mut Sampler, count: usize) -> MeshData {
+    let mut data = MeshData::default();
+    for index in 0..count as u32 {
+        let center = Vec3(
+            sampler.gen_range(-5.0, 5.0),
+            sampler.gen_range(-5.0, 5.0),
+            sampler.gen_range(-5.0, 5.0),
+        );
+        for _ in 0..3 {
+            let offset = Vec3(sampler.gen(), sampler.gen(), sampler.gen()) - Vec3(0.5, 0.5, 0.5);
+            data.positions.push(center + offset);
+        }
+        data.triangles
+            .push([3 * index, 3 * index + 1, 3 * index + 2]);
+    }
+    data
+}
+
+#[test]
+fn test_mesh_matches_triangles() {
+    let mut sampler = Sampler::new(11);
+    let data = random_soup(&mut sampler, 500);
+    let triangles: Vec<Box<dyn Hittable>> = data
+        .triangles
+        .iter()
+        .map(|&[a, b, c]| {
+            let triangle: Box<dyn Hittable> = Box::new(Triangle::new(
+                data.positions[a as usize],
+                data.positions[b as usize],
+                data.positions[c as usize],
+                gray(),
+            ));
+            triangle
+        })
+        .collect();
+    let list = HittableList::new(triangles);
+    let mesh = TriangleMesh::new(data, gray());
+
+    assert!(mesh.triangle_count() == 500);
+    let mut hits = 0;
+    for _ in 0..2000 {
+        let origin = Vec3(
+            sampler.gen_range(-8.0, 8.0),
+            sampler.gen_range(-8.0, 8.0),
+            sampler.gen_range(-8.0, 8.0),
+        );
+        let direction = Vec3(sampler.gen(), sampler.gen(), sampler.gen()) - Vec3(0.5, 0.5, 0.5);
+        let ray = Ray::new(origin, direction);
+        let expected = list.hit(&ray, 0.001, f32::MAX).map(|hit| hit.distance);
+        let actual = mesh.hit(&ray, 0.001, f32::MAX).map(|hit| hit.distance);
+        assert!(expected == actual);
+        hits += expected.is_some() as usize;
+    }
+    assert!(hits > 100);
+}
+
+#[test]
+fn test_mesh_interpolates_normals() {
+    let data = MeshData {
+        positions: vec![
+            Vec3(0.0, 0.0, -2.0),
+            Vec3(2.0, 0.0, -2.0),
+            Vec3(0.0, 2.0, -2.0),
+        ],
+        normals: vec![
+            Vec3(0.0, 0.0, 1.0),
+            Vec3(1.0, 0.0, 0.0),
+            Vec3(0.0, 1.0, 0.0),
+        ],
+        uvs: Vec::new(),
+        triangles: vec![[0, 1, 2]],
+    };
+    let mesh = TriangleMesh::new(data, gray());
+    let ray = Ray::new(Vec3(1.0, 1.0, 0.0), Vec3(0.0, 0.0, -1.0));
+    let normal = mesh.hit(&ray, 0.001, f32::MAX).unwrap().normal;
+
+    let expected = Vec3(1.0, 1.0, 0.0).unit_vector();
+    assert!((normal - expected).length() < 1e-6);
+}
+
+#[test]
+fn test_texture_coordinates() {
+    let sphere = Sphere {
+        center: Vec3(0.0, 0.0, -3.0),
+        radius: 1.0,
+        material: gray(),
+    };
+    // Straight at the side of the sphere facing +z: a quarter of the way round from -x, and
+    // halfway up.
+    let hit = sphere
+        .hit(
+            &Ray::new(Vec3(0.0, 0.0, 0.0), Vec3(0.0, 0.0, -1.0)),
+            0.001,
+            f32::MAX,
+        )
+        .unwrap();
+    assert!((hit.u - 0.25).abs() < 1e-6 && (hit.v - 0.5).abs() < 1e-6);
+    let hit = sphere
+        .hit(
+            &Ray::new(Vec3(0.0, 5.0, -3.0), Vec3(0.0, -1.0, 0.0)),
+            0.001,
+            f32::MAX,
+        )
+        .unwrap();
+    assert!((hit.v - 1.0).abs() < 1e-6);
+
+    let positions = vec![
+        Vec3(0.0, 0.0, -2.0),
+        Vec3(2.0, 0.0, -2.0),
+        Vec3(0.0, 2.0, -2.0),
+    ];
+    let ray = Ray::new(Vec3(0.5, 1.0, 0.0), Vec3(0.0, 0.0, -1.0));
+    let triangle = Triangle::new(positions[0], positions[1], positions[2], gray());
+    let hit = triangle.hit(&ray, 0.001, f32::MAX).unwrap();
+    assert!((hit.u - 0.25).abs() < 1e-6 && (hit.v - 0.5).abs() < 1e-6);
+    let data = MeshData {
+        positions,
+        uvs: vec![[0.5, 0.5], [1.0, 0.5], [0.5, 1.0]],
+        triangles: vec![[0, 1, 2]],
+        ..MeshData::default()
+    };
+    let mesh = TriangleMesh::new(data, gray());
+    let hit = mesh.hit(&ray, 0.001, f32::MAX).unwrap();
+    assert!((hit.u - 0.625).abs() < 1e-6 && (hit.v - 0.75).abs() < 1e-6);
+}
+
+#[test]
+#[should_panic(expected = "refers to a vertex")]
+fn test_mesh_rejects_bad_index() {
+    let data = MeshData {
+        positions: vec![Vec3(0.0, 0.0, 0.0); 3],
+        triangles: vec![[0, 1, 3]],
+        ..MeshData::default()
+    };
+    TriangleMesh::new(data, gray());
+}
+
+#[test]
+fn test_quad_hit() {
+    let quad = Quad::new(
+        Vec3(-1.0, -1.0, -2.0),
+        Vec3(2.0, 0.0, 0.0),
+        Vec3(1.0, 2.0, 0.0),
+        gray(),
+    );
+    let ray = Ray::new(Vec3(0.5, 0.0, 0.0), Vec3(0.0, 0.0, -1.0));
+    let record = quad.hit(&ray, 0.001, f32::MAX).unwrap();
+
+    assert!(record.distance == 2.0);
+    assert!(record.point == Vec3(0.5, 0.0, -2.0));
+    assert!(record.normal == Vec3(0.0, 0.0, 1.0));
+    assert!(record.u == 0.5 && record.v == 0.5);
+    // Slanted sides: inside the bounding rectangle but outside the parallelogram.
+    let outside = Ray::new(Vec3(-0.9, 0.9, 0.0), Vec3(0.0, 0.0, -1.0));
+    assert!(quad.hit(&outside, 0.001, f32::MAX).is_none());
+    let parallel = Ray::new(Vec3(0.0, 0.0, -2.0), Vec3(1.0, 0.0, 0.0));
+    assert!(quad.hit(&parallel, 0.001, f32::MAX).is_none());
+    assert!(quad.hit(&ray, 0.001, 1.5).is_none());
+
+    let bounds = quad.bounding_box();
+    assert!(bounds.min == Vec3(-1.0, -1.0, -2.0) && bounds.max == Vec3(2.0, 1.0, -2.0));
+}
+
+#[test]
+fn test_cuboid_hit() {
+    let cuboid = Cuboid::new(Vec3(1.0, 1.0, 1.0), Vec3(-1.0, 0.0, -2.0), gray());
+    let bounds = cuboid.bounding_box();
+    assert!(bounds.min == Vec3(-1.0, 0.0, -2.0) && bounds.max == Vec3(1.0, 1.0, 1.0));
+
+    // From outside along each axis the near face is hit, and it faces back at the ray.
+    let center = Vec3(0.0, 0.5, -0.5);
+    let axes = [
+        Vec3(1.0, 0.0, 0.0),
+        Vec3(0.0, 1.0, 0.0),
+        Vec3(0.0, 0.0, 1.0),
+    ];
+    for axis in axes.iter() {
+        for &side in [-1.0, 1.0].iter() {
+            let outward = side * *axis;
+            let ray = Ray::new(center + 5.0 * outward, -outward);
+            let record = cuboid.hit(&ray, 0.001, f32::MAX).unwrap();
+            assert!(
+                record.normal == outward,
+                "{:?} for {:?}",
+                record.normal,
+                outward
+            );
+            assert!(Vec3::dot(&(record.point - center), &outward) > 0.0);
+        }
+    }
+    // From inside, the face ahead.
+    let ray = Ray::new(center, Vec3(0.0, 0.0, -1.0));
+    let record = cuboid.hit(&ray, 0.001, f32::MAX).unwrap();
+    assert!(record.distance == 1.5);
+    assert!(record.normal == Vec3(0.0, 0.0, -1.0));
+
+    let miss = Ray::new(Vec3(0.0, 2.0, 5.0), Vec3(0.0, 0.0, -1.0));
+    assert!(cuboid.hit(&miss, 0.001, f32::MAX).is_none());
+}
+
+#[test]
+fn test_disk_hit() {
+    let disk = Disk::new(Vec3(0.0, 1.0, 0.0), Vec3(0.0, 2.0, 0.0), 2.0, gray());
+    let ray = Ray::new(Vec3(1.0, 3.0, 0.0), Vec3(0.0, -1.0, 0.0));
+    let record = disk.hit(&ray, 0.001, f32::MAX).unwrap();
+
+    assert!(record.distance == 2.0);
+    assert!(record.normal == Vec3(0.0, 1.0, 0.0));
+    assert!(record.v == 0.5);
+    assert!((0.0..=1.0).contains(&record.u));
+    let rim = Ray::new(Vec3(1.5, 3.0, 1.5), Vec3(0.0, -1.0, 0.0));
+    assert!(disk.hit(&rim, 0.001, f32::MAX).is_none());
+
+    // A quarter turn about the center is a quarter of the way around.
+    let turned = Ray::new(Vec3(0.0, 3.0, 1.0), Vec3(0.0, -1.0, 0.0));
+    let quarter = disk.hit(&turned, 0.001, f32::MAX).unwrap();
+    let turn = (quarter.u - record.u).rem_euclid(1.0);
+    assert!((turn - 0.25).abs() < 1e-5 || (turn - 0.75).abs() < 1e-5);
+
+    let bounds = disk.bounding_box();
+    assert!(bounds.min == Vec3(-2.0, 1.0, -2.0) && bounds.max == Vec3(2.0, 1.0, 2.0));
+}
+
+#[test]
+fn test_plane_hit() {
+    let plane = Plane::new(Vec3(0.0, -1.0, 0.0), Vec3(0.0, 1.0, 0.0), gray());
+    let ray = Ray::new(Vec3(0.0, 0.0, 0.0), Vec3(1000.0, -1.0, 0.0));
+    let record = plane.hit(&ray, 0.001, f32::MAX).unwrap();
+
+    assert!(record.distance == 1.0);
+    assert!(record.normal == Vec3(0.0, 1.0, 0.0));
+    // The coordinates measure distance across the plane.
+    assert!((record.u.hypot(record.v) - 1000.0).abs() < 1e-2);
+    let upward = Ray::new(Vec3(0.0, 0.0, 0.0), Vec3(0.0, 1.0, 0.0));
+    assert!(plane.hit(&upward, 0.001, f32::MAX).is_none());
+    assert!(!plane.bounding_box().is_bounded());
+
+    // Unbounded objects still work inside a list.
+    let list = HittableList::new(vec![
+        Box::new(Plane::new(
+            Vec3(0.0, -1.0, 0.0),
+            Vec3(0.0, 1.0, 0.0),
+            gray(),
+        )),
+        Box::new(Sphere {
+            center: Vec3(0.0, 0.0, -3.0),
+            radius: 0.5,
+            material: gray(),
+        }),
+    ]);
+    let down = Ray::new(Vec3(0.0, 0.0, 0.0), Vec3(0.0, -1.0, -1.0));
+    assert!(list.hit(&down, 0.001, f32::MAX).unwrap().distance == 1.0);
+    let ahead = Ray::new(Vec3(0.0, 0.0, 0.0), Vec3(0.0, 0.0, -1.0));
+    assert!(list.hit(&ahead, 0.001, f32::MAX).unwrap().distance == 2.5);
+}
+
+#[test]
+fn test_transformed_hit() {
+    // A unit sphere squashed to half height and moved down the z axis.
+    let ellipsoid = Transformed::new(
+        Box::new(Sphere {
+            center: Vec3(0.0, 0.0, 0.0),
+            radius: 1.0,
+            material: gray(),
+        }),
+        Matrix4::translation(Vec3(0.0, 0.0, -4.0)) * Matrix4::scaling(Vec3(1.0, 0.5, 1.0)),
+    );
+    let ray = Ray::new(Vec3(0.0, 2.0, -4.0), Vec3(0.0, -2.0, 0.0));
+    let record = ellipsoid.hit(&ray, 0.001, f32::MAX).unwrap();
+    assert!(record.distance == 0.75);
+    assert!(record.point == Vec3(0.0, 0.5, -4.0));
+    assert!(record.normal == Vec3(0.0, 1.0, 0.0));
+
+    // Off-center the normal tilts more steeply than the sphere's would.
+    let ray = Ray::new(Vec3(0.6, 2.0, -4.0), Vec3(0.0, -1.0, 0.0));
+    let record = ellipsoid.hit(&ray, 0.001, f32::MAX).unwrap();
+    assert!((record.point.1 - 0.4).abs() < 1e-5);
+    let expected = Vec3(0.6, 0.8 / 0.5, 0.0).unit_vector();
+    assert!((record.normal - expected).length() < 1e-5);
+    let past = Ray::new(Vec3(0.0, 0.7, -4.0), Vec3(0.0, 0.0, 1.0));
+    assert!(ellipsoid.hit(&past, 0.001, f32::MAX).is_none());
+
+    let bounds = ellipsoid.bounding_box();
+    assert!(bounds.min == Vec3(-1.0, -0.5, -5.0) && bounds.max == Vec3(1.0, 0.5, -3.0));
+    let plane = Transformed::new(
+        Box::new(Plane::new(Vec3(0.0, 0.0, 0.0), Vec3(0.0, 1.0, 0.0), gray())),
+        Matrix4::rotation(Vec3(0.0, 0.0, 1.0), 30.0),
+    );
+    assert!(!plane.bounding_box().is_bounded());
+}
+
+#[test]
+fn test_instances_share_mesh() {
+    let mut sampler = Sampler::new(3);
+    let mesh: Arc<dyn Hittable> =
+        Arc::new(TriangleMesh::new(random_soup(&mut sampler, 200), gray()));
+    let instances: Vec<Box<dyn Hittable>> = (0..10_000)
+        .map(|index| {
+            let offset = Vec3((index % 100) as f32 * 3.0, 0.0, (index / 100) as f32 * -3.0);
+            let instance: Box<dyn Hittable> = Box::new(Transformed::instance(
+                Arc::clone(&mesh),
+                Matrix4::translation(offset) * Matrix4::rotation(Vec3(0.0, 1.0, 0.0), index as f32),
+            ));
+            instance
+        })
+        .collect();
+    // One mesh, and per instance little more than a pair of matrices.
+    assert!(Arc::strong_count(&mesh) == 10_001);
+    assert!(std::mem::size_of::<Transformed>() <= 160);
+
+    let list = HittableList::new(instances);
+    let bounds = list.bounding_box();
+    assert!(bounds.max.0 > 290.0 && bounds.min.2 < -290.0);
+}
+
+#[test]
+fn test_moving_sphere() {
+    let sphere = MovingSphere {
+        center0: Vec3(0.0, 0.0, -2.0),
+        center1: Vec3(2.0, 0.0, -2.0),
+        time0: 1.0,
+        time1: 3.0,
+        radius: 0.5,
+        material: gray(),
+    };
+    assert!(sphere.center(2.0) == Vec3(1.0, 0.0, -2.0));
+    // Still before and after its motion.
+    assert!(sphere.center(0.0) == sphere.center0 && sphere.center(5.0) == sphere.center1);
+
+    let at = |time: f32| Ray::with_time(Vec3(1.0, 0.0, 0.0), Vec3(0.0, 0.0, -1.0), time);
+    assert!(sphere.hit(&at(1.0), 0.001, f32::MAX).is_none());
+    let record = sphere.hit(&at(2.0), 0.001, f32::MAX).unwrap();
+    assert!(record.distance == 1.5 && record.normal == Vec3(0.0, 0.0, 1.0));
+    assert!(sphere.hit(&at(3.0), 0.001, f32::MAX).is_none());
+
+    let bounds = sphere.bounding_box();
+    assert!(bounds.min == Vec3(-0.5, -0.5, -2.5) && bounds.max == Vec3(2.5, 0.5, -1.5));
+    let glowing = MovingSphere {
+        material: light(),
+        ..sphere
+    };
+    let mut lights = Vec::new();
+    glowing.collect_lights(&mut lights);
+    assert!(lights.is_empty());
+}
+
+#[test]
+fn test_animated_transform() {
+    let cube: Arc<dyn Hittable> = Arc::new(Cuboid::new(
+        Vec3(-0.5, -0.5, -0.5),
+        Vec3(0.5, 0.5, 0.5),
+        light(),
+    ));
+    // Sliding along x while growing to twice the size.
+    let moving = Transformed::animated(
+        Arc::clone(&cube),
+        vec![
+            (
+                1.0,
+                Matrix4::translation(Vec3(4.0, 0.0, -5.0)) * Matrix4::scaling(Vec3(2.0, 2.0, 2.0)),
+            ),
+            (0.0, Matrix4::translation(Vec3(0.0, 0.0, -5.0))),
+        ],
+    );
+    let toward = |x: f32, time: f32| Ray::with_time(Vec3(x, 0.0, 0.0), Vec3(0.0, 0.0, -1.0), time);
+    let record = moving.hit(&toward(0.0, 0.0), 0.001, f32::MAX).unwrap();
+    assert!(record.distance == 4.5 && record.normal == Vec3(0.0, 0.0, 1.0));
+    assert!(moving.hit(&toward(0.0, 1.0), 0.001, f32::MAX).is_none());
+    let record = moving.hit(&toward(4.0, 1.0), 0.001, f32::MAX).unwrap();
+    assert!(record.distance == 4.0);
+    // Halfway it's halfway along and one and a half times the size.
+    let record = moving.hit(&toward(2.0, 0.5), 0.001, f32::MAX).unwrap();
+    assert!((record.distance - 4.25).abs() < 1e-5);
+    assert!(moving.hit(&toward(2.8, 0.5), 0.001, f32::MAX).is_none());
+    // Holding still beyond the keys.
+    assert!(moving.hit(&toward(0.0, -1.0), 0.001, f32::MAX).is_some());
+    assert!(moving.hit(&toward(4.0, 2.0), 0.001, f32::MAX).is_some());
+
+    let bounds = moving.bounding_box();
+    assert!(bounds.min == Vec3(-0.5, -1.0, -6.0) && bounds.max == Vec3(5.0, 1.0, -4.0));
+    // Only the still instance is sampled as a light.
+    let still = Transformed::instance(cube, Matrix4::translation(Vec3(0.0, 0.0, -5.0)));
+    let mut lights = Vec::new();
+    moving.collect_lights(&mut lights);
+    assert!(lights.is_empty());
+    still.collect_lights(&mut lights);
+    assert!(lights.len() == 1);
+}
+
+#[cfg(test)]
+fn light() -> Box<DiffuseLight> {
+    Box::new(DiffuseLight::new(Vec3(1.0, 1.0, 1.0)))
+}
+
+#[test]
+fn test_light_sampling() {
+    let square = MeshData {
+        positions: vec![
+            Vec3(-1.0, -1.0, -2.0),
+            Vec3(1.0, -1.0, -2.0),
+            Vec3(1.0, 1.0, -2.0),
+            Vec3(-1.0, 1.0, -2.0),
+        ],
+        triangles: vec![[0, 1, 2], [0, 2, 3]],
+        ..MeshData::default()
+    };
+    // Closed, so directions cross it twice, and shaded with normals that lean away from the
+    // faces', which the density must not depend on.
+    let octahedron = MeshData {
+        positions: vec![
+            Vec3(1.0, 0.0, -3.0),
+            Vec3(-1.0, 0.0, -3.0),
+            Vec3(0.0, 1.0, -3.0),
+            Vec3(0.0, -1.0, -3.0),
+            Vec3(0.0, 0.0, -2.0),
+            Vec3(0.0, 0.0, -4.0),
+        ],
+        normals: vec![
+            Vec3(1.0, 1.0, 0.0).unit_vector(),
+            Vec3(-1.0, 0.0, 1.0).unit_vector(),
+            Vec3(0.0, 1.0, 1.0).unit_vector(),
+            Vec3(1.0, -1.0, 0.0).unit_vector(),
+            Vec3(1.0, 0.0, 1.0).unit_vector(),
+            Vec3(0.0, 1.0, -1.0).unit_vector(),
+        ],
+        uvs: Vec::new(),
+        triangles: vec![
+            [0, 2, 4],
+            [2, 1, 4],
+            [1, 3, 4],
+            [3, 0, 4],
+            [2, 0, 5],
+            [1, 2, 5],
+            [3, 1, 5],
+            [0, 3, 5],
+        ],
+    };
+    let shapes: Vec<Box<dyn Hittable>> = vec![
+        Box::new(Sphere {
+            center: Vec3(0.0, 0.0, -3.0),
+            radius: 1.0,
+            material: light(),
+        }),
+        Box::new(Triangle::new(
+            Vec3(-1.0, -1.0, -2.0),
+            Vec3(1.0, -1.0, -2.0),
+            Vec3(0.0, 1.0, -2.0),
+            light(),
+        )),
+        Box::new(TriangleMesh::new(square, light())),
+        Box::new(TriangleMesh::new(octahedron, light())),
+        Box::new(Quad::new(
+            Vec3(-1.0, -1.0, -2.0),
+            Vec3(2.0, 0.0, 0.0),
+            Vec3(0.5, 2.0, 0.0),
+            light(),
+        )),
+        Box::new(Cuboid::new(
+            Vec3(-0.5, -0.5, -3.5),
+            Vec3(0.5, 0.5, -2.5),
+            light(),
+        )),
+        Box::new(Disk::new(
+            Vec3(0.0, 0.0, -2.0),
+            Vec3(0.0, 1.0, 1.0),
+            1.0,
+            light(),
+        )),
+        // Stretched out of shape, which changes the density of directions.
+        Box::new(Transformed::new(
+            Box::new(Sphere {
+                center: Vec3(0.0, 0.0, 0.0),
+                radius: 1.0,
+                material: light(),
+            }),
+            Matrix4::translation(Vec3(0.0, 0.0, -3.0)) * Matrix4::scaling(Vec3(1.5, 0.5, 1.0)),
+        )),
+        Box::new(Transformed::new(
+            Box::new(HittableList::new(vec![
+                Box::new(Sphere {
+                    center: Vec3(1.0, 0.0, 0.0),
+                    radius: 0.5,
+                    material: light(),
+                }),
+                Box::new(Quad::new(
+                    Vec3(-1.0, -1.0, 0.0),
+                    Vec3(1.0, 0.0, 0.0),
+                    Vec3(0.0, 1.0, 0.0),
+                    light(),
+                )),
+            ])),
+            Matrix4::translation(Vec3(0.0, 0.5, -2.5))
+                * Matrix4::rotation(Vec3(1.0, 0.0, 0.0), 40.0),
+        )),
+        // Lights among unlit shapes in a tree, which the instance finds by their counts.
+        Box::new(Transformed::new(
+            Box::new(BvhNode::new(vec![
+                Box::new(Sphere {
+                    center: Vec3(-1.0, 0.0, 0.0),
+                    radius: 0.5,
+                    material: light(),
+                }),
+                Box::new(Sphere {
+                    center: Vec3(0.0, 0.0, 0.0),
+                    radius: 0.5,
+                    material: gray(),
+                }),
+                Box::new(Disk::new(
+                    Vec3(1.0, 0.0, 0.0),
+                    Vec3(0.0, 0.0, 1.0),
+                    0.5,
+                    light(),
+                )),
+                Box::new(Plane::new(
+                    Vec3(0.0, -5.0, 0.0),
+                    Vec3(0.0, 1.0, 0.0),
+                    gray(),
+                )),
+            ])),
+            Matrix4::translation(Vec3(0.0, 0.0, -3.0)),
+        )),
+    ];
+    let origin = Vec3(0.2, 0.1, 0.0);
+    let mut sampler = Sampler::new(7);
+
+    for shape in shapes.iter() {
+        let mut lights = Vec::new();
+        shape.collect_lights(&mut lights);
+        assert!(shape.light_count() == lights.len());
+        // Every sampled direction leads to the shape.
+        for _ in 0..100 {
+            let direction = shape.sample_direction(&origin, &mut sampler);
+            assert!(shape.direction_pdf(&origin, &direction) > 0.0);
+        }
+        // And the density integrates to one over the sphere of directions.
+        let count = 40_000;
+        let total: f32 = (0..count)
+            .map(|_| {
+                let z = 1.0 - 2.0 * sampler.gen::<f32>();
+                let phi = 2.0 * std::f32::consts::PI * sampler.gen::<f32>();
+                let radius = (1.0 - z * z).sqrt();
+                let direction = Vec3(radius * phi.cos(), radius * phi.sin(), z);
+                shape.direction_pdf(&origin, &direction)
+            })
+            .sum();
+        let integral = total / count as f32 * 4.0 * std::f32::consts::PI;
+        assert!((integral - 1.0).abs() < 0.1, "integral {}", integral);
+    }
+
+    let list = HittableList::new(shapes);
+    let mut lights = Vec::new();
+    list.collect_lights(&mut lights);
+    assert!(lights.len() == 10);
+    let unlit = Triangle::new(
+        Vec3(0.0, 0.0, 0.0),
+        Vec3(1.0, 0.0, 0.0),
+        Vec3(0.0, 1.0, 0.0),
+        gray(),
+    );
+    let mut lights = Vec::new();
+    unlit.collect_lights(&mut lights);
+    assert!(lights.is_empty());
+}
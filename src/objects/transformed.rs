@@ -0,0 +1,171 @@
+use super::{HitRecord, Hittable};
+use crate::aabb::Aabb;
+use crate::ray::Ray;
+use crate::sampler::Sampler;
+use crate::vec3::{Matrix4, Vec3, Vector};
+use rand::prelude::*;
+use std::sync::Arc;
+
+/// Another object moved, turned or scaled by an affine transform, which may change over time.
+///
+/// Rays are carried into the object's own space rather than the object into the world, and
+/// the object is held behind an `Arc`, so any number of instances can share one mesh.
+pub struct Transformed {
+    object: Arc<dyn Hittable>,
+    /// In order of time, and never empty.
+    keys: Vec<Keyframe>,
+    /// How many lights the object holds, all of which this instance samples through, counted
+    /// once here so that sampling can go straight to them. Always zero for moving instances,
+    /// which aren't sampled.
+    object_light_count: usize,
+}
+
+/// The transform in force at one moment, and its inverse.
+#[derive(Copy, Clone)]
+struct Keyframe {
+    time: f32,
+    to_world: Matrix4,
+    to_object: Matrix4,
+}
+
+impl Transformed {
+    /// Panics if the transform can't be undone, as when it scales by zero.
+    pub fn new(object: Box<dyn Hittable>, transform: Matrix4) -> Self {
+        Self::instance(Arc::from(object), transform)
+    }
+
+    /// One instance of an object that others may share.
+    pub fn instance(object: Arc<dyn Hittable>, transform: Matrix4) -> Self {
+        Self::animated(object, vec![(0.0, transform)])
+    }
+
+    /// An instance whose transform is given at several times. In between, the transforms are
+    /// blended as by `Matrix4::lerp`, so a turn needs a key every few degrees to keep its
+    /// shape. Before the first key and after the last, the object holds still.
+    ///
+    /// Moving instances aren't sampled as lights, since where they are depends on the ray's
+    /// time; an emissive one is still found by the paths that hit it.
+    ///
+    /// Panics if there are no keys, or one of them can't be undone.
+    pub fn animated(object: Arc<dyn Hittable>, mut keys: Vec<(f32, Matrix4)>) -> Self {
+        assert!(!keys.is_empty(), "an instance needs at least one transform");
+        keys.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let keys: Vec<Keyframe> = keys
+            .into_iter()
+            .map(|(time, transform)| Keyframe {
+                time,
+                to_world: transform,
+                to_object: transform
+                    .inverse()
+                    .expect("the transform flattens the object"),
+            })
+            .collect();
+        let object_light_count = if keys.len() == 1 {
+            object.light_count()
+        } else {
+            0
+        };
+        Transformed {
+            object,
+            keys,
+            object_light_count,
+        }
+    }
+
+    /// The transforms into the world and back at `time`, or `None` if the blend between two
+    /// keys happens to flatten the object.
+    fn transforms_at(&self, time: f32) -> Option<(Matrix4, Matrix4)> {
+        let next = self.keys.iter().position(|key| key.time > time);
+        let key = match next {
+            Some(0) => &self.keys[0],
+            None => &self.keys[self.keys.len() - 1],
+            Some(next) => {
+                let (before, after) = (&self.keys[next - 1], &self.keys[next]);
+                let along = (time - before.time) / (after.time - before.time);
+                let to_world = before.to_world.lerp(&after.to_world, along);
+                return Some((to_world, to_world.inverse()?));
+            }
+        };
+        Some((key.to_world, key.to_object))
+    }
+}
+
+impl Hittable for Transformed {
+    fn hit(&self, ray: &Ray, distance_min: f32, distance_max: f32) -> Option<HitRecord<'_>> {
+        let (_, to_object) = self.transforms_at(ray.time())?;
+        // The direction isn't renormalized, so distances along the ray mean the same in both
+        // spaces.
+        let local = Ray::with_time(
+            to_object.transform_point(ray.origin()),
+            to_object.transform_vector(ray.direction()),
+            ray.time(),
+        );
+        let record = self.object.hit(&local, distance_min, distance_max)?;
+        Some(HitRecord {
+            point: ray.point_at(record.distance),
+            normal: to_object.transform_normal(&record.normal).unit_vector(),
+            ..record
+        })
+    }
+
+    /// Every point moves in a straight line between keys, so the boxes around the object at
+    /// each key together cover its whole motion.
+    fn bounding_box(&self) -> Aabb {
+        let local = self.object.bounding_box();
+        if local.is_empty() || !local.is_bounded() {
+            return local;
+        }
+        let mut bounds = Aabb::empty();
+        for key in self.keys.iter() {
+            for corner in 0..8 {
+                let pick = |axis: usize| {
+                    if corner & (1 << axis) == 0 {
+                        local.min[axis]
+                    } else {
+                        local.max[axis]
+                    }
+                };
+                let point = key
+                    .to_world
+                    .transform_point(&Vec3(pick(0), pick(1), pick(2)));
+                bounds = Aabb::surrounding(&bounds, &Aabb::new(point, point));
+            }
+        }
+        bounds
+    }
+
+    fn collect_lights<'a>(&'a self, lights: &mut Vec<&'a dyn Hittable>) {
+        if self.light_count() > 0 {
+            lights.push(self);
+        }
+    }
+
+    /// The instance is one light, however many the object holds.
+    fn light_count(&self) -> usize {
+        usize::from(self.object_light_count > 0)
+    }
+
+    /// The object's lights are picked evenly. A transform that isn't rigid stretches the
+    /// directions leaving the origin, and their density with them.
+    fn direction_pdf(&self, origin: &Vec3, direction: &Vec3) -> f32 {
+        let to_object = &self.keys[0].to_object;
+        let local_origin = to_object.transform_point(origin);
+        let local_direction = to_object.transform_vector(&direction.unit_vector());
+        let pdf = self
+            .object
+            .lights_direction_pdf(&local_origin, &local_direction)
+            / self.object_light_count as f32;
+        let stretch = local_direction.length();
+        pdf * to_object.determinant().abs() / (stretch * stretch * stretch)
+    }
+
+    fn sample_direction(&self, origin: &Vec3, sampler: &mut Sampler) -> Vec3 {
+        let key = &self.keys[0];
+        let local_origin = key.to_object.transform_point(origin);
+        let index = sampler.gen_range(0, self.object_light_count);
+        let local_direction = self
+            .object
+            .sample_light_direction(index, &local_origin, sampler);
+        key.to_world.transform_vector(&local_direction)
+    }
+}
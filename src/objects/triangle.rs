@@ -0,0 +1,193 @@
+use super::{HitRecord, Hittable};
+use crate::aabb::Aabb;
+use crate::materials::Material;
+use crate::ray::Ray;
+use crate::sampler::Sampler;
+use crate::vec3::{Vec3, Vector};
+use rand::prelude::*;
+
+/// A single triangle, facing the side from which its vertices run counter-clockwise.
+pub struct Triangle {
+    pub vertices: [Vec3; 3],
+    pub material: Box<dyn Material>,
+}
+
+/// Where a ray crosses a triangle.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TriangleHit {
+    pub distance: f32,
+    /// Weights of the three vertices at the hit point, summing to 1.
+    pub barycentric: [f32; 3],
+}
+
+impl Triangle {
+    pub fn new(a: Vec3, b: Vec3, c: Vec3, material: Box<dyn Material>) -> Self {
+        Triangle {
+            vertices: [a, b, c],
+            material,
+        }
+    }
+
+    pub fn intersect(
+        &self,
+        ray: &Ray,
+        distance_min: f32,
+        distance_max: f32,
+    ) -> Option<TriangleHit> {
+        intersect(ray, &self.vertices, distance_min, distance_max)
+    }
+}
+
+impl Hittable for Triangle {
+    fn hit(&self, ray: &Ray, distance_min: f32, distance_max: f32) -> Option<HitRecord<'_>> {
+        let hit = self.intersect(ray, distance_min, distance_max)?;
+        Some(HitRecord {
+            distance: hit.distance,
+            point: ray.point_at(hit.distance),
+            normal: geometric_normal(&self.vertices),
+            u: hit.barycentric[1],
+            v: hit.barycentric[2],
+            material: &*self.material,
+        })
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        bounding_box(&self.vertices)
+    }
+
+    fn collect_lights<'a>(&'a self, lights: &mut Vec<&'a dyn Hittable>) {
+        if self.light_count() > 0 {
+            lights.push(self);
+        }
+    }
+
+    fn light_count(&self) -> usize {
+        usize::from(self.material.is_emissive())
+    }
+
+    fn direction_pdf(&self, origin: &Vec3, direction: &Vec3) -> f32 {
+        match self.intersect(&Ray::new(*origin, *direction), 0.001, f32::MAX) {
+            Some(hit) => solid_angle_pdf(
+                direction,
+                hit.distance,
+                &geometric_normal(&self.vertices),
+                area(&self.vertices),
+            ),
+            None => 0.0,
+        }
+    }
+
+    fn sample_direction(&self, origin: &Vec3, sampler: &mut Sampler) -> Vec3 {
+        sample_point(&self.vertices, sampler) - origin
+    }
+}
+
+pub(super) fn geometric_normal(vertices: &[Vec3; 3]) -> Vec3 {
+    Vec3::cross(&(vertices[1] - vertices[0]), &(vertices[2] - vertices[0])).unit_vector()
+}
+
+pub(super) fn area(vertices: &[Vec3; 3]) -> f32 {
+    0.5 * Vec3::cross(&(vertices[1] - vertices[0]), &(vertices[2] - vertices[0])).length()
+}
+
+/// A point picked uniformly over the triangle's area.
+pub(super) fn sample_point(vertices: &[Vec3; 3], sampler: &mut Sampler) -> Vec3 {
+    let root = sampler.gen::<f32>().sqrt();
+    let (wa, wb) = (1.0 - root, sampler.gen::<f32>() * root);
+    wa * vertices[0] + wb * vertices[1] + (1.0 - wa - wb) * vertices[2]
+}
+
+/// Converts a density of one over `area` per unit area, at a point `distance` along
+/// `direction` on a surface with the given normal, into a density per unit solid angle.
+pub(super) fn solid_angle_pdf(direction: &Vec3, distance: f32, normal: &Vec3, area: f32) -> f32 {
+    let length = direction.length();
+    let cosine = (Vec3::dot(direction, normal) / length).abs();
+    if cosine == 0.0 || area == 0.0 {
+        return 0.0;
+    }
+    let squared_distance = distance * distance * length * length;
+    squared_distance / (cosine * area)
+}
+
+pub(super) fn bounding_box(vertices: &[Vec3; 3]) -> Aabb {
+    vertices.iter().fold(Aabb::empty(), |bounds, vertex| {
+        Aabb::surrounding(&bounds, &Aabb::new(*vertex, *vertex))
+    })
+}
+
+/// The watertight ray/triangle test of Woop, Benthin and Wald (2013).
+///
+/// The triangle is moved into a space where the ray starts at the origin and runs along +z,
+/// and the hit is decided by the signs of three 2D edge functions. A ray through an edge
+/// shared by two triangles sees the same edge function, with opposite sign, from both sides,
+/// so it hits at least one of them: there are no cracks for rays to slip through.
+pub(super) fn intersect(
+    ray: &Ray,
+    vertices: &[Vec3; 3],
+    distance_min: f32,
+    distance_max: f32,
+) -> Option<TriangleHit> {
+    let direction = ray.direction();
+    // Make z the direction's largest axis, swapping x and y to keep the winding.
+    let kz = largest_axis(direction);
+    let (mut kx, mut ky) = ((kz + 1) % 3, (kz + 2) % 3);
+    if direction[kz] < 0.0 {
+        std::mem::swap(&mut kx, &mut ky);
+    }
+    let shear_x = direction[kx] / direction[kz];
+    let shear_y = direction[ky] / direction[kz];
+    let shear_z = 1.0 / direction[kz];
+
+    let a = vertices[0] - ray.origin();
+    let b = vertices[1] - ray.origin();
+    let c = vertices[2] - ray.origin();
+    let (ax, ay) = (a[kx] - shear_x * a[kz], a[ky] - shear_y * a[kz]);
+    let (bx, by) = (b[kx] - shear_x * b[kz], b[ky] - shear_y * b[kz]);
+    let (cx, cy) = (c[kx] - shear_x * c[kz], c[ky] - shear_y * c[kz]);
+
+    let mut u = cx * by - cy * bx;
+    let mut v = ax * cy - ay * cx;
+    let mut w = bx * ay - by * ax;
+    // An edge function that rounds to exactly zero can't be trusted to have the right sign.
+    if u == 0.0 || v == 0.0 || w == 0.0 {
+        let (ax, ay, bx, by, cx, cy) = (
+            f64::from(ax),
+            f64::from(ay),
+            f64::from(bx),
+            f64::from(by),
+            f64::from(cx),
+            f64::from(cy),
+        );
+        u = (cx * by - cy * bx) as f32;
+        v = (ax * cy - ay * cx) as f32;
+        w = (bx * ay - by * ax) as f32;
+    }
+    if (u < 0.0 || v < 0.0 || w < 0.0) && (u > 0.0 || v > 0.0 || w > 0.0) {
+        return None;
+    }
+    let determinant = u + v + w;
+    if determinant == 0.0 {
+        return None;
+    }
+
+    let scaled_distance = u * shear_z * a[kz] + v * shear_z * b[kz] + w * shear_z * c[kz];
+    let distance = scaled_distance / determinant;
+    if !(distance > distance_min && distance < distance_max) {
+        return None;
+    }
+    Some(TriangleHit {
+        distance,
+        barycentric: [u / determinant, v / determinant, w / determinant],
+    })
+}
+
+fn largest_axis(vector: &Vec3) -> usize {
+    let (x, y, z) = (vector.0.abs(), vector.1.abs(), vector.2.abs());
+    if x > y && x > z {
+        0
+    } else if y > z {
+        1
+    } else {
+        2
+    }
+}
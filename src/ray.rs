@@ -3,11 +3,21 @@ use crate::vec3::Vec3;
 pub struct Ray {
     point: Vec3,
     vector: Vec3,
+    /// When the ray is cast, within the camera's shutter interval, so that moving objects can
+    /// be found where they were at that moment.
+    time: f32,
 }
 
 impl Ray {
     pub fn new(point: Vec3, vector: Vec3) -> Self {
-        Ray { point, vector }
+        Self::with_time(point, vector, 0.0)
+    }
+    pub fn with_time(point: Vec3, vector: Vec3, time: f32) -> Self {
+        Ray {
+            point,
+            vector,
+            time,
+        }
     }
     pub fn origin(&self) -> &Vec3 {
         &self.point
@@ -15,6 +25,13 @@ impl Ray {
     pub fn direction(&self) -> &Vec3 {
         &self.vector
     }
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+    /// The componentwise reciprocal of the direction, used by the bounding box slab test.
+    pub fn inverse_direction(&self) -> Vec3 {
+        Vec3(1.0 / self.vector.0, 1.0 / self.vector.1, 1.0 / self.vector.2)
+    }
     pub fn point_at(&self, t: f32) -> Vec3 {
         let vector = t * self.vector;
         self.point + vector
@@ -0,0 +1,425 @@
+mod tests;
+use crate::background::Background;
+use crate::bvh::{BvhNode, BvhOptions};
+use crate::camera::Camera;
+use crate::framebuffer::{Framebuffer, Tile};
+use crate::lights::Light;
+use crate::materials::{Bsdf, Frame};
+use crate::objects::Hittable;
+use crate::ray::Ray;
+use crate::sampler::Sampler;
+use crate::scenes::Scene;
+use crate::vec3::{Vec3, Vector};
+use rand::prelude::*;
+use std::f32;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+
+#[derive(Copy, Clone, Debug)]
+pub struct RenderSettings {
+    pub width: usize,
+    pub height: usize,
+    /// Rays traced per pixel.
+    pub samples: usize,
+    /// Bounces after which a path is cut off and contributes no light.
+    pub max_depth: usize,
+    /// Edge length in pixels of the square tiles handed out to threads.
+    pub tile_size: usize,
+    /// Number of render threads. 0 uses one per available core.
+    pub threads: usize,
+    /// Seeds every pixel's sampler; the same seed always gives the same image.
+    pub seed: u64,
+    /// Frame number in an animation, mixed into the per-pixel seeds so that consecutive
+    /// frames don't share their noise.
+    pub frame: u64,
+    /// How the light reaching each hit straight from an emitter is found.
+    pub lighting: LightingStrategy,
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        RenderSettings {
+            width: 1200,
+            height: 800,
+            samples: 10,
+            max_depth: 50,
+            tile_size: 32,
+            threads: 0,
+            seed: 0,
+            frame: 0,
+            lighting: LightingStrategy::default(),
+        }
+    }
+}
+
+/// Ways of finding the light that reaches a hit straight from an emitter.
+///
+/// A path can find an emitter by scattering into it, or the renderer can pick a point on an
+/// emitter and check that nothing is in the way. Each is noisy where the other does well:
+/// scattering rarely hits small lights, and lights picked at random mostly fall outside the
+/// narrow lobe of a glossy surface. Multiple importance sampling takes a sample of each and
+/// weighs them by how likely each strategy was to produce it (Veach and Guibas, 1995).
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum LightingStrategy {
+    /// Only by scattering.
+    Bsdf,
+    /// By sampling the emitters wherever the material can be evaluated, and by scattering
+    /// elsewhere.
+    Lights,
+    /// Both, weighed by the balance heuristic.
+    Balance,
+    /// Both, weighed by the power heuristic with an exponent of 2.
+    #[default]
+    Power,
+}
+
+impl LightingStrategy {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "bsdf" => Some(LightingStrategy::Bsdf),
+            "lights" => Some(LightingStrategy::Lights),
+            "balance" => Some(LightingStrategy::Balance),
+            "power" => Some(LightingStrategy::Power),
+            _ => None,
+        }
+    }
+
+    /// The weight of a sample drawn with density `pdf` by one strategy, where the other would
+    /// have drawn it with density `other_pdf`.
+    fn weight(self, pdf: f32, other_pdf: f32) -> f32 {
+        match self {
+            LightingStrategy::Bsdf | LightingStrategy::Lights => 1.0,
+            LightingStrategy::Balance => pdf / (pdf + other_pdf),
+            LightingStrategy::Power => pdf * pdf / (pdf * pdf + other_pdf * other_pdf),
+        }
+    }
+
+    /// The weight of a light sample that the material would scatter into with density
+    /// `bsdf_pdf`.
+    fn light_weight(self, light_pdf: f32, bsdf_pdf: f32) -> f32 {
+        self.weight(light_pdf, bsdf_pdf)
+    }
+
+    /// The weight of emission found by scattering, where sampling the lights would have found
+    /// it with density `light_pdf`.
+    fn bsdf_weight(self, bsdf_pdf: f32, light_pdf: f32) -> f32 {
+        match self {
+            LightingStrategy::Lights if light_pdf > 0.0 => 0.0,
+            _ => self.weight(bsdf_pdf, light_pdf),
+        }
+    }
+}
+
+impl RenderSettings {
+    fn thread_count(&self) -> usize {
+        if self.threads > 0 {
+            self.threads
+        } else {
+            thread::available_parallelism().map_or(1, |count| count.get())
+        }
+    }
+}
+
+/// Turns scenes into framebuffers with a fixed set of render settings.
+pub struct Renderer {
+    settings: RenderSettings,
+}
+
+impl Renderer {
+    pub fn new(settings: RenderSettings) -> Self {
+        Renderer { settings }
+    }
+
+    pub fn settings(&self) -> &RenderSettings {
+        &self.settings
+    }
+
+    /// Renders a scene, first gathering its objects into a BVH built with the default options.
+    pub fn render_scene(&self, scene: Scene) -> Framebuffer {
+        let world = BvhNode::with_options(scene.objects, BvhOptions::default());
+        self.render(&scene.camera, &world, &scene.lights, &*scene.background)
+    }
+
+    /// Renders the world in tiles, spread over `settings.threads` threads.
+    ///
+    /// Threads take the next unrendered tile from a shared counter as they finish, so a thread
+    /// that gets cheap tiles (all sky, say) just ends up doing more of them.
+    pub fn render(
+        &self,
+        camera: &Camera,
+        world: &dyn Hittable,
+        lights: &[Box<dyn Light>],
+        background: &dyn Background,
+    ) -> Framebuffer {
+        let settings = &self.settings;
+        let tiles = tiles(settings.width, settings.height, settings.tile_size);
+        let next_tile = AtomicUsize::new(0);
+        let tracer = PathTracer::new(world, lights, background, settings);
+        let mut framebuffer = Framebuffer::new(settings.width, settings.height);
+        let (sender, receiver) = mpsc::channel();
+
+        thread::scope(|scope| {
+            for _ in 0..settings.thread_count().min(tiles.len()) {
+                let sender = sender.clone();
+                let tiles = &tiles;
+                let next_tile = &next_tile;
+                let tracer = &tracer;
+                scope.spawn(move || loop {
+                    let index = next_tile.fetch_add(1, Ordering::Relaxed);
+                    let tile = match tiles.get(index) {
+                        Some(tile) => *tile,
+                        None => break,
+                    };
+                    let pixels = render_tile(camera, tracer, settings, &tile);
+                    if sender.send((tile, pixels)).is_err() {
+                        break;
+                    }
+                });
+            }
+            drop(sender);
+            for (tile, pixels) in receiver {
+                framebuffer.write_tile(&tile, &pixels);
+            }
+        });
+        framebuffer
+    }
+}
+
+/// Splits the image into tiles of at most `tile_size` pixels square, row by row from the top.
+pub fn tiles(width: usize, height: usize, tile_size: usize) -> Vec<Tile> {
+    let tile_size = tile_size.max(1);
+    let mut tiles = Vec::new();
+    for y in (0..height).step_by(tile_size) {
+        for x in (0..width).step_by(tile_size) {
+            tiles.push(Tile {
+                x,
+                y,
+                width: tile_size.min(width - x),
+                height: tile_size.min(height - y),
+            });
+        }
+    }
+    tiles
+}
+
+fn render_tile(
+    camera: &Camera,
+    tracer: &PathTracer,
+    settings: &RenderSettings,
+    tile: &Tile,
+) -> Vec<Vec3> {
+    let mut pixels = Vec::with_capacity(tile.width * tile.height);
+    for y in tile.y..tile.y + tile.height {
+        // Framebuffer rows run from the top, the camera's v from the bottom.
+        let j = settings.height - 1 - y;
+        for i in tile.x..tile.x + tile.width {
+            let mut sampler = Sampler::for_pixel(settings.seed, settings.frame, i, y);
+            let mut color = Vec3(0.0, 0.0, 0.0);
+            for _ in 0..settings.samples {
+                let u_jitter: f32 = sampler.gen();
+                let v_jitter: f32 = sampler.gen();
+                let u = (i as f32 + u_jitter) / settings.width as f32;
+                let v = (j as f32 + v_jitter) / settings.height as f32;
+                let my_ray = camera.get_ray(u, v, &mut sampler);
+                color += tracer.calculate_color(&my_ray, 0, None, &mut sampler);
+            }
+            color /= settings.samples as f32;
+            pixels.push(color);
+        }
+    }
+    pixels
+}
+
+/// What a path needs to know about the scene it runs through.
+struct PathTracer<'a> {
+    world: &'a dyn Hittable,
+    /// The world's emissive shapes, which hits sample directly, along with the background if
+    /// it can be sampled.
+    lights: Vec<&'a dyn Hittable>,
+    /// Lights without a shape, which every hit samples in full.
+    delta_lights: &'a [Box<dyn Light>],
+    background: &'a dyn Background,
+    max_depth: usize,
+    strategy: LightingStrategy,
+}
+
+impl<'a> PathTracer<'a> {
+    fn new(
+        world: &'a dyn Hittable,
+        delta_lights: &'a [Box<dyn Light>],
+        background: &'a dyn Background,
+        settings: &RenderSettings,
+    ) -> Self {
+        let mut lights = Vec::new();
+        world.collect_lights(&mut lights);
+        PathTracer {
+            world,
+            lights,
+            delta_lights,
+            background,
+            max_depth: settings.max_depth,
+            strategy: settings.lighting,
+        }
+    }
+
+    /// Follows a path from `ray`, adding up the light emitted at each hit as scaled by the
+    /// surfaces before it.
+    ///
+    /// Where the material allows it, the light reaching a hit straight from an emitter is also
+    /// found by sampling the emitters (next-event estimation). The path still goes on to find
+    /// emitters by scattering, and `bsdf_pdf`, the density with which the previous hit
+    /// scattered into `ray`, lets the next hit weigh what it finds against what light sampling
+    /// would have. It is `None` where the previous hit didn't sample the lights.
+    fn calculate_color(
+        &self,
+        ray: &Ray,
+        depth: usize,
+        bsdf_pdf: Option<f32>,
+        sampler: &mut Sampler,
+    ) -> Vec3 {
+        let hit_record = match self.world.hit(ray, 0.001, f32::MAX) {
+            Some(hit_record) => hit_record,
+            None => {
+                let mut radiance = self.background.radiance(ray.direction());
+                if let Some(bsdf_pdf) = bsdf_pdf {
+                    if self.background.can_sample() {
+                        let light_pdf = self.light_pdf(ray.origin(), ray.direction());
+                        radiance *= self.strategy.bsdf_weight(bsdf_pdf, light_pdf);
+                    }
+                }
+                return radiance;
+            }
+        };
+        let mut emitted = hit_record.material.emitted(ray, &hit_record);
+        if let Some(bsdf_pdf) = bsdf_pdf {
+            if emitted != Vec3(0.0, 0.0, 0.0) {
+                let light_pdf = self.light_pdf(ray.origin(), ray.direction());
+                emitted *= self.strategy.bsdf_weight(bsdf_pdf, light_pdf);
+            }
+        }
+        let bsdf = match hit_record.material.bsdf(&hit_record) {
+            Some(bsdf) => bsdf,
+            None => return emitted,
+        };
+        let frame = Frame::new(&hit_record.normal);
+        let wo = frame.to_local(&-ray.direction().unit_vector());
+        let lit_directly = depth < self.max_depth && !bsdf.flags().is_delta();
+        let direct = if lit_directly {
+            self.sample_lights(&hit_record.point, ray.time(), &frame, &*bsdf, &wo, sampler)
+        } else {
+            None
+        };
+        let mut lit = emitted + direct.unwrap_or(Vec3(0.0, 0.0, 0.0));
+        if lit_directly {
+            lit += self.delta_lighting(&hit_record.point, ray.time(), &frame, &*bsdf, &wo);
+        }
+        let sample = bsdf.sample(&wo, sampler);
+        match sample {
+            Some(sample) if depth < self.max_depth => {
+                let scattered = Ray::with_time(
+                    hit_record.point,
+                    frame.to_world(&sample.direction),
+                    ray.time(),
+                );
+                let bsdf_pdf = direct.map(|_| sample.pdf);
+                lit + sample.weight * self.calculate_color(&scattered, depth + 1, bsdf_pdf, sampler)
+            }
+            _ => lit,
+        }
+    }
+
+    /// One sample of the light reaching `point` straight from an emitter and leaving along
+    /// `wo`, already weighed against scattering, or `None` if the lights weren't sampled:
+    /// because there are none, or the strategy says not to.
+    ///
+    /// The direction comes from one emitter picked at random, but is weighed by the average
+    /// density of all of them, so that a sample landing on some other emitter is still counted
+    /// correctly. A sampled background counts as one more emitter, found by shadow rays that
+    /// hit nothing.
+    fn sample_lights(
+        &self,
+        point: &Vec3,
+        time: f32,
+        frame: &Frame,
+        bsdf: &dyn Bsdf,
+        wo: &Vec3,
+        sampler: &mut Sampler,
+    ) -> Option<Vec3> {
+        let count = self.light_count();
+        if count == 0 || self.strategy == LightingStrategy::Bsdf {
+            return None;
+        }
+        let direction = match self.lights.get(sampler.gen_range(0, count)) {
+            Some(light) => light.sample_direction(point, sampler),
+            None => self.background.sample_direction(sampler),
+        };
+        let wi = frame.to_local(&direction.unit_vector());
+        let scattering = bsdf.eval(wo, &wi);
+        let light_pdf = self.light_pdf(point, &direction);
+        if light_pdf <= 0.0 || scattering == Vec3(0.0, 0.0, 0.0) {
+            return Some(Vec3(0.0, 0.0, 0.0));
+        }
+        let shadow_ray = Ray::with_time(*point, direction, time);
+        let radiance = match self.world.hit(&shadow_ray, 0.001, f32::MAX) {
+            Some(light_hit) => light_hit.material.emitted(&shadow_ray, &light_hit),
+            None if self.background.can_sample() => self.background.radiance(&direction),
+            None => return Some(Vec3(0.0, 0.0, 0.0)),
+        };
+        let bsdf_pdf = bsdf.pdf(wo, &wi);
+        let weight = self.strategy.light_weight(light_pdf, bsdf_pdf);
+        Some(scattering * radiance * (weight / light_pdf))
+    }
+
+    /// The light reaching `point` from the delta lights and leaving along `wo`. Scattering can
+    /// never find these lights, so every one of them is checked, whatever the strategy.
+    fn delta_lighting(
+        &self,
+        point: &Vec3,
+        time: f32,
+        frame: &Frame,
+        bsdf: &dyn Bsdf,
+        wo: &Vec3,
+    ) -> Vec3 {
+        let mut total = Vec3(0.0, 0.0, 0.0);
+        for light in self.delta_lights {
+            let sample = match light.illuminate(point) {
+                Some(sample) => sample,
+                None => continue,
+            };
+            let scattering = bsdf.eval(wo, &frame.to_local(&sample.direction));
+            if scattering == Vec3(0.0, 0.0, 0.0) {
+                continue;
+            }
+            let shadow_ray = Ray::with_time(*point, sample.direction, time);
+            if self
+                .world
+                .hit(&shadow_ray, 0.001, sample.distance)
+                .is_none()
+            {
+                total += scattering * sample.irradiance;
+            }
+        }
+        total
+    }
+
+    /// The density with which `sample_lights` picks `direction` from `origin`.
+    fn light_pdf(&self, origin: &Vec3, direction: &Vec3) -> f32 {
+        let shapes: f32 = self
+            .lights
+            .iter()
+            .map(|light| light.direction_pdf(origin, direction))
+            .sum();
+        let background = if self.background.can_sample() {
+            self.background.direction_pdf(direction)
+        } else {
+            0.0
+        };
+        (shapes + background) / self.light_count() as f32
+    }
+
+    /// The emitters `sample_lights` picks from.
+    fn light_count(&self) -> usize {
+        self.lights.len() + self.background.can_sample() as usize
+    }
+}
@@ -0,0 +1,619 @@
+#[cfg(test)]
+use crate::aabb::Aabb;
+#[cfg(test)]
+use crate::background::{Background, Constant, EnvironmentMap, Gradient};
+#[cfg(test)]
+use crate::bvh::BvhNode;
+#[cfg(test)]
+use crate::camera::Camera;
+#[cfg(test)]
+use crate::framebuffer::{Framebuffer, Tile};
+#[cfg(test)]
+use crate::lights::{DirectionalLight, Light, PointLight, SpotLight};
+#[cfg(test)]
+use crate::materials::{Dielectric, DiffuseLight, Lambertian, Metal};
+#[cfg(test)]
+use crate::objects::{HitRecord, Hittable, MovingSphere, Sphere, Triangle};
+#[cfg(test)]
+use crate::ray::Ray;
+#[cfg(test)]
+use crate::render::{tiles, LightingStrategy, RenderSettings, Renderer};
+#[cfg(test)]
+use crate::vec3::{Vec3, Vector};
+
+#[test]
+fn test_tiles_cover_image_once() {
+    let (width, height) = (70, 45);
+    let mut covered = vec![0; width * height];
+    for tile in tiles(width, height, 16) {
+        for y in tile.y..tile.y + tile.height {
+            for x in tile.x..tile.x + tile.width {
+                covered[y * width + x] += 1;
+            }
+        }
+    }
+
+    assert!(covered.iter().all(|count| *count == 1));
+    assert!(
+        tiles(70, 45, 16)[4]
+            == Tile {
+                x: 64,
+                y: 0,
+                width: 6,
+                height: 16
+            }
+    );
+}
+
+#[test]
+fn test_render_fills_framebuffer() {
+    let settings = RenderSettings {
+        width: 37,
+        height: 21,
+        samples: 1,
+        tile_size: 8,
+        threads: 3,
+        ..RenderSettings::default()
+    };
+    let camera = Camera::new(
+        Vec3(0.0, 0.0, 0.0),
+        Vec3(0.0, 0.0, -1.0),
+        Vec3(0.0, 1.0, 0.0),
+        90.0,
+        37.0 / 21.0,
+        0.0,
+        1.0,
+    );
+    let world = BvhNode::new(Vec::new());
+    let framebuffer = Renderer::new(settings).render(&camera, &world, &[], &Gradient::default());
+
+    // Against an empty world every pixel is sky, which is bluer towards the top.
+    assert!(framebuffer.pixels().iter().all(|pixel| pixel.2 == 1.0));
+    let pixels = framebuffer.pixels();
+    assert!(pixels[18].0 < pixels[20 * 37 + 18].0);
+}
+
+#[cfg(test)]
+fn small_scene() -> (Camera, BvhNode) {
+    let camera = Camera::new(
+        Vec3(0.0, 1.0, 4.0),
+        Vec3(0.0, 0.0, -1.0),
+        Vec3(0.0, 1.0, 0.0),
+        40.0,
+        1.5,
+        0.1,
+        4.0,
+    );
+    let objects: Vec<Box<dyn Hittable>> = vec![
+        Box::new(Sphere {
+            center: Vec3(0.0, -100.5, -1.0),
+            radius: 100.0,
+            material: Box::new(Lambertian::new(Vec3(0.8, 0.8, 0.0))),
+        }),
+        Box::new(Sphere {
+            center: Vec3(1.0, 0.0, -1.0),
+            radius: 0.5,
+            material: Box::new(Metal::new(Vec3(0.8, 0.6, 0.2), 0.3)),
+        }),
+        Box::new(Sphere {
+            center: Vec3(-1.0, 0.0, -1.0),
+            radius: 0.5,
+            material: Box::new(Dielectric::new(1.5)),
+        }),
+    ];
+    (camera, BvhNode::new(objects))
+}
+
+#[test]
+fn test_render_is_reproducible() {
+    let (camera, world) = small_scene();
+    let settings = RenderSettings {
+        width: 30,
+        height: 20,
+        samples: 4,
+        tile_size: 7,
+        threads: 1,
+        seed: 42,
+        ..RenderSettings::default()
+    };
+    let reference = Renderer::new(settings).render(&camera, &world, &[], &Gradient::default());
+    let threaded = Renderer::new(RenderSettings {
+        tile_size: 16,
+        threads: 4,
+        ..settings
+    })
+    .render(&camera, &world, &[], &Gradient::default());
+    let reseeded = Renderer::new(RenderSettings {
+        seed: 43,
+        ..settings
+    })
+    .render(&camera, &world, &[], &Gradient::default());
+    let next_frame = Renderer::new(RenderSettings {
+        frame: 1,
+        ..settings
+    })
+    .render(&camera, &world, &[], &Gradient::default());
+
+    assert!(reference.pixels() == threaded.pixels());
+    assert!(reference.pixels() != reseeded.pixels());
+    assert!(reference.pixels() != next_frame.pixels());
+}
+
+#[test]
+fn test_render_matches_scatter() {
+    // Rendered before materials sampled in a local shading frame. The move must not have
+    // changed which directions a seed picks, so the image is the same up to rounding.
+    let expected = [
+        (0.7272379, 0.83634275, 1.0),
+        (0.7256842, 0.83541054, 1.0),
+        (0.72803855, 0.8368231, 1.0),
+        (0.7229618, 0.83377707, 1.0),
+        (0.72725177, 0.836351, 1.0),
+        (0.7408822, 0.84452933, 1.0),
+        (0.6028999, 0.7217399, 0.5),
+        (0.6382006, 0.74292034, 0.5),
+        (0.6006416, 0.72038496, 0.5),
+        (0.63483274, 0.7408996, 0.5),
+        (0.65273523, 0.67209154, 0.6),
+        (0.7716218, 0.8629731, 1.0),
+        (0.4884205, 0.6130523, 0.0),
+        (0.44605905, 0.58763546, 0.0),
+        (0.4200215, 0.5720129, 0.0),
+        (0.4632596, 0.5022471, 0.0),
+        (0.36283386, 0.4159742, 0.0),
+        (0.48793077, 0.61275846, 0.0),
+        (0.4649822, 0.5989893, 0.0),
+        (0.49536258, 0.6172176, 0.0),
+        (0.5111723, 0.6267034, 0.0),
+        (0.44165343, 0.58499205, 0.0),
+        (0.42522955, 0.5298319, 0.0),
+        (0.45179093, 0.5910745, 0.0),
+    ];
+    let (camera, world) = small_scene();
+    let settings = RenderSettings {
+        width: 6,
+        height: 4,
+        samples: 4,
+        threads: 1,
+        seed: 7,
+        ..RenderSettings::default()
+    };
+    let framebuffer = Renderer::new(settings).render(&camera, &world, &[], &Gradient::default());
+
+    for (pixel, expected) in framebuffer.pixels().iter().zip(expected.iter()) {
+        let expected = Vec3(expected.0, expected.1, expected.2);
+        assert!(
+            (*pixel - expected).length() < 1e-4,
+            "{:?} != {:?}",
+            pixel,
+            expected
+        );
+    }
+}
+
+#[test]
+fn test_emission() {
+    let settings = RenderSettings {
+        width: 9,
+        height: 9,
+        samples: 16,
+        threads: 1,
+        ..RenderSettings::default()
+    };
+    let camera = Camera::new(
+        Vec3(0.0, 0.0, 0.0),
+        Vec3(0.0, 0.0, -1.0),
+        Vec3(0.0, 1.0, 0.0),
+        90.0,
+        1.0,
+        0.0,
+        1.0,
+    );
+    // A light in the middle of the view, in front of a white wall.
+    let objects: Vec<Box<dyn Hittable>> = vec![
+        Box::new(Sphere {
+            center: Vec3(0.0, 0.0, -3.0),
+            radius: 1.0,
+            material: Box::new(DiffuseLight::new(Vec3(4.0, 2.0, 1.0))),
+        }),
+        Box::new(Triangle::new(
+            Vec3(-20.0, -20.0, -5.0),
+            Vec3(20.0, -20.0, -5.0),
+            Vec3(0.0, 20.0, -5.0),
+            Box::new(Lambertian::new(Vec3(1.0, 1.0, 1.0))),
+        )),
+    ];
+    let world = BvhNode::new(objects);
+    let black = Constant(Vec3(0.0, 0.0, 0.0));
+    let framebuffer = Renderer::new(settings).render(&camera, &world, &[], &black);
+    let pixels = framebuffer.pixels();
+
+    assert!(pixels[4 * 9 + 4] == Vec3(4.0, 2.0, 1.0));
+    // The wall around the light is lit only by it, and so shares its hue.
+    let wall = pixels
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| index % 9 < 2 || index % 9 > 6)
+        .fold(Vec3(0.0, 0.0, 0.0), |sum, (_, pixel)| sum + *pixel);
+    assert!(wall.0 > wall.1 && wall.1 > wall.2 && wall.2 > 0.0);
+    let unlit = Renderer::new(settings).render(&camera, &BvhNode::new(Vec::new()), &[], &black);
+    assert!(unlit
+        .pixels()
+        .iter()
+        .all(|pixel| *pixel == Vec3(0.0, 0.0, 0.0)));
+}
+
+/// Forwards to a shape but keeps it out of the lights, so that the renderer can only find it
+/// by chance.
+#[cfg(test)]
+struct Unsampled(Box<dyn Hittable>);
+
+#[cfg(test)]
+impl Hittable for Unsampled {
+    fn hit(&self, ray: &Ray, distance_min: f32, distance_max: f32) -> Option<HitRecord<'_>> {
+        self.0.hit(ray, distance_min, distance_max)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.0.bounding_box()
+    }
+}
+
+/// Looking straight down from one unit above the origin, at `floor`.
+#[cfg(test)]
+fn overhead_camera() -> Camera {
+    Camera::new(
+        Vec3(0.0, 1.0, 0.0),
+        Vec3(0.0, 0.0, 0.0),
+        Vec3(0.0, 0.0, -1.0),
+        90.0,
+        1.0,
+        0.0,
+        1.0,
+    )
+}
+
+/// A gray floor through the origin, reaching far past what `overhead_camera` sees.
+#[cfg(test)]
+fn floor() -> Triangle {
+    Triangle::new(
+        Vec3(-20.0, 0.0, 20.0),
+        Vec3(20.0, 0.0, 20.0),
+        Vec3(0.0, 0.0, -20.0),
+        Box::new(Lambertian::new(Vec3(0.5, 0.5, 0.5))),
+    )
+}
+
+/// The mean red brightness of renders with two seeds, and the mean squared difference between
+/// them, which is how noisy the estimate is.
+#[cfg(test)]
+fn seed_noise(
+    settings: RenderSettings,
+    camera: &Camera,
+    world: &dyn Hittable,
+    background: &dyn Background,
+) -> (f32, f32) {
+    let renders: Vec<_> = [1, 2]
+        .iter()
+        .map(|&seed| {
+            Renderer::new(RenderSettings { seed, ..settings }).render(
+                camera,
+                world,
+                &[],
+                background,
+            )
+        })
+        .collect();
+    let pixels = renders[0].pixels().iter().zip(renders[1].pixels());
+    let count = (settings.width * settings.height) as f32;
+    let mean = pixels.clone().map(|(a, b)| a.0 + b.0).sum::<f32>() / (2.0 * count);
+    let noise = pixels.map(|(a, b)| (a.0 - b.0).powi(2)).sum::<f32>() / count;
+    (mean, noise)
+}
+
+#[test]
+fn test_light_sampling_reduces_noise() {
+    let settings = RenderSettings {
+        width: 16,
+        height: 16,
+        samples: 64,
+        threads: 1,
+        ..RenderSettings::default()
+    };
+    let camera = overhead_camera();
+    // A small bright light over a floor, out of the camera's view.
+    let world = |sampled: bool| {
+        let light = Sphere {
+            center: Vec3(0.0, 1.5, 0.0),
+            radius: 0.25,
+            material: Box::new(DiffuseLight::new(Vec3(20.0, 20.0, 20.0))),
+        };
+        let light: Box<dyn Hittable> = if sampled {
+            Box::new(light)
+        } else {
+            Box::new(Unsampled(Box::new(light)))
+        };
+        BvhNode::new(vec![light, Box::new(floor())])
+    };
+    let black = Constant(Vec3(0.0, 0.0, 0.0));
+    let measure = |sampled: bool| seed_noise(settings, &camera, &world(sampled), &black);
+    let (sampled_mean, sampled_noise) = measure(true);
+    let (unsampled_mean, unsampled_noise) = measure(false);
+
+    assert!(
+        sampled_noise * 10.0 < unsampled_noise,
+        "{} vs {}",
+        sampled_noise,
+        unsampled_noise
+    );
+    // Both estimate the same image.
+    assert!(
+        (sampled_mean - unsampled_mean).abs() < 0.1 * sampled_mean,
+        "{} vs {}",
+        sampled_mean,
+        unsampled_mean
+    );
+}
+
+#[test]
+fn test_multiple_importance_sampling() {
+    let settings = RenderSettings {
+        width: 24,
+        height: 16,
+        samples: 16,
+        threads: 1,
+        ..RenderSettings::default()
+    };
+    let camera = Camera::new(
+        Vec3(0.0, 2.0, 4.0),
+        Vec3(0.0, 0.0, -2.0),
+        Vec3(0.0, 1.0, 0.0),
+        40.0,
+        1.5,
+        0.0,
+        1.0,
+    );
+    // A glossy floor reflecting a small bright light and a large dim one, both out of view.
+    let objects: Vec<Box<dyn Hittable>> = vec![
+        Box::new(Sphere {
+            center: Vec3(-1.0, 3.0, -4.0),
+            radius: 0.05,
+            material: Box::new(DiffuseLight::new(Vec3(400.0, 400.0, 400.0))),
+        }),
+        Box::new(Sphere {
+            center: Vec3(3.0, 6.0, -8.0),
+            radius: 3.0,
+            material: Box::new(DiffuseLight::new(Vec3(1.0, 1.0, 1.0))),
+        }),
+        Box::new(Triangle::new(
+            Vec3(-20.0, 0.0, 20.0),
+            Vec3(20.0, 0.0, 20.0),
+            Vec3(0.0, 0.0, -20.0),
+            Box::new(Metal::new(Vec3(0.8, 0.8, 0.8), 0.05)),
+        )),
+    ];
+    let world = BvhNode::new(objects);
+    let black = Constant(Vec3(0.0, 0.0, 0.0));
+    let noise = |lighting: LightingStrategy| {
+        let settings = RenderSettings {
+            lighting,
+            ..settings
+        };
+        seed_noise(settings, &camera, &world, &black).1
+    };
+    let bsdf = noise(LightingStrategy::Bsdf);
+    let lights = noise(LightingStrategy::Lights);
+    let balance = noise(LightingStrategy::Balance);
+    let power = noise(LightingStrategy::Power);
+
+    assert!(
+        power < 0.5 * bsdf.min(lights),
+        "{} {} {}",
+        bsdf,
+        lights,
+        power
+    );
+    assert!(
+        balance < 0.5 * bsdf.min(lights),
+        "{} {} {}",
+        bsdf,
+        lights,
+        balance
+    );
+}
+
+/// Forwards to a background but keeps it from being sampled.
+#[cfg(test)]
+struct UnsampledBackground(EnvironmentMap);
+
+#[cfg(test)]
+impl Background for UnsampledBackground {
+    fn radiance(&self, direction: &Vec3) -> Vec3 {
+        self.0.radiance(direction)
+    }
+}
+
+#[test]
+fn test_environment_sampling_reduces_noise() {
+    let settings = RenderSettings {
+        width: 16,
+        height: 16,
+        samples: 32,
+        threads: 1,
+        ..RenderSettings::default()
+    };
+    let camera = overhead_camera();
+    let world = BvhNode::new(vec![Box::new(floor())]);
+    // A dark sky with one small, bright sun high overhead.
+    let mut pixels = vec![Vec3(0.05, 0.05, 0.05); 64 * 32];
+    for row in 4..7 {
+        for column in 20..23 {
+            pixels[row * 64 + column] = Vec3(500.0, 500.0, 500.0);
+        }
+    }
+    let sky = || EnvironmentMap::new(Framebuffer::from_pixels(64, 32, pixels.clone()), 0.0, 1.0);
+    let measure = |background: &dyn Background| seed_noise(settings, &camera, &world, background);
+    let (sampled_mean, sampled_noise) = measure(&sky());
+    let (unsampled_mean, unsampled_noise) = measure(&UnsampledBackground(sky()));
+
+    assert!(
+        sampled_noise * 10.0 < unsampled_noise,
+        "{} vs {}",
+        sampled_noise,
+        unsampled_noise
+    );
+    assert!(
+        (sampled_mean - unsampled_mean).abs() < 0.15 * sampled_mean,
+        "{} vs {}",
+        sampled_mean,
+        unsampled_mean
+    );
+}
+
+#[test]
+fn test_delta_lights() {
+    let settings = RenderSettings {
+        width: 9,
+        height: 9,
+        samples: 4,
+        threads: 1,
+        ..RenderSettings::default()
+    };
+    // Looking straight down at a white floor, with a ball hanging to one side.
+    let camera = Camera::new(
+        Vec3(0.0, 4.0, 0.0),
+        Vec3(0.0, 0.0, 0.0),
+        Vec3(0.0, 0.0, -1.0),
+        90.0,
+        1.0,
+        0.0,
+        1.0,
+    );
+    let objects: Vec<Box<dyn Hittable>> = vec![
+        Box::new(Triangle::new(
+            Vec3(-20.0, 0.0, 20.0),
+            Vec3(20.0, 0.0, 20.0),
+            Vec3(0.0, 0.0, -20.0),
+            Box::new(Lambertian::new(Vec3(1.0, 1.0, 1.0))),
+        )),
+        Box::new(Sphere {
+            center: Vec3(3.0, 1.0, 0.0),
+            radius: 0.5,
+            material: Box::new(Lambertian::new(Vec3(1.0, 1.0, 1.0))),
+        }),
+    ];
+    let world = BvhNode::new(objects);
+    let black = Constant(Vec3(0.0, 0.0, 0.0));
+    let render = |lights: &[Box<dyn Light>]| {
+        Renderer::new(settings)
+            .render(&camera, &world, lights, &black)
+            .pixels()
+            .to_vec()
+    };
+    // Pixel (x, y) looks at the floor around (x - 4, 0, y - 4).
+    let at = |pixels: &[Vec3], x: usize, y: usize| pixels[y * 9 + x];
+
+    // Under a point light, a white floor reflects E / π, for irradiance E = I cos θ / d².
+    let point: Vec<Box<dyn Light>> = vec![Box::new(PointLight {
+        position: Vec3(0.0, 2.0, 0.0),
+        color: Vec3(1.0, 1.0, 1.0),
+        intensity: 4.0,
+    })];
+    let pixels = render(&point);
+    let center = at(&pixels, 4, 4);
+    assert!(
+        (center.0 - 1.0 / std::f32::consts::PI).abs() < 0.05,
+        "{:?}",
+        center
+    );
+    assert!(at(&pixels, 6, 4).0 < center.0);
+    // The ball, which the camera sees from above, shades the floor beyond it.
+    let shadowed: Vec<Box<dyn Light>> = vec![Box::new(PointLight {
+        position: Vec3(6.0, 2.0, 0.0),
+        color: Vec3(1.0, 1.0, 1.0),
+        intensity: 4.0,
+    })];
+    let pixels = render(&shadowed);
+    assert!(at(&pixels, 4, 4) == Vec3(0.0, 0.0, 0.0));
+
+    // A spot only lights the floor inside its cone.
+    let spot: Vec<Box<dyn Light>> = vec![Box::new(SpotLight::new(
+        Vec3(0.0, 2.0, 0.0),
+        Vec3(0.0, 0.0, 0.0),
+        Vec3(1.0, 1.0, 1.0),
+        4.0,
+        20.0,
+        5.0,
+    ))];
+    let pixels = render(&spot);
+    assert!((at(&pixels, 4, 4).0 - center.0).abs() < 1e-3);
+    assert!(at(&pixels, 1, 4) == Vec3(0.0, 0.0, 0.0));
+
+    // A directional light lights the whole floor evenly, but for the ball's shadow.
+    let sun: Vec<Box<dyn Light>> = vec![Box::new(DirectionalLight {
+        direction: Vec3(0.0, -1.0, 0.0),
+        color: Vec3(0.0, 1.0, 0.0),
+        intensity: std::f32::consts::PI,
+    })];
+    let pixels = render(&sun);
+    assert!((at(&pixels, 0, 0).1 - 1.0).abs() < 1e-3);
+    assert!((at(&pixels, 4, 4).1 - 1.0).abs() < 1e-3);
+    assert!(at(&pixels, 0, 0).0 == 0.0);
+}
+
+#[test]
+fn test_motion_blur() {
+    let settings = RenderSettings {
+        width: 21,
+        height: 21,
+        samples: 200,
+        threads: 1,
+        ..RenderSettings::default()
+    };
+    let camera = Camera::new(
+        Vec3(0.0, 0.0, 0.0),
+        Vec3(0.0, 0.0, -1.0),
+        Vec3(0.0, 1.0, 0.0),
+        90.0,
+        1.0,
+        0.0,
+        1.0,
+    );
+    // A glowing ball crossing the view from left to right, against black.
+    let world = BvhNode::new(vec![Box::new(MovingSphere {
+        center0: Vec3(-2.0, 0.0, -3.0),
+        center1: Vec3(2.0, 0.0, -3.0),
+        time0: 0.0,
+        time1: 1.0,
+        radius: 0.3,
+        material: Box::new(DiffuseLight::new(Vec3(1.0, 1.0, 1.0))),
+    })]);
+    let black = Constant(Vec3(0.0, 0.0, 0.0));
+    let middle_row = |camera: &Camera| {
+        let pixels = Renderer::new(settings)
+            .render(camera, &world, &[], &black)
+            .pixels()
+            .to_vec();
+        pixels[10 * 21..11 * 21]
+            .iter()
+            .map(|pixel| pixel.0)
+            .collect::<Vec<f32>>()
+    };
+
+    // With the shutter closed at time 0 the ball is sharp, at its starting point.
+    let still = middle_row(&camera);
+    assert!(still[3] > 0.9 && still[10] == 0.0 && still[17] == 0.0);
+
+    // Held open, the ball is smeared along its path, with the same light in all.
+    let blurred = middle_row(&camera.with_shutter(0.0, 1.0));
+    for x in 4..=16 {
+        assert!(blurred[x] > 0.02 && blurred[x] < 0.5, "{:?}", blurred);
+    }
+    let total = |row: &[f32]| row.iter().sum::<f32>();
+    assert!(
+        (total(&blurred) - total(&still)).abs() < 0.2 * total(&still),
+        "{} against {}",
+        total(&blurred),
+        total(&still)
+    );
+}
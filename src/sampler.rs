@@ -0,0 +1,54 @@
+use rand::{Error, RngCore, SeedableRng};
+use rand_pcg::Pcg32;
+
+/// The source of every random number used while building and rendering a scene.
+///
+/// Each pixel gets its own sampler, seeded from the render seed, the frame number and the
+/// pixel's position, so a render is reproducible bit-for-bit no matter how many threads there
+/// are or in which order they finish their tiles.
+pub struct Sampler {
+    rng: Pcg32,
+}
+
+impl Sampler {
+    pub fn new(seed: u64) -> Self {
+        Sampler {
+            rng: Pcg32::seed_from_u64(seed),
+        }
+    }
+
+    pub fn for_pixel(seed: u64, frame: u64, x: usize, y: usize) -> Self {
+        let mut hash = mix(seed);
+        hash = mix(hash ^ frame);
+        hash = mix(hash ^ x as u64);
+        hash = mix(hash ^ y as u64);
+        Self::new(hash)
+    }
+}
+
+/// The SplitMix64 finalizer, which spreads nearby inputs (neighbouring pixels, consecutive
+/// frames) over unrelated seeds.
+fn mix(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+impl RngCore for Sampler {
+    fn next_u32(&mut self) -> u32 {
+        self.rng.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.rng.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.rng.try_fill_bytes(dest)
+    }
+}
@@ -0,0 +1,1071 @@
+use super::Scene;
+use crate::background::{Background, Constant, EnvironmentMap, Gradient, PhysicalSky};
+use crate::bvh::BvhNode;
+use crate::camera::Camera;
+use crate::import::{self, ImportError, ObjModel, PlyModel};
+use crate::lights::{DirectionalLight, Light, PointLight, SpotLight};
+use crate::materials::{Dielectric, DiffuseLight, Lambertian, Material, Metal};
+use crate::objects::{
+    Cuboid, Disk, Hittable, MovingSphere, Plane, Quad, Sphere, Transformed, TriangleMesh,
+};
+use crate::render::RenderSettings;
+use crate::textures::{
+    Checker, ColorRamp, ImageTexture, NoisePattern, NoiseTexture, Perlin, Texture, WrapMode,
+};
+use crate::vec3::{Matrix4, Vec3, Vector};
+use serde::de::value::{MapAccessDeserializer, SeqAccessDeserializer};
+use serde::de::{Error as _, MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// A scene read from a TOML file.
+///
+/// ```toml
+/// background = [0.0, 0.0, 0.0]  # optional, a sky gradient when left out
+/// # or a gradient from straight down to straight up:
+/// # background = { type = "gradient", down = [1.0, 1.0, 1.0], up = [0.5, 0.7, 1.0] }
+/// # or a panorama relative to the scene file, turned about the y axis and scaled:
+/// # background = { type = "environment", path = "sky.hdr", rotation = 90.0, intensity = 1.0 }
+/// # or a daylight sky, with the sun's elevation and azimuth in degrees, azimuth 0 along -z
+/// # and 90 along +x, and a turbidity from 2 (clear) to 10 (hazy):
+/// # background = { type = "sky", elevation = 30.0, azimuth = 90.0, turbidity = 3.0 }
+///
+/// [render]                      # optional, and overridden by the command line
+/// width = 400
+/// height = 225
+/// samples = 100
+/// max_depth = 50
+///
+/// [camera]
+/// look_from = [0.0, 1.0, 3.0]
+/// look_at = [0.0, 0.0, -1.0]
+/// up = [0.0, 1.0, 0.0]          # optional
+/// vertical_fov = 30.0           # degrees
+/// aperture = 0.1                # optional, 0 for a pinhole
+/// focus_distance = 4.0          # optional, defaults to the look_at distance
+/// shutter = [0.0, 1.0]          # optional, when the shutter opens and closes
+///
+/// [materials.gold]
+/// type = "metal"                # lambertian, metal, dielectric or diffuse_light
+/// albedo = [0.8, 0.6, 0.2]
+/// fuzziness = 0.1
+///
+/// [materials.tiles]             # any color can be a texture instead
+/// type = "lambertian"
+/// albedo = { type = "checker", even = [0.9, 0.9, 0.9], odd = [0.1, 0.1, 0.1], scale = 0.5 }
+///
+/// [materials.earth]
+/// type = "lambertian"
+/// # A PNG or JPEG relative to the scene file. wrap is optional: repeat, clamp or mirror.
+/// albedo = { type = "image", path = "earth.jpg", wrap = "repeat" }
+///
+/// [materials.stone]
+/// type = "lambertian"
+///
+/// [materials.stone.albedo]      # noise, turbulence, marble or wood
+/// type = "marble"
+/// scale = 4.0                   # optional, larger for finer detail
+/// octaves = 7                   # optional
+/// colors = [[0.1, 0.1, 0.1], [0.9, 0.9, 0.9]]  # optional, spread evenly along the pattern
+/// seed = 0                      # optional
+///
+/// [[objects]]
+/// type = "sphere"
+/// center = [1.0, 0.0, -1.0]
+/// radius = 0.5
+/// material = "gold"
+///
+/// [[objects]]
+/// type = "quad"                 # a parallelogram, facing where u turns counter-clockwise to v
+/// corner = [-1.0, 0.0, -2.0]
+/// u = [2.0, 0.0, 0.0]
+/// v = [0.0, 1.0, 0.0]
+/// material = "tiles"
+///
+/// [[objects]]
+/// type = "box"                  # axis-aligned, between two opposite corners
+/// min = [-2.0, -0.5, -2.0]
+/// max = [-1.5, 0.5, -1.5]
+/// material = "stone"
+///
+/// [[objects]]
+/// type = "disk"
+/// center = [0.0, 2.0, -1.0]
+/// normal = [0.0, -1.0, 0.0]     # the side the disk faces
+/// radius = 0.5
+/// material = "gold"
+///
+/// [[objects]]
+/// type = "plane"                # infinite, through point and facing along normal
+/// point = [0.0, -0.5, 0.0]
+/// normal = [0.0, 1.0, 0.0]
+/// material = "tiles"
+///
+/// [[objects]]
+/// type = "mesh"
+/// path = "teapot.obj"           # .obj or .ply, relative to the scene file
+/// material = "gold"             # optional, replaces the file's own materials or colors
+/// point_radius = 0.01           # optional, for .ply files with no faces
+///
+/// [objects.transform]           # optional, for any object: scale, rotate, then translate
+/// translate = [0.0, -0.5, -1.0]
+/// rotate = [0.0, 45.0, 0.0]     # degrees about x, then y, then z
+/// scale = [0.5, 0.5, 0.5]
+///
+/// [[objects]]
+/// type = "moving_sphere"        # blurred while the camera's shutter is open
+/// start = [0.0, 0.0, -2.0]
+/// end = [0.0, 0.5, -2.0]
+/// start_time = 0.0              # optional, the sphere is at start until then
+/// end_time = 1.0                # optional, and at end from then on
+/// radius = 0.25
+/// material = "gold"
+///
+/// [[objects]]
+/// type = "box"
+/// min = [-0.5, -0.5, -0.5]
+/// max = [0.5, 0.5, 0.5]
+/// material = "gold"
+///
+/// [[objects.keyframes]]         # optional, for any object: where it is at given times
+/// time = 0.0
+/// translate = [2.0, 0.0, -3.0]
+///
+/// [[objects.keyframes]]         # blended straight between keys, so keep turns small
+/// time = 1.0
+/// translate = [2.5, 0.0, -3.0]
+/// rotate = [0.0, 10.0, 0.0]
+///
+/// [[objects]]
+/// type = "mesh"
+/// path = "tree.obj"
+///
+/// [[objects.instances]]         # copies sharing one mesh, each after any transform
+/// translate = [-3.0, 0.0, -6.0]
+///
+/// [[objects.instances]]
+/// translate = [3.0, 0.0, -6.0]
+/// rotate = [0.0, 90.0, 0.0]
+///
+/// [[lights]]                    # point, spot or directional, lighting without any shape
+/// type = "point"
+/// position = [0.0, 4.0, 0.0]
+/// color = [1.0, 0.9, 0.8]       # optional, white by default
+/// intensity = 20.0              # the irradiance at a distance of 1
+///
+/// [[lights]]
+/// type = "spot"
+/// position = [2.0, 3.0, 2.0]
+/// look_at = [0.0, 0.0, -1.0]
+/// intensity = 40.0              # along the axis
+/// angle = 30.0                  # optional, degrees from the axis to the edge of the cone
+/// falloff = 5.0                 # optional, degrees at the edge over which the light fades
+///
+/// [[lights]]
+/// type = "directional"
+/// direction = [-1.0, -2.0, -1.0]  # the way the light travels
+/// intensity = 2.0               # the irradiance on a surface facing the light
+/// ```
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SceneFile {
+    background: Option<ColorOr<BackgroundSection>>,
+    #[serde(default)]
+    render: RenderSection,
+    camera: CameraSection,
+    #[serde(default)]
+    materials: BTreeMap<String, MaterialSection>,
+    #[serde(default)]
+    objects: Vec<ObjectEntry>,
+    #[serde(default)]
+    lights: Vec<LightSection>,
+    /// The file's text, kept for reporting where a bad reference is.
+    #[serde(skip)]
+    source: String,
+    /// Where paths in the file are relative to.
+    #[serde(skip)]
+    directory: PathBuf,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct RenderSection {
+    width: Option<usize>,
+    height: Option<usize>,
+    samples: Option<usize>,
+    max_depth: Option<usize>,
+    seed: Option<u64>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CameraSection {
+    look_from: [f32; 3],
+    look_at: [f32; 3],
+    #[serde(default = "default_up")]
+    up: [f32; 3],
+    vertical_fov: f32,
+    #[serde(default)]
+    aperture: f32,
+    focus_distance: Option<f32>,
+    /// When the shutter opens and closes.
+    #[serde(default)]
+    shutter: [f32; 2],
+}
+
+fn default_up() -> [f32; 3] {
+    [0.0, 1.0, 0.0]
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum MaterialSection {
+    Lambertian {
+        albedo: ColorSection,
+    },
+    Metal {
+        albedo: ColorSection,
+        fuzziness: f32,
+    },
+    Dielectric {
+        refractive_index: f32,
+    },
+    DiffuseLight {
+        emit: ColorSection,
+    },
+}
+
+/// A constant color, or a table describing something richer.
+#[derive(Clone)]
+enum ColorOr<T> {
+    Constant([f32; 3]),
+    Table(T),
+}
+
+/// A constant color, or a texture.
+type ColorSection = ColorOr<TextureSection>;
+
+/// Picks the variant by the kind of value, which unlike `#[serde(untagged)]` keeps the errors
+/// from inside a table.
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for ColorOr<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ColorVisitor<T>(PhantomData<T>);
+
+        impl<'de, T: Deserialize<'de>> Visitor<'de> for ColorVisitor<T> {
+            type Value = ColorOr<T>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a color as [red, green, blue], or a table")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<ColorOr<T>, A::Error> {
+                Deserialize::deserialize(SeqAccessDeserializer::new(seq)).map(ColorOr::Constant)
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<ColorOr<T>, A::Error> {
+                Deserialize::deserialize(MapAccessDeserializer::new(map)).map(ColorOr::Table)
+            }
+        }
+
+        deserializer.deserialize_any(ColorVisitor(PhantomData))
+    }
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum BackgroundSection {
+    Gradient {
+        down: [f32; 3],
+        up: [f32; 3],
+    },
+    Environment {
+        path: PathBuf,
+        /// Degrees about the y axis.
+        #[serde(default)]
+        rotation: f32,
+        #[serde(default = "default_intensity")]
+        intensity: f32,
+    },
+    Sky {
+        elevation: f32,
+        #[serde(default)]
+        azimuth: f32,
+        #[serde(default = "default_turbidity")]
+        turbidity: f32,
+        #[serde(default = "default_intensity")]
+        intensity: f32,
+    },
+}
+
+fn default_intensity() -> f32 {
+    1.0
+}
+
+fn default_turbidity() -> f32 {
+    3.0
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum TextureSection {
+    Checker {
+        even: Box<ColorSection>,
+        odd: Box<ColorSection>,
+        scale: f32,
+    },
+    Image {
+        path: PathBuf,
+        #[serde(default, deserialize_with = "wrap_mode")]
+        wrap: WrapMode,
+    },
+    Noise(NoiseSection),
+    Turbulence(NoiseSection),
+    Marble(NoiseSection),
+    Wood(NoiseSection),
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+struct NoiseSection {
+    #[serde(default = "default_noise_scale")]
+    scale: f32,
+    #[serde(default = "default_octaves")]
+    octaves: u32,
+    /// Black to white when left out.
+    #[serde(default)]
+    colors: Vec<[f32; 3]>,
+    #[serde(default)]
+    seed: u64,
+}
+
+fn default_noise_scale() -> f32 {
+    1.0
+}
+
+fn default_octaves() -> u32 {
+    7
+}
+
+impl NoiseSection {
+    fn build(&self, pattern: NoisePattern) -> NoiseTexture {
+        let ramp = if self.colors.is_empty() {
+            ColorRamp::default()
+        } else {
+            ColorRamp::even(
+                &self
+                    .colors
+                    .iter()
+                    .map(|color| vec3(*color))
+                    .collect::<Vec<_>>(),
+            )
+        };
+        NoiseTexture {
+            pattern,
+            scale: self.scale,
+            octaves: self.octaves,
+            ramp,
+            perlin: Perlin::new(self.seed),
+        }
+    }
+}
+
+fn wrap_mode<'de, D: Deserializer<'de>>(deserializer: D) -> Result<WrapMode, D::Error> {
+    let name = String::deserialize(deserializer)?;
+    WrapMode::from_name(&name).ok_or_else(|| {
+        D::Error::custom(format!(
+            "unknown wrap mode `{}`, expected repeat, clamp or mirror",
+            name
+        ))
+    })
+}
+
+/// The images the materials use, by their path in the scene file.
+type Images = BTreeMap<PathBuf, ImageTexture>;
+
+impl MaterialSection {
+    fn colors(&self) -> Vec<&ColorSection> {
+        match self {
+            MaterialSection::Lambertian { albedo } | MaterialSection::Metal { albedo, .. } => {
+                vec![albedo]
+            }
+            MaterialSection::Dielectric { .. } => Vec::new(),
+            MaterialSection::DiffuseLight { emit } => vec![emit],
+        }
+    }
+}
+
+impl ColorSection {
+    /// Loads the images in the color that aren't in `images` yet.
+    fn load_images(&self, directory: &Path, images: &mut Images) -> Result<(), SceneError> {
+        match self {
+            ColorSection::Table(TextureSection::Checker { even, odd, .. }) => {
+                even.load_images(directory, images)?;
+                odd.load_images(directory, images)
+            }
+            ColorSection::Table(TextureSection::Image { path, .. }) => {
+                if !images.contains_key(path) {
+                    let image = ImageTexture::load(&directory.join(path))?;
+                    images.insert(path.clone(), image);
+                }
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn build(&self, images: &Images) -> Box<dyn Texture> {
+        match self {
+            ColorSection::Constant(color) => Box::new(vec3(*color)),
+            ColorSection::Table(TextureSection::Checker { even, odd, scale }) => {
+                Box::new(Checker::new(even.build(images), odd.build(images), *scale))
+            }
+            ColorSection::Table(TextureSection::Image { path, wrap }) => {
+                let mut image = images[path].clone();
+                image.wrap = *wrap;
+                Box::new(image)
+            }
+            ColorSection::Table(TextureSection::Noise(noise)) => {
+                Box::new(noise.build(NoisePattern::Noise))
+            }
+            ColorSection::Table(TextureSection::Turbulence(noise)) => {
+                Box::new(noise.build(NoisePattern::Turbulence))
+            }
+            ColorSection::Table(TextureSection::Marble(noise)) => {
+                Box::new(noise.build(NoisePattern::Marble))
+            }
+            ColorSection::Table(TextureSection::Wood(noise)) => {
+                Box::new(noise.build(NoisePattern::Wood))
+            }
+        }
+    }
+}
+
+/// An object, optionally moved into place, set in motion, and perhaps repeated. The shape is
+/// built once and shared by all of its instances.
+#[derive(Deserialize)]
+struct ObjectEntry {
+    #[serde(flatten)]
+    shape: ObjectSection,
+    transform: Option<TransformSection>,
+    /// Where the object is at given times, each after its `transform`.
+    #[serde(default)]
+    keyframes: Vec<KeyframeSection>,
+    /// Where to place copies of the object, each after its `transform` and motion.
+    #[serde(default)]
+    instances: Vec<TransformSection>,
+}
+
+impl ObjectEntry {
+    /// Adds the built shape to `objects`, once for each placement. The error says what is wrong
+    /// with the entry, for `SceneFile::invalid_object` to point at it.
+    fn place(
+        &self,
+        mut parts: Vec<Box<dyn Hittable>>,
+        objects: &mut Vec<Box<dyn Hittable>>,
+    ) -> Result<(), &'static str> {
+        if self.transform.is_none() && self.keyframes.is_empty() && self.instances.is_empty() {
+            objects.extend(parts);
+            return Ok(());
+        }
+        let object: Arc<dyn Hittable> = match parts.len() {
+            1 => Arc::from(parts.pop().unwrap()),
+            _ => Arc::new(BvhNode::new(parts)),
+        };
+        let base = self
+            .transform
+            .as_ref()
+            .map_or_else(Matrix4::identity, TransformSection::matrix);
+        let motion: Vec<(f32, Matrix4)> = if self.keyframes.is_empty() {
+            vec![(0.0, base)]
+        } else {
+            self.keyframes
+                .iter()
+                .map(|key| (key.time, key.transform.matrix() * base))
+                .collect()
+        };
+        let placements: Vec<Matrix4> = if self.instances.is_empty() {
+            vec![Matrix4::identity()]
+        } else {
+            self.instances
+                .iter()
+                .map(TransformSection::matrix)
+                .collect()
+        };
+        for placement in placements {
+            let keys: Vec<(f32, Matrix4)> = motion
+                .iter()
+                .map(|(time, transform)| (*time, placement * *transform))
+                .collect();
+            if keys
+                .iter()
+                .any(|(_, transform)| transform.inverse().is_none())
+            {
+                return Err("a transform scales the object down to nothing");
+            }
+            objects.push(Box::new(Transformed::animated(Arc::clone(&object), keys)));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Deserialize)]
+struct KeyframeSection {
+    time: f32,
+    #[serde(flatten)]
+    transform: TransformSection,
+}
+
+/// Scaling, then rotation about the x, y and z axes in turn, then translation.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TransformSection {
+    #[serde(default)]
+    translate: [f32; 3],
+    /// Degrees about each axis.
+    #[serde(default)]
+    rotate: [f32; 3],
+    #[serde(default = "unit_scale")]
+    scale: [f32; 3],
+}
+
+fn unit_scale() -> [f32; 3] {
+    [1.0, 1.0, 1.0]
+}
+
+impl TransformSection {
+    fn matrix(&self) -> Matrix4 {
+        let [x, y, z] = self.rotate;
+        Matrix4::translation(vec3(self.translate))
+            * Matrix4::rotation(Vec3(0.0, 0.0, 1.0), z)
+            * Matrix4::rotation(Vec3(0.0, 1.0, 0.0), y)
+            * Matrix4::rotation(Vec3(1.0, 0.0, 0.0), x)
+            * Matrix4::scaling(vec3(self.scale))
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum ObjectSection {
+    Sphere {
+        center: [f32; 3],
+        radius: f32,
+        material: String,
+    },
+    MovingSphere {
+        start: [f32; 3],
+        end: [f32; 3],
+        #[serde(default)]
+        start_time: f32,
+        #[serde(default = "default_end_time")]
+        end_time: f32,
+        radius: f32,
+        material: String,
+    },
+    Quad {
+        corner: [f32; 3],
+        u: [f32; 3],
+        v: [f32; 3],
+        material: String,
+    },
+    #[serde(rename = "box")]
+    Cuboid {
+        min: [f32; 3],
+        max: [f32; 3],
+        material: String,
+    },
+    Disk {
+        center: [f32; 3],
+        normal: [f32; 3],
+        radius: f32,
+        material: String,
+    },
+    Plane {
+        point: [f32; 3],
+        normal: [f32; 3],
+        material: String,
+    },
+    Mesh {
+        path: PathBuf,
+        material: Option<String>,
+        #[serde(default = "default_point_radius")]
+        point_radius: f32,
+    },
+}
+
+fn default_end_time() -> f32 {
+    1.0
+}
+
+fn default_point_radius() -> f32 {
+    0.01
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum LightSection {
+    Point {
+        position: [f32; 3],
+        #[serde(default = "white")]
+        color: [f32; 3],
+        intensity: f32,
+    },
+    Spot {
+        position: [f32; 3],
+        look_at: [f32; 3],
+        #[serde(default = "white")]
+        color: [f32; 3],
+        intensity: f32,
+        #[serde(default = "default_spot_angle")]
+        angle: f32,
+        #[serde(default = "default_spot_falloff")]
+        falloff: f32,
+    },
+    Directional {
+        direction: [f32; 3],
+        #[serde(default = "white")]
+        color: [f32; 3],
+        intensity: f32,
+    },
+}
+
+fn white() -> [f32; 3] {
+    [1.0, 1.0, 1.0]
+}
+
+fn default_spot_angle() -> f32 {
+    30.0
+}
+
+fn default_spot_falloff() -> f32 {
+    5.0
+}
+
+impl LightSection {
+    fn build(&self) -> Box<dyn Light> {
+        match self {
+            LightSection::Point {
+                position,
+                color,
+                intensity,
+            } => Box::new(PointLight {
+                position: vec3(*position),
+                color: vec3(*color),
+                intensity: *intensity,
+            }),
+            LightSection::Spot {
+                position,
+                look_at,
+                color,
+                intensity,
+                angle,
+                falloff,
+            } => Box::new(SpotLight::new(
+                vec3(*position),
+                vec3(*look_at),
+                vec3(*color),
+                *intensity,
+                *angle,
+                *falloff,
+            )),
+            LightSection::Directional {
+                direction,
+                color,
+                intensity,
+            } => Box::new(DirectionalLight {
+                direction: vec3(*direction),
+                color: vec3(*color),
+                intensity: *intensity,
+            }),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum SceneError {
+    Io(io::Error),
+    /// A model the scene refers to couldn't be loaded.
+    Import(ImportError),
+    /// A syntax or content error, with the 1-based line it was found on where known.
+    Invalid {
+        message: String,
+        line: Option<usize>,
+    },
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SceneError::Io(error) => write!(f, "{}", error),
+            SceneError::Import(error) => write!(f, "{}", error),
+            SceneError::Invalid { message, .. } => f.write_str(message),
+        }
+    }
+}
+
+impl From<io::Error> for SceneError {
+    fn from(error: io::Error) -> Self {
+        SceneError::Io(error)
+    }
+}
+
+impl From<ImportError> for SceneError {
+    fn from(error: ImportError) -> Self {
+        SceneError::Import(error)
+    }
+}
+
+impl From<toml::de::Error> for SceneError {
+    fn from(error: toml::de::Error) -> Self {
+        SceneError::Invalid {
+            line: error.line_col().map(|(line, _)| line + 1),
+            message: error.to_string(),
+        }
+    }
+}
+
+impl SceneFile {
+    pub fn load(path: &Path) -> Result<Self, SceneError> {
+        let mut scene = Self::parse(&fs::read_to_string(path)?)?;
+        scene.directory = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        Ok(scene)
+    }
+
+    /// Parses a scene and checks that every material it refers to is defined.
+    pub fn parse(source: &str) -> Result<Self, SceneError> {
+        let mut scene: SceneFile = toml::from_str(source)?;
+        scene.source = source.to_string();
+        for object in &scene.objects {
+            let material = match &object.shape {
+                ObjectSection::Sphere { material, .. }
+                | ObjectSection::MovingSphere { material, .. }
+                | ObjectSection::Quad { material, .. }
+                | ObjectSection::Cuboid { material, .. }
+                | ObjectSection::Disk { material, .. }
+                | ObjectSection::Plane { material, .. } => Some(material),
+                ObjectSection::Mesh { material, .. } => material.as_ref(),
+            };
+            if let Some(material) = material {
+                if !scene.materials.contains_key(material) {
+                    return Err(scene.unknown_material(material));
+                }
+            }
+        }
+        // A zero-sized image or no samples at all would leave nothing to save, or NaNs.
+        let render = &scene.render;
+        for (key, value) in &[
+            ("width", render.width),
+            ("height", render.height),
+            ("samples", render.samples),
+        ] {
+            if *value == Some(0) {
+                return Err(scene.zero_render_setting(key));
+            }
+        }
+        Ok(scene)
+    }
+
+    /// The file's `[render]` settings laid over `defaults`.
+    pub fn render_settings(&self, defaults: RenderSettings) -> RenderSettings {
+        let render = &self.render;
+        RenderSettings {
+            width: render.width.unwrap_or(defaults.width),
+            height: render.height.unwrap_or(defaults.height),
+            samples: render.samples.unwrap_or(defaults.samples),
+            max_depth: render.max_depth.unwrap_or(defaults.max_depth),
+            seed: render.seed.unwrap_or(defaults.seed),
+            ..defaults
+        }
+    }
+
+    /// Builds the camera, framed for the given aspect ratio, and the objects, loading any
+    /// meshes and images the file names.
+    pub fn build(&self, aspect: f32) -> Result<Scene, SceneError> {
+        // Each image is loaded once, however many objects share it.
+        let mut images = Images::new();
+        for section in self.materials.values() {
+            for color in section.colors() {
+                color.load_images(&self.directory, &mut images)?;
+            }
+        }
+        let camera = &self.camera;
+        let look_from = vec3(camera.look_from);
+        let look_at = vec3(camera.look_at);
+        let focus_distance = camera
+            .focus_distance
+            .unwrap_or_else(|| (look_from - look_at).length());
+        let mut objects: Vec<Box<dyn Hittable>> = Vec::new();
+        for (index, entry) in self.objects.iter().enumerate() {
+            let mut parts = Vec::new();
+            self.build_object(index, &entry.shape, &images, &mut parts)?;
+            entry
+                .place(parts, &mut objects)
+                .map_err(|message| self.invalid_object(index, message))?;
+        }
+        Ok(Scene {
+            camera: Camera::new(
+                look_from,
+                look_at,
+                vec3(camera.up),
+                camera.vertical_fov,
+                aspect,
+                camera.aperture,
+                focus_distance,
+            )
+            .with_shutter(camera.shutter[0], camera.shutter[1]),
+            objects,
+            lights: self.lights.iter().map(LightSection::build).collect(),
+            background: self.build_background()?,
+        })
+    }
+
+    fn build_background(&self) -> Result<Box<dyn Background>, SceneError> {
+        Ok(match &self.background {
+            None => Box::new(Gradient::default()),
+            Some(ColorOr::Constant(color)) => Box::new(Constant(vec3(*color))),
+            Some(ColorOr::Table(BackgroundSection::Gradient { down, up })) => Box::new(Gradient {
+                down: vec3(*down),
+                up: vec3(*up),
+            }),
+            Some(ColorOr::Table(BackgroundSection::Environment {
+                path,
+                rotation,
+                intensity,
+            })) => Box::new(EnvironmentMap::load(
+                &self.directory.join(path),
+                *rotation,
+                *intensity,
+            )?),
+            Some(ColorOr::Table(BackgroundSection::Sky {
+                elevation,
+                azimuth,
+                turbidity,
+                intensity,
+            })) => {
+                let mut sky = PhysicalSky::new(*elevation, *azimuth, *turbidity);
+                sky.intensity = *intensity;
+                Box::new(sky)
+            }
+        })
+    }
+
+    /// Builds the `index`th `[[objects]]` entry's shape, or for a mesh its parts, into
+    /// `objects`.
+    fn build_object(
+        &self,
+        index: usize,
+        shape: &ObjectSection,
+        images: &Images,
+        objects: &mut Vec<Box<dyn Hittable>>,
+    ) -> Result<(), SceneError> {
+        if let ObjectSection::Disk { normal, .. } | ObjectSection::Plane { normal, .. } = shape {
+            if vec3(*normal).squared_length() == 0.0 {
+                return Err(self.invalid_object(index, "the normal has no direction"));
+            }
+        }
+        match shape {
+            ObjectSection::Sphere {
+                center,
+                radius,
+                material,
+            } => objects.push(Box::new(Sphere {
+                center: vec3(*center),
+                radius: *radius,
+                material: build_material(&self.materials[material], images),
+            })),
+            ObjectSection::MovingSphere {
+                start,
+                end,
+                start_time,
+                end_time,
+                radius,
+                material,
+            } => objects.push(Box::new(MovingSphere {
+                center0: vec3(*start),
+                center1: vec3(*end),
+                time0: *start_time,
+                time1: *end_time,
+                radius: *radius,
+                material: build_material(&self.materials[material], images),
+            })),
+            ObjectSection::Quad {
+                corner,
+                u,
+                v,
+                material,
+            } => objects.push(Box::new(Quad::new(
+                vec3(*corner),
+                vec3(*u),
+                vec3(*v),
+                build_material(&self.materials[material], images),
+            ))),
+            ObjectSection::Cuboid { min, max, material } => objects.push(Box::new(Cuboid::new(
+                vec3(*min),
+                vec3(*max),
+                build_material(&self.materials[material], images),
+            ))),
+            ObjectSection::Disk {
+                center,
+                normal,
+                radius,
+                material,
+            } => objects.push(Box::new(Disk::new(
+                vec3(*center),
+                vec3(*normal),
+                *radius,
+                build_material(&self.materials[material], images),
+            ))),
+            ObjectSection::Plane {
+                point,
+                normal,
+                material,
+            } => objects.push(Box::new(Plane::new(
+                vec3(*point),
+                vec3(*normal),
+                build_material(&self.materials[material], images),
+            ))),
+            ObjectSection::Mesh {
+                path,
+                material,
+                point_radius,
+            } => objects.extend(self.load_mesh(path, material.as_ref(), *point_radius, images)?),
+        }
+        Ok(())
+    }
+
+    /// Loads an OBJ or PLY file, giving every part of it `material` if there is one.
+    fn load_mesh(
+        &self,
+        path: &Path,
+        material: Option<&String>,
+        point_radius: f32,
+        images: &Images,
+    ) -> Result<Vec<Box<dyn Hittable>>, SceneError> {
+        let path = self.directory.join(path);
+        let is_ply = path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("ply"));
+        let material = match material {
+            Some(name) => &self.materials[name],
+            None if is_ply => return Ok(import::load_ply(&path, point_radius)?),
+            None => {
+                let meshes = import::load_obj(&path)?;
+                return Ok(meshes.into_iter().map(boxed).collect());
+            }
+        };
+        if !is_ply {
+            let groups = ObjModel::load(&path)?.groups;
+            return Ok(groups
+                .into_iter()
+                .map(|group| {
+                    boxed(TriangleMesh::new(
+                        group.data,
+                        build_material(material, images),
+                    ))
+                })
+                .collect());
+        }
+        let model = PlyModel::load(&path)?;
+        if !model.mesh.triangles.is_empty() {
+            return Ok(vec![boxed(TriangleMesh::new(
+                model.mesh,
+                build_material(material, images),
+            ))]);
+        }
+        Ok(model
+            .mesh
+            .positions
+            .into_iter()
+            .map(|center| {
+                boxed(Sphere {
+                    center,
+                    radius: point_radius,
+                    material: build_material(material, images),
+                })
+            })
+            .collect())
+    }
+
+    /// toml keeps no positions for values inside `[[objects]]` tables, so the reference is
+    /// found again in the text to report its line.
+    fn unknown_material(&self, name: &str) -> SceneError {
+        let quoted = format!("\"{}\"", name);
+        let line = self.source.lines().position(|line| {
+            let line = line.trim_start();
+            line.starts_with("material") && line.contains(&quoted)
+        });
+        SceneError::Invalid {
+            message: match line {
+                Some(index) => format!("unknown material `{}` at line {}", name, index + 1),
+                None => format!("unknown material `{}`", name),
+            },
+            line: line.map(|index| index + 1),
+        }
+    }
+
+    /// The error for the `index`th `[[objects]]` entry, at the line of its header, which is
+    /// found again in the text as materials are.
+    fn invalid_object(&self, index: usize, message: &str) -> SceneError {
+        let line = self
+            .source
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| line.trim_start().starts_with("[[objects]]"))
+            .nth(index)
+            .map(|(number, _)| number + 1);
+        SceneError::Invalid {
+            message: match line {
+                Some(line) => format!("{}, in the object at line {}", message, line),
+                None => message.to_string(),
+            },
+            line,
+        }
+    }
+
+    /// As with materials, the setting's line is found again in the text, within the
+    /// `[render]` table.
+    fn zero_render_setting(&self, key: &str) -> SceneError {
+        let mut in_render = false;
+        let line = self.source.lines().position(|line| {
+            let line = line.trim_start();
+            if line.starts_with('[') {
+                in_render = line.starts_with("[render]");
+                return false;
+            }
+            in_render && line.starts_with(key) && line[key.len()..].trim_start().starts_with('=')
+        });
+        SceneError::Invalid {
+            message: match line {
+                Some(index) => format!("`{}` must be at least 1, at line {}", key, index + 1),
+                None => format!("`{}` must be at least 1", key),
+            },
+            line: line.map(|index| index + 1),
+        }
+    }
+}
+
+fn build_material(section: &MaterialSection, images: &Images) -> Box<dyn Material> {
+    match section {
+        MaterialSection::Lambertian { albedo } => Box::new(Lambertian::new(albedo.build(images))),
+        MaterialSection::Metal { albedo, fuzziness } => {
+            Box::new(Metal::new(albedo.build(images), *fuzziness))
+        }
+        MaterialSection::Dielectric { refractive_index } => {
+            Box::new(Dielectric::new(*refractive_index))
+        }
+        MaterialSection::DiffuseLight { emit } => Box::new(DiffuseLight::new(emit.build(images))),
+    }
+}
+
+fn boxed(object: impl Hittable + 'static) -> Box<dyn Hittable> {
+    Box::new(object)
+}
+
+fn vec3(array: [f32; 3]) -> Vec3 {
+    Vec3(array[0], array[1], array[2])
+}
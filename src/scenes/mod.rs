@@ -0,0 +1,262 @@
+mod file;
+mod tests;
+use crate::background::{Background, Constant, Gradient};
+use crate::camera::Camera;
+use crate::lights::Light;
+use crate::materials::{Dielectric, DiffuseLight, Lambertian, Material, Metal};
+use crate::objects::{Cuboid, Hittable, Plane, Quad, Sphere, Transformed};
+use crate::sampler::Sampler;
+use crate::textures::{ColorRamp, NoisePattern, NoiseTexture};
+use crate::vec3::{Matrix4, Vec3, Vector};
+use rand::prelude::*;
+
+pub use file::{SceneError, SceneFile};
+
+/// A camera, everything it can see, and the light from beyond it.
+pub struct Scene {
+    pub camera: Camera,
+    pub objects: Vec<Box<dyn Hittable>>,
+    /// Point, spot and directional lights, which aren't objects as nothing can hit them.
+    pub lights: Vec<Box<dyn Light>>,
+    pub background: Box<dyn Background>,
+}
+
+/// Names of the scenes built into the renderer, for `builtin`.
+pub const BUILTIN_SCENES: &[&str] = &["random", "spheres", "cornell"];
+
+/// Builds one of the scenes in `BUILTIN_SCENES`, framed for an image with the given aspect
+/// ratio. Scenes with random placement draw from `sampler`.
+pub fn builtin(name: &str, aspect: f32, sampler: &mut Sampler) -> Option<Scene> {
+    match name {
+        "random" => Some(random_scene(aspect, sampler)),
+        "spheres" => Some(spheres(aspect)),
+        "cornell" => Some(cornell_box(aspect)),
+        _ => None,
+    }
+}
+
+/// Three spheres, one of them hollow glass, resting on a big yellow one.
+fn spheres(aspect: f32) -> Scene {
+    let objects: Vec<Box<dyn Hittable>> = vec![
+        Box::new(Sphere {
+            center: Vec3(0.0, 0.0, -1.0),
+            radius: 0.5,
+            material: Box::new(Lambertian::new(Vec3(0.1, 0.2, 0.5))),
+        }),
+        Box::new(Sphere {
+            center: Vec3(0.0, -100.5, -1.0),
+            radius: 100.0,
+            material: Box::new(Lambertian::new(Vec3(0.8, 0.8, 0.0))),
+        }),
+        Box::new(Sphere {
+            center: Vec3(1.0, 0.0, -1.0),
+            radius: 0.5,
+            material: Box::new(Metal::new(Vec3(0.8, 0.6, 0.2), 0.0)),
+        }),
+        Box::new(Sphere {
+            center: Vec3(-1.0, 0.0, -1.0),
+            radius: 0.5,
+            material: Box::new(Dielectric::new(1.5)),
+        }),
+        // A negative radius flips the normals, making the glass sphere above a bubble.
+        Box::new(Sphere {
+            center: Vec3(-1.0, 0.0, -1.0),
+            radius: -0.45,
+            material: Box::new(Dielectric::new(1.5)),
+        }),
+    ];
+    let look_from = Vec3(0.0, 1.0, 3.0);
+    let look_at = Vec3(0.0, 0.0, -1.0);
+    Scene {
+        camera: Camera::new(
+            look_from,
+            look_at,
+            Vec3(0.0, 1.0, 0.0),
+            30.0,
+            aspect,
+            0.1,
+            (look_from - look_at).length(),
+        ),
+        objects,
+        lights: Vec::new(),
+        background: Box::new(Gradient::default()),
+    }
+}
+
+/// The final scene from Ray Tracing in One Weekend: three big spheres among a few hundred
+/// small random ones.
+fn random_scene(aspect: f32, rng: &mut Sampler) -> Scene {
+    let number_of_spheres = 500;
+    let mut world: Vec<Box<dyn Hittable>> = Vec::with_capacity(number_of_spheres);
+    for a in -11..11 {
+        for b in -11..11 {
+            let material_choice: f32 = rng.gen();
+            let x_rand: f32 = rng.gen();
+            let z_rand: f32 = rng.gen();
+            let center = Vec3(a as f32 + 0.9 * x_rand, 0.2, b as f32 + 0.9 * z_rand);
+            if (center - Vec3(4.0, 0.2, 0.0)).length() > 0.9 {
+                if material_choice < 0.8 {
+                    world.push(Box::new(Sphere {
+                        // diffuse,
+                        center,
+                        radius: 0.2,
+                        material: Box::new(Lambertian::new(Vec3(
+                            rng.gen::<f32>() * rng.gen::<f32>(),
+                            rng.gen::<f32>() * rng.gen::<f32>(),
+                            rng.gen::<f32>() * rng.gen::<f32>(),
+                        ))),
+                    }));
+                } else if material_choice < 0.95 {
+                    // metal
+                    world.push(Box::new(Sphere {
+                        center,
+                        radius: 0.2,
+                        material: Box::new(Metal::new(
+                            Vec3(
+                                0.5 * (1.0 + rng.gen::<f32>()),
+                                0.5 * (1.0 + rng.gen::<f32>()),
+                                0.5 * (1.0 * rng.gen::<f32>()),
+                            ),
+                            0.5 * rng.gen::<f32>(),
+                        )),
+                    }));
+                } else {
+                    // glass
+                    world.push(Box::new(Sphere {
+                        center,
+                        radius: 0.2,
+                        material: Box::new(Dielectric::new(1.5)),
+                    }));
+                }
+            }
+        }
+    }
+    // Mottled gray stone for the ground.
+    let mut ground = NoiseTexture::new(NoisePattern::Turbulence, 1.0);
+    ground.ramp = ColorRamp::new(vec![
+        (0.1, Vec3(0.6, 0.6, 0.57)),
+        (0.5, Vec3(0.25, 0.25, 0.25)),
+    ]);
+    world.push(Box::new(Plane::new(
+        Vec3(0.0, 0.0, 0.0),
+        Vec3(0.0, 1.0, 0.0),
+        Box::new(Lambertian::new(ground)),
+    )));
+    world.push(Box::new(Sphere {
+        center: Vec3(0.0, 1.0, 0.0),
+        radius: 1.0,
+        material: Box::new(Dielectric::new(1.5)),
+    }));
+    world.push(Box::new(Sphere {
+        center: Vec3(-4.0, 1.0, 0.0),
+        radius: 1.0,
+        material: Box::new(Lambertian::new(Vec3(0.4, 0.2, 0.1))),
+    }));
+    world.push(Box::new(Sphere {
+        center: Vec3(4.0, 1.0, 0.0),
+        radius: 1.0,
+        material: Box::new(Metal::new(Vec3(0.7, 0.6, 0.5), 0.0)),
+    }));
+    Scene {
+        camera: Camera::new(
+            Vec3(13.0, 2.0, 3.0),
+            Vec3(0.0, 0.0, 0.0),
+            Vec3(0.0, 1.0, 0.0),
+            20.0,
+            aspect,
+            0.1,
+            10.0,
+        ),
+        objects: world,
+        lights: Vec::new(),
+        background: Box::new(Gradient::default()),
+    }
+}
+
+/// The Cornell box: a white room with a red and a green wall, lit only by a square light in
+/// the ceiling, holding two white blocks.
+fn cornell_box(aspect: f32) -> Scene {
+    let red = || Box::new(Lambertian::new(Vec3(0.65, 0.05, 0.05)));
+    let white = || Box::new(Lambertian::new(Vec3(0.73, 0.73, 0.73)));
+    let green = || Box::new(Lambertian::new(Vec3(0.12, 0.45, 0.15)));
+    let light = Box::new(DiffuseLight::new(Vec3(15.0, 15.0, 15.0)));
+    let size = 555.0;
+
+    let objects: Vec<Box<dyn Hittable>> = vec![
+        Box::new(Quad::new(
+            Vec3(size, 0.0, 0.0),
+            Vec3(0.0, size, 0.0),
+            Vec3(0.0, 0.0, size),
+            green(),
+        )),
+        Box::new(Quad::new(
+            Vec3(0.0, 0.0, 0.0),
+            Vec3(0.0, size, 0.0),
+            Vec3(0.0, 0.0, size),
+            red(),
+        )),
+        // Hung just below the ceiling: the light shines from both sides, and a wider gap would
+        // light up a sliver of ceiling that paths only rarely find, leaving fireflies.
+        Box::new(Quad::new(
+            Vec3(213.0, size - 0.01, 227.0),
+            Vec3(130.0, 0.0, 0.0),
+            Vec3(0.0, 0.0, 105.0),
+            light,
+        )),
+        Box::new(Quad::new(
+            Vec3(0.0, 0.0, 0.0),
+            Vec3(size, 0.0, 0.0),
+            Vec3(0.0, 0.0, size),
+            white(),
+        )),
+        Box::new(Quad::new(
+            Vec3(0.0, size, 0.0),
+            Vec3(size, 0.0, 0.0),
+            Vec3(0.0, 0.0, size),
+            white(),
+        )),
+        Box::new(Quad::new(
+            Vec3(0.0, 0.0, size),
+            Vec3(size, 0.0, 0.0),
+            Vec3(0.0, size, 0.0),
+            white(),
+        )),
+        Box::new(block(
+            Vec3(165.0, 330.0, 165.0),
+            15.0,
+            Vec3(265.0, 0.0, 295.0),
+            white(),
+        )),
+        Box::new(block(
+            Vec3(165.0, 165.0, 165.0),
+            -18.0,
+            Vec3(130.0, 0.0, 65.0),
+            white(),
+        )),
+    ];
+    let look_from = Vec3(278.0, 278.0, -800.0);
+    let look_at = Vec3(278.0, 278.0, 0.0);
+    Scene {
+        camera: Camera::new(
+            look_from,
+            look_at,
+            Vec3(0.0, 1.0, 0.0),
+            40.0,
+            aspect,
+            0.0,
+            (look_from - look_at).length(),
+        ),
+        objects,
+        lights: Vec::new(),
+        background: Box::new(Constant(Vec3(0.0, 0.0, 0.0))),
+    }
+}
+
+/// A box of the given size with one corner at the origin, turned about the y axis by
+/// `degrees` and then moved by `offset`.
+fn block(size: Vec3, degrees: f32, offset: Vec3, material: Box<dyn Material>) -> Transformed {
+    Transformed::new(
+        Box::new(Cuboid::new(Vec3(0.0, 0.0, 0.0), size, material)),
+        Matrix4::translation(offset) * Matrix4::rotation(Vec3(0.0, 1.0, 0.0), degrees),
+    )
+}
@@ -0,0 +1,496 @@
+#[cfg(test)]
+use crate::import::ImportError;
+#[cfg(test)]
+use crate::render::RenderSettings;
+#[cfg(test)]
+use crate::scenes::{SceneError, SceneFile};
+#[cfg(test)]
+use crate::vec3::Vec3;
+#[cfg(test)]
+use std::path::Path;
+
+#[cfg(test)]
+fn error_line(source: &str) -> Option<usize> {
+    match SceneFile::parse(source) {
+        Err(SceneError::Invalid { line, .. }) => line,
+        Err(error) => panic!("unexpected error: {}", error),
+        Ok(_) => panic!("expected an error"),
+    }
+}
+
+#[test]
+fn test_load_example_scene() {
+    let file = SceneFile::parse(include_str!("../../scenes/three_spheres.toml")).unwrap();
+    let settings = file.render_settings(RenderSettings::default());
+    let scene = file
+        .build(settings.width as f32 / settings.height as f32)
+        .unwrap();
+
+    assert!(settings.width == 400 && settings.height == 225 && settings.samples == 100);
+    assert!(settings.max_depth == RenderSettings::default().max_depth);
+    assert!(scene.objects.len() == 5);
+}
+
+#[test]
+fn test_zero_render_settings() {
+    let source = "\
+[render]
+width = 64
+height = 48
+samples = 4
+
+[camera]
+look_from = [0.0, 0.0, 0.0]
+look_at = [0.0, 0.0, -1.0]
+vertical_fov = 90.0
+";
+    assert!(SceneFile::parse(source).is_ok());
+    for (setting, line) in &[("width", 2), ("height", 3), ("samples", 4)] {
+        let zeroed = source.replace(&format!("{} = ", setting), &format!("{} = 0 #", setting));
+        assert!(error_line(&zeroed) == Some(*line), "{}", setting);
+        let error = SceneFile::parse(&zeroed).err().unwrap().to_string();
+        assert!(error.contains("at least 1"), "{}", error);
+    }
+}
+
+#[test]
+fn test_unknown_material_type() {
+    let source = "\
+[camera]
+look_from = [0.0, 0.0, 0.0]
+look_at = [0.0, 0.0, -1.0]
+vertical_fov = 90.0
+
+[materials.glass]
+type = \"glas\"
+refractive_index = 1.5
+";
+    assert!(error_line(source) == Some(7));
+}
+
+#[test]
+fn test_missing_field() {
+    let source = "\
+[camera]
+look_from = [0.0, 0.0, 0.0]
+look_at = [0.0, 0.0, -1.0]
+vertical_fov = 90.0
+
+[materials.white]
+type = \"lambertian\"
+albedo = [1.0, 1.0, 1.0]
+
+[[objects]]
+type = \"sphere\"
+center = [0.0, 0.0, -1.0]
+material = \"white\"
+";
+    assert!(error_line(source) == Some(10));
+    let error = SceneFile::parse(source).err().unwrap().to_string();
+    assert!(error.contains("missing field `radius`"));
+}
+
+#[test]
+fn test_unknown_material_name() {
+    let source = "\
+[camera]
+look_from = [0.0, 0.0, 0.0]
+look_at = [0.0, 0.0, -1.0]
+vertical_fov = 90.0
+
+[[objects]]
+type = \"sphere\"
+center = [0.0, 0.0, -1.0]
+radius = 0.5
+material = \"chrome\"
+";
+    assert!(error_line(source) == Some(10));
+    let error = SceneFile::parse(source).err().unwrap().to_string();
+    assert!(error == "unknown material `chrome` at line 10");
+}
+
+#[test]
+fn test_missing_camera() {
+    assert!(SceneFile::parse("[render]\nwidth = 10\n").is_err());
+}
+
+#[test]
+fn test_load_mesh_scene() {
+    let file = SceneFile::load(Path::new("scenes/cube.toml")).unwrap();
+    let scene = file.build(1.0).unwrap();
+
+    // The cube's two materials give two meshes, next to the two spheres.
+    assert!(scene.objects.len() == 4);
+}
+
+#[test]
+fn test_load_motion_blur_scene() {
+    let file = SceneFile::load(Path::new("scenes/motion_blur.toml")).unwrap();
+    let scene = file.build(16.0 / 9.0).unwrap();
+
+    assert!(scene.camera.shutter() == (0.0, 1.0));
+    assert!(scene.objects.len() == 5);
+    // The sliding box's bounds cover its whole path.
+    let bounds = scene.objects[4].bounding_box();
+    assert!(bounds.min.0 < 0.1 && bounds.max.0 > 1.1);
+}
+
+#[test]
+fn test_missing_mesh() {
+    let source = "\
+[camera]
+look_from = [0.0, 0.0, 0.0]
+look_at = [0.0, 0.0, -1.0]
+vertical_fov = 90.0
+
+[[objects]]
+type = \"mesh\"
+path = \"no/such/model.obj\"
+";
+    match SceneFile::parse(source).unwrap().build(1.0) {
+        Err(SceneError::Import(ImportError::Io { path, .. })) => {
+            assert!(path == Path::new("no/such/model.obj"))
+        }
+        _ => panic!("expected the mesh to be missing"),
+    }
+}
+
+#[test]
+fn test_textured_materials() {
+    let source = "\
+[camera]
+look_from = [0.0, 0.0, 0.0]
+look_at = [0.0, 0.0, -1.0]
+vertical_fov = 90.0
+
+[materials.tiles]
+type = \"lambertian\"
+albedo = { type = \"checker\", even = [1.0, 1.0, 1.0], odd = [0.0, 0.0, 0.0], scale = 0.5 }
+
+[materials.light]
+type = \"diffuse_light\"
+
+[materials.light.emit]
+type = \"checker\"
+odd = [1.0, 1.0, 1.0]
+scale = 2.0
+even = { type = \"image\", path = \"no/such/image.png\", wrap = \"clamp\" }
+
+[[objects]]
+type = \"sphere\"
+center = [0.0, 0.0, -1.0]
+radius = 0.5
+material = \"tiles\"
+";
+    // Images are only loaded when the scene is built.
+    match SceneFile::parse(source).unwrap().build(1.0) {
+        Err(SceneError::Import(ImportError::Io { path, .. })) => {
+            assert!(path == Path::new("no/such/image.png"))
+        }
+        _ => panic!("expected the image to be missing"),
+    }
+    let checkered = source.replace(
+        "{ type = \"image\", path = \"no/such/image.png\", wrap = \"clamp\" }",
+        "[0.5, 0.5, 0.5]",
+    );
+    assert!(SceneFile::parse(&checkered).unwrap().build(1.0).is_ok());
+    let marbled = checkered.replace(
+        "odd = [1.0, 1.0, 1.0]",
+        "odd = { type = \"marble\", scale = 4.0, colors = [[0.1, 0.1, 0.1], [0.9, 0.9, 0.9]] }",
+    );
+    assert!(SceneFile::parse(&marbled).unwrap().build(1.0).is_ok());
+    let error = SceneFile::parse(&marbled.replace("scale = 4.0", "size = 4.0"))
+        .err()
+        .unwrap()
+        .to_string();
+    assert!(error.starts_with("unknown field `size`"));
+    let badly_wrapped = source.replace("clamp", "tile");
+    let error = SceneFile::parse(&badly_wrapped).err().unwrap().to_string();
+    assert!(error.starts_with("unknown wrap mode `tile`, expected repeat, clamp or mirror"));
+}
+
+#[test]
+fn test_backgrounds() {
+    let scene = |background: &str| {
+        let source = format!(
+            "background = {}\n\
+             [camera]\n\
+             look_from = [0.0, 0.0, 0.0]\n\
+             look_at = [0.0, 0.0, -1.0]\n\
+             vertical_fov = 90.0\n",
+            background
+        );
+        SceneFile::parse(&source).and_then(|file| file.build(1.0))
+    };
+    let up = Vec3(0.0, 1.0, 0.0);
+
+    let constant = scene("[0.1, 0.2, 0.3]").ok().unwrap();
+    assert!(constant.background.radiance(&up) == Vec3(0.1, 0.2, 0.3));
+    let gradient = scene("{ type = \"gradient\", down = [1.0, 0.0, 0.0], up = [0.0, 0.0, 1.0] }")
+        .ok()
+        .unwrap();
+    assert!(gradient.background.radiance(&up) == Vec3(0.0, 0.0, 1.0));
+    let sky = scene("{ type = \"sky\", elevation = 20.0, turbidity = 4.0, intensity = 0.5 }")
+        .ok()
+        .unwrap();
+    assert!(sky.background.radiance(&up).2 > 0.0);
+    let error = scene("{ type = \"sky\", azimuth = 20.0 }")
+        .err()
+        .unwrap()
+        .to_string();
+    assert!(error.starts_with("missing field `elevation`"));
+    match scene("{ type = \"environment\", path = \"no/such/sky.hdr\", rotation = 45.0 }") {
+        Err(SceneError::Import(ImportError::Io { path, .. })) => {
+            assert!(path == Path::new("no/such/sky.hdr"))
+        }
+        _ => panic!("expected the panorama to be missing"),
+    }
+    let error = scene("{ type = \"environment\", path = \"sky.hdr\", exposure = 2.0 }")
+        .err()
+        .unwrap()
+        .to_string();
+    assert!(error.starts_with("unknown field `exposure`"));
+}
+
+#[test]
+fn test_lights() {
+    let source = "\
+[camera]
+look_from = [0.0, 0.0, 0.0]
+look_at = [0.0, 0.0, -1.0]
+vertical_fov = 90.0
+
+[[lights]]
+type = \"point\"
+position = [0.0, 4.0, 0.0]
+intensity = 20.0
+
+[[lights]]
+type = \"spot\"
+position = [2.0, 3.0, 2.0]
+look_at = [0.0, 0.0, -1.0]
+color = [1.0, 0.5, 0.5]
+intensity = 40.0
+angle = 20.0
+
+[[lights]]
+type = \"directional\"
+direction = [-1.0, -2.0, -1.0]
+intensity = 2.0
+";
+    let scene = SceneFile::parse(source).unwrap().build(1.0).ok().unwrap();
+    assert!(scene.lights.len() == 3);
+    let point = scene.lights[0].illuminate(&Vec3(0.0, 0.0, 0.0)).unwrap();
+    assert!(point.irradiance == Vec3(1.25, 1.25, 1.25));
+
+    let error = SceneFile::parse(&source.replace("angle = 20.0", "radius = 20.0"))
+        .err()
+        .unwrap()
+        .to_string();
+    assert!(error.starts_with("unknown field `radius`"));
+    let error = SceneFile::parse(&source.replace("\"directional\"", "\"area\""))
+        .err()
+        .unwrap()
+        .to_string();
+    assert!(error.starts_with("unknown variant `area`"));
+}
+
+#[test]
+fn test_planar_objects() {
+    let source = "\
+[camera]
+look_from = [0.0, 0.0, 0.0]
+look_at = [0.0, 0.0, -1.0]
+vertical_fov = 90.0
+
+[materials.gray]
+type = \"lambertian\"
+albedo = [0.5, 0.5, 0.5]
+
+[[objects]]
+type = \"quad\"
+corner = [-1.0, -1.0, -2.0]
+u = [2.0, 0.0, 0.0]
+v = [0.0, 2.0, 0.0]
+material = \"gray\"
+
+[[objects]]
+type = \"box\"
+min = [-1.0, -1.0, -4.0]
+max = [1.0, 1.0, -3.0]
+material = \"gray\"
+
+[[objects]]
+type = \"disk\"
+center = [0.0, 2.0, -1.0]
+normal = [0.0, -1.0, 0.0]
+radius = 0.5
+material = \"gray\"
+
+[[objects]]
+type = \"plane\"
+point = [0.0, -1.0, 0.0]
+normal = [0.0, 1.0, 0.0]
+material = \"gray\"
+";
+    let scene = SceneFile::parse(source).unwrap().build(1.0).ok().unwrap();
+    assert!(scene.objects.len() == 4);
+    assert!(!scene.objects[3].bounding_box().is_bounded());
+
+    let error = SceneFile::parse(&source.replace("radius = 0.5", "size = 0.5"))
+        .err()
+        .unwrap()
+        .to_string();
+    assert!(error.starts_with("unknown field `size`"));
+    let error = SceneFile::parse(&source.replace(
+        "material = \"gray\"\n\n[[objects]]\ntype = \"disk\"",
+        "material = \"grey\"\n\n[[objects]]\ntype = \"disk\"",
+    ));
+    assert!(matches!(error, Err(SceneError::Invalid { .. })));
+
+    // A zero normal would leave the shape facing nowhere, and never hit.
+    for (from, line) in &[
+        ("normal = [0.0, -1.0, 0.0]", 23),
+        ("normal = [0.0, 1.0, 0.0]", 30),
+    ] {
+        let file = SceneFile::parse(&source.replace(from, "normal = [0.0, 0.0, 0.0]")).unwrap();
+        match file.build(1.0) {
+            Err(SceneError::Invalid {
+                message,
+                line: Some(found),
+            }) => assert!(found == *line, "{}", message),
+            _ => panic!("expected an error at line {}", line),
+        }
+    }
+}
+
+#[test]
+fn test_transforms() {
+    let source = "\
+[camera]
+look_from = [0.0, 0.0, 0.0]
+look_at = [0.0, 0.0, -1.0]
+vertical_fov = 90.0
+
+[materials.gray]
+type = \"lambertian\"
+albedo = [0.5, 0.5, 0.5]
+
+[[objects]]
+type = \"box\"
+min = [-1.0, -1.0, -1.0]
+max = [1.0, 1.0, 1.0]
+material = \"gray\"
+
+[objects.transform]
+translate = [0.0, 0.0, -5.0]
+rotate = [0.0, 45.0, 0.0]
+scale = [1.0, 2.0, 1.0]
+
+[[objects]]
+type = \"sphere\"
+center = [0.0, 0.0, 0.0]
+radius = 1.0
+material = \"gray\"
+
+[objects.transform]
+scale = [2.0, 2.0, 2.0]
+
+[[objects.instances]]
+translate = [-5.0, 0.0, -10.0]
+
+[[objects.instances]]
+translate = [5.0, 0.0, -10.0]
+
+[[objects.instances]]
+translate = [0.0, 5.0, -10.0]
+";
+    let scene = SceneFile::parse(source).unwrap().build(1.0).ok().unwrap();
+    assert!(scene.objects.len() == 4);
+    let bounds = scene.objects[0].bounding_box();
+    let half_diagonal = 2.0_f32.sqrt();
+    assert!((bounds.max.0 - half_diagonal).abs() < 1e-5);
+    assert!(bounds.min.1 == -2.0 && bounds.max.1 == 2.0);
+    // Instances are placed after the shared transform.
+    let bounds = scene.objects[3].bounding_box();
+    assert!(bounds.min == Vec3(-2.0, 3.0, -12.0) && bounds.max == Vec3(2.0, 7.0, -8.0));
+
+    let error = SceneFile::parse(&source.replace("rotate", "turn"))
+        .err()
+        .unwrap()
+        .to_string();
+    assert!(error.starts_with("unknown field `turn`"));
+    // A transform that can't be undone is blamed on its object's entry.
+    for (from, to, line) in &[
+        ("[1.0, 2.0, 1.0]", "[1.0, 0.0, 1.0]", 10),
+        ("[2.0, 2.0, 2.0]", "[0.0, 2.0, 2.0]", 21),
+    ] {
+        let flat = SceneFile::parse(&source.replace(from, to)).unwrap();
+        match flat.build(1.0) {
+            Err(SceneError::Invalid {
+                message,
+                line: Some(found),
+            }) => {
+                assert!(found == *line, "{}", message);
+                assert!(message.contains("down to nothing"), "{}", message);
+            }
+            _ => panic!("expected an error at line {}", line),
+        }
+    }
+}
+
+#[test]
+fn test_motion() {
+    let source = "\
+[camera]
+look_from = [0.0, 0.0, 0.0]
+look_at = [0.0, 0.0, -1.0]
+vertical_fov = 90.0
+shutter = [0.0, 0.5]
+
+[materials.gray]
+type = \"lambertian\"
+albedo = [0.5, 0.5, 0.5]
+
+[[objects]]
+type = \"moving_sphere\"
+start = [0.0, 0.0, -3.0]
+end = [1.0, 0.0, -3.0]
+radius = 0.5
+material = \"gray\"
+
+[[objects]]
+type = \"sphere\"
+center = [0.0, 0.0, 0.0]
+radius = 1.0
+material = \"gray\"
+
+[[objects.keyframes]]
+time = 0.0
+translate = [-2.0, 0.0, -6.0]
+
+[[objects.keyframes]]
+time = 1.0
+translate = [2.0, 0.0, -6.0]
+
+[[objects.instances]]
+translate = [0.0, 3.0, 0.0]
+
+[[objects.instances]]
+translate = [0.0, -3.0, 0.0]
+";
+    let scene = SceneFile::parse(source).unwrap().build(1.0).ok().unwrap();
+    assert!(scene.camera.shutter() == (0.0, 0.5));
+    assert!(scene.objects.len() == 3);
+    let bounds = scene.objects[0].bounding_box();
+    assert!(bounds.min == Vec3(-0.5, -0.5, -3.5) && bounds.max == Vec3(1.5, 0.5, -2.5));
+    // Each instance moves the same way from its own place.
+    let bounds = scene.objects[2].bounding_box();
+    assert!(bounds.min == Vec3(-3.0, -4.0, -7.0) && bounds.max == Vec3(3.0, -2.0, -5.0));
+
+    let error = SceneFile::parse(&source.replace("time = 1.0", "when = 1.0"))
+        .err()
+        .unwrap()
+        .to_string();
+    assert!(error.starts_with("missing field `time`"), "{}", error);
+}
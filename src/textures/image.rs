@@ -0,0 +1,193 @@
+use super::Texture;
+use crate::image::srgb_to_linear;
+use crate::import::ImportError;
+use crate::vec3::Vec3;
+use ::png::{ColorType, Transformations};
+use jpeg_decoder::PixelFormat;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+/// How texture coordinates outside [0, 1] are brought back onto the image.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum WrapMode {
+    /// Tiles the image.
+    #[default]
+    Repeat,
+    /// Stretches the pixels at the edges outwards.
+    Clamp,
+    /// Tiles the image, flipping every other copy so that neighbouring edges match.
+    Mirror,
+}
+
+impl WrapMode {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "repeat" => Some(WrapMode::Repeat),
+            "clamp" => Some(WrapMode::Clamp),
+            "mirror" => Some(WrapMode::Mirror),
+            _ => None,
+        }
+    }
+
+    /// Maps a pixel column or row, which may lie off the image, onto one of the `size` on it.
+    fn apply(self, index: i64, size: usize) -> usize {
+        let size = size as i64;
+        let index = match self {
+            WrapMode::Repeat => index.rem_euclid(size),
+            WrapMode::Clamp => index.clamp(0, size - 1),
+            WrapMode::Mirror => {
+                let index = index.rem_euclid(2 * size);
+                if index < size {
+                    index
+                } else {
+                    2 * size - 1 - index
+                }
+            }
+        };
+        index as usize
+    }
+}
+
+/// An image laid over a surface by its texture coordinates, with (0, 0) at the bottom left
+/// corner of the image and (1, 1) at the top right, as in OBJ files. Colors between pixel
+/// centers are blended bilinearly.
+///
+/// Cloning is cheap, as the pixels are shared.
+#[derive(Clone)]
+pub struct ImageTexture {
+    width: usize,
+    height: usize,
+    /// Linear colors, row by row from the top.
+    pixels: Arc<Vec<Vec3>>,
+    pub wrap: WrapMode,
+}
+
+impl ImageTexture {
+    /// `pixels` are linear colors, row by row from the top. Panics if there aren't
+    /// `width * height` of them, or if there are none.
+    pub fn new(width: usize, height: usize, pixels: Vec<Vec3>) -> Self {
+        assert!(
+            pixels.len() == width * height && !pixels.is_empty(),
+            "{} pixels for a {}x{} image",
+            pixels.len(),
+            width,
+            height
+        );
+        ImageTexture {
+            width,
+            height,
+            pixels: Arc::new(pixels),
+            wrap: WrapMode::default(),
+        }
+    }
+
+    /// Loads a PNG or JPEG file, recognized by its contents rather than its extension. Both
+    /// are taken to be sRGB-encoded, and converted to linear colors. Gray images become gray
+    /// colors, and alpha is ignored.
+    pub fn load(path: &Path) -> Result<Self, ImportError> {
+        let bytes = fs::read(path).map_err(|error| ImportError::Io {
+            path: path.to_path_buf(),
+            error,
+        })?;
+        let decoded = if bytes.starts_with(b"\x89PNG") {
+            decode_png(&bytes)
+        } else if bytes.starts_with(&[0xff, 0xd8]) {
+            decode_jpeg(&bytes)
+        } else {
+            Err(String::from("not a PNG or JPEG image"))
+        };
+        let (width, height, channels, samples) =
+            decoded.map_err(|message| ImportError::Malformed {
+                path: path.to_path_buf(),
+                message,
+            })?;
+        if width == 0 || height == 0 || samples.len() < width * height * channels {
+            return Err(ImportError::Malformed {
+                path: path.to_path_buf(),
+                message: String::from("the image has no pixels, or too few of them"),
+            });
+        }
+        let linear = |sample: u8| srgb_to_linear(sample as f32 / 255.0);
+        let pixels = samples
+            .chunks_exact(channels)
+            .take(width * height)
+            .map(|pixel| {
+                if channels < 3 {
+                    let gray = linear(pixel[0]);
+                    Vec3(gray, gray, gray)
+                } else {
+                    Vec3(linear(pixel[0]), linear(pixel[1]), linear(pixel[2]))
+                }
+            })
+            .collect();
+        Ok(Self::new(width, height, pixels))
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    fn pixel(&self, x: i64, y: i64) -> Vec3 {
+        let x = self.wrap.apply(x, self.width);
+        let y = self.wrap.apply(y, self.height);
+        self.pixels[y * self.width + x]
+    }
+}
+
+impl Texture for ImageTexture {
+    #[allow(unused_variables)]
+    fn value(&self, u: f32, v: f32, point: &Vec3) -> Vec3 {
+        // Pixel centers lie at half-integer positions.
+        let x = u * self.width as f32 - 0.5;
+        let y = (1.0 - v) * self.height as f32 - 0.5;
+        let (left, top) = (x.floor(), y.floor());
+        let (right_weight, bottom_weight) = (x - left, y - top);
+        let (left, top) = (left as i64, top as i64);
+        let upper =
+            (1.0 - right_weight) * self.pixel(left, top) + right_weight * self.pixel(left + 1, top);
+        let lower = (1.0 - right_weight) * self.pixel(left, top + 1)
+            + right_weight * self.pixel(left + 1, top + 1);
+        (1.0 - bottom_weight) * upper + bottom_weight * lower
+    }
+}
+
+/// The width, height, channel count and 8-bit samples of an image, or what's wrong with it.
+type Decoded = Result<(usize, usize, usize, Vec<u8>), String>;
+
+fn decode_png(bytes: &[u8]) -> Decoded {
+    let mut decoder = ::png::Decoder::new(bytes);
+    // Palettes and low bit depths expand to 8 bits, and 16-bit samples are cut down to 8.
+    decoder.set_transformations(Transformations::EXPAND | Transformations::STRIP_16);
+    let mut reader = decoder.read_info().map_err(|error| error.to_string())?;
+    let mut samples = vec![0; reader.output_buffer_size()];
+    let info = reader
+        .next_frame(&mut samples)
+        .map_err(|error| error.to_string())?;
+    let channels = match info.color_type {
+        ColorType::Grayscale => 1,
+        ColorType::GrayscaleAlpha => 2,
+        ColorType::Rgb => 3,
+        ColorType::Rgba => 4,
+        ColorType::Indexed => return Err(String::from("the palette wasn't expanded")),
+    };
+    Ok((info.width as usize, info.height as usize, channels, samples))
+}
+
+fn decode_jpeg(bytes: &[u8]) -> Decoded {
+    let mut decoder = jpeg_decoder::Decoder::new(bytes);
+    let samples = decoder.decode().map_err(|error| error.to_string())?;
+    let info = decoder
+        .info()
+        .ok_or_else(|| String::from("the JPEG has no frame"))?;
+    let channels = match info.pixel_format {
+        PixelFormat::L8 => 1,
+        PixelFormat::RGB24 => 3,
+        format => return Err(format!("{:?} JPEGs aren't supported", format)),
+    };
+    Ok((info.width as usize, info.height as usize, channels, samples))
+}
@@ -0,0 +1,59 @@
+mod image;
+mod noise;
+mod tests;
+use crate::vec3::Vec3;
+pub use image::{ImageTexture, WrapMode};
+pub use noise::{ColorRamp, NoisePattern, NoiseTexture, Perlin};
+
+/// A color that varies over a surface, looked up by the texture coordinates of a hit or by the
+/// point hit. Shared between render threads, so implementors must be `Send + Sync`.
+pub trait Texture: Send + Sync {
+    fn value(&self, u: f32, v: f32, point: &Vec3) -> Vec3;
+}
+
+/// A solid color, the same everywhere.
+impl Texture for Vec3 {
+    #[allow(unused_variables)]
+    fn value(&self, u: f32, v: f32, point: &Vec3) -> Vec3 {
+        *self
+    }
+}
+
+impl<T: Texture + ?Sized> Texture for Box<T> {
+    fn value(&self, u: f32, v: f32, point: &Vec3) -> Vec3 {
+        (**self).value(u, v, point)
+    }
+}
+
+/// A checkerboard of cubes in space, alternating between two textures. Being solid, it needs no
+/// texture coordinates, so it doesn't stretch over curved surfaces.
+///
+/// Surfaces lying exactly on a cube face, such as a floor at height 0 with a scale of 1, flicker
+/// between the two; move them or the pattern a little off the boundary.
+pub struct Checker {
+    even: Box<dyn Texture>,
+    odd: Box<dyn Texture>,
+    /// The width of a cube.
+    scale: f32,
+}
+
+impl Checker {
+    pub fn new(even: impl Texture + 'static, odd: impl Texture + 'static, scale: f32) -> Self {
+        Checker {
+            even: Box::new(even),
+            odd: Box::new(odd),
+            scale,
+        }
+    }
+}
+
+impl Texture for Checker {
+    fn value(&self, u: f32, v: f32, point: &Vec3) -> Vec3 {
+        let cell = |coordinate: f32| (coordinate / self.scale).floor() as i64;
+        if (cell(point.0) + cell(point.1) + cell(point.2)).rem_euclid(2) == 0 {
+            self.even.value(u, v, point)
+        } else {
+            self.odd.value(u, v, point)
+        }
+    }
+}
@@ -0,0 +1,210 @@
+use super::Texture;
+use crate::sampler::Sampler;
+use crate::vec3::{Vec3, Vector};
+use rand::prelude::*;
+
+const POINT_COUNT: usize = 256;
+
+/// Gradient noise (Perlin, 1985): a smooth random function of space, built from random
+/// gradients at the corners of a unit lattice. Features are about one unit across.
+#[derive(Clone)]
+pub struct Perlin {
+    gradients: Vec<Vec3>,
+    /// A shuffle of the lattice coordinates along each axis, whose entries are combined to
+    /// pick a corner's gradient.
+    permutations: [Vec<usize>; 3],
+}
+
+impl Perlin {
+    /// Noise from the same seed is the same everywhere.
+    pub fn new(seed: u64) -> Self {
+        let mut sampler = Sampler::new(seed);
+        let gradients = (0..POINT_COUNT)
+            .map(|_| loop {
+                let gradient = Vec3(
+                    sampler.gen_range(-1.0, 1.0),
+                    sampler.gen_range(-1.0, 1.0),
+                    sampler.gen_range(-1.0, 1.0),
+                );
+                let length = gradient.squared_length();
+                if length > 1e-4 && length <= 1.0 {
+                    break gradient.unit_vector();
+                }
+            })
+            .collect();
+        let mut permutation = || {
+            let mut permutation: Vec<usize> = (0..POINT_COUNT).collect();
+            permutation.shuffle(&mut sampler);
+            permutation
+        };
+        Perlin {
+            gradients,
+            permutations: [permutation(), permutation(), permutation()],
+        }
+    }
+
+    /// The noise at `point`, between about -1 and 1, and 0 at every lattice point.
+    pub fn noise(&self, point: &Vec3) -> f32 {
+        let floor = Vec3(point.0.floor(), point.1.floor(), point.2.floor());
+        let offset = point - &floor;
+        // A Hermite cubic, so that the blend is smooth across cell boundaries.
+        let smooth = |t: f32| t * t * (3.0 - 2.0 * t);
+        let weights = [smooth(offset.0), smooth(offset.1), smooth(offset.2)];
+        let cell = [floor.0 as i64, floor.1 as i64, floor.2 as i64];
+        let mut total = 0.0;
+        for corner in 0..8 {
+            let step = [corner & 1, (corner >> 1) & 1, (corner >> 2) & 1];
+            let index = (0..3).fold(0, |index, axis| {
+                let coordinate = (cell[axis] + step[axis] as i64).rem_euclid(POINT_COUNT as i64);
+                index ^ self.permutations[axis][coordinate as usize]
+            });
+            let mut weight = 1.0;
+            for axis in 0..3 {
+                weight *= if step[axis] == 1 {
+                    weights[axis]
+                } else {
+                    1.0 - weights[axis]
+                };
+            }
+            let to_point = offset - Vec3(step[0] as f32, step[1] as f32, step[2] as f32);
+            total += weight * Vec3::dot(&self.gradients[index], &to_point);
+        }
+        total
+    }
+
+    /// Fractal sum of `octaves` layers of noise, each at twice the frequency and half the
+    /// weight of the one before. Between about -1 and 1.
+    pub fn fractal(&self, point: &Vec3, octaves: u32) -> f32 {
+        self.octaves(point, octaves, |noise| noise)
+    }
+
+    /// Like `fractal`, but summing the magnitude of each layer, which leaves creases where the
+    /// noise crosses zero. Between 0 and about 1.
+    pub fn turbulence(&self, point: &Vec3, octaves: u32) -> f32 {
+        self.octaves(point, octaves, f32::abs)
+    }
+
+    fn octaves(&self, point: &Vec3, octaves: u32, layer: impl Fn(f32) -> f32) -> f32 {
+        let mut total = 0.0;
+        let mut point = *point;
+        let mut weight = 1.0;
+        for _ in 0..octaves.max(1) {
+            total += weight * layer(self.noise(&point));
+            weight *= 0.5;
+            point = 2.0 * point;
+        }
+        total
+    }
+}
+
+/// Colors at positions along [0, 1], blended linearly in between. Positions before the first
+/// stop or after the last take its color.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColorRamp {
+    stops: Vec<(f32, Vec3)>,
+}
+
+impl ColorRamp {
+    /// Panics if there are no stops. They needn't be in order.
+    pub fn new(mut stops: Vec<(f32, Vec3)>) -> Self {
+        assert!(!stops.is_empty(), "a color ramp needs at least one color");
+        stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+        ColorRamp { stops }
+    }
+
+    /// Colors spread evenly from 0 to 1. Panics if there are none.
+    pub fn even(colors: &[Vec3]) -> Self {
+        let last = colors.len().saturating_sub(1).max(1) as f32;
+        Self::new(
+            colors
+                .iter()
+                .enumerate()
+                .map(|(index, color)| (index as f32 / last, *color))
+                .collect(),
+        )
+    }
+
+    pub fn color(&self, position: f32) -> Vec3 {
+        let next = self.stops.partition_point(|stop| stop.0 <= position);
+        if next == 0 {
+            return self.stops[0].1;
+        }
+        if next == self.stops.len() {
+            return self.stops[next - 1].1;
+        }
+        let (start, start_color) = self.stops[next - 1];
+        let (end, end_color) = self.stops[next];
+        let t = (position - start) / (end - start);
+        (1.0 - t) * start_color + t * end_color
+    }
+}
+
+/// From black to white.
+impl Default for ColorRamp {
+    fn default() -> Self {
+        Self::even(&[Vec3(0.0, 0.0, 0.0), Vec3(1.0, 1.0, 1.0)])
+    }
+}
+
+/// How a `NoiseTexture` turns noise into a position on its color ramp.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum NoisePattern {
+    /// Soft, cloudy blotches.
+    Noise,
+    /// Sharper blotches, with dark creases.
+    Turbulence,
+    /// Bands along the z axis, swirled by turbulence.
+    Marble,
+    /// Rings around the y axis, warped by noise.
+    Wood,
+}
+
+/// A solid texture made from Perlin noise. Like `Checker`, it depends only on the point hit.
+#[derive(Clone)]
+pub struct NoiseTexture {
+    pub pattern: NoisePattern,
+    /// Multiplies points before the noise is taken, so that larger scales give finer detail.
+    pub scale: f32,
+    /// Layers of noise summed by the fractal patterns, each adding finer detail.
+    pub octaves: u32,
+    pub ramp: ColorRamp,
+    pub perlin: Perlin,
+}
+
+impl NoiseTexture {
+    /// A black and white pattern with 7 octaves, from noise seeded with 0.
+    pub fn new(pattern: NoisePattern, scale: f32) -> Self {
+        NoiseTexture {
+            pattern,
+            scale,
+            octaves: 7,
+            ramp: ColorRamp::default(),
+            perlin: Perlin::new(0),
+        }
+    }
+
+    /// The position on the color ramp for a point.
+    fn position(&self, point: &Vec3) -> f32 {
+        let point = self.scale * point;
+        match self.pattern {
+            NoisePattern::Noise => 0.5 * (1.0 + self.perlin.fractal(&point, self.octaves)),
+            NoisePattern::Turbulence => self.perlin.turbulence(&point, self.octaves),
+            NoisePattern::Marble => {
+                let phase = point.2 + 10.0 * self.perlin.turbulence(&point, self.octaves);
+                0.5 * (1.0 + phase.sin())
+            }
+            NoisePattern::Wood => {
+                let radius = (point.0 * point.0 + point.2 * point.2).sqrt();
+                let rings = radius + 0.5 * self.perlin.fractal(&point, self.octaves);
+                rings - rings.floor()
+            }
+        }
+    }
+}
+
+impl Texture for NoiseTexture {
+    #[allow(unused_variables)]
+    fn value(&self, u: f32, v: f32, point: &Vec3) -> Vec3 {
+        self.ramp.color(self.position(point))
+    }
+}
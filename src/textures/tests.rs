@@ -0,0 +1,257 @@
+#[cfg(test)]
+use crate::framebuffer::{Framebuffer, Tile};
+#[cfg(test)]
+use crate::image::{self, srgb_to_linear, ImageFormat};
+#[cfg(test)]
+use crate::import::ImportError;
+#[cfg(test)]
+use crate::sampler::Sampler;
+#[cfg(test)]
+use crate::textures::{
+    Checker, ColorRamp, ImageTexture, NoisePattern, NoiseTexture, Perlin, Texture, WrapMode,
+};
+#[cfg(test)]
+use crate::vec3::{Vec3, Vector};
+#[cfg(test)]
+use rand::prelude::*;
+#[cfg(test)]
+use std::fs;
+
+/// Black and white pixels side by side, over a red and a blue one.
+#[cfg(test)]
+fn two_by_two() -> ImageTexture {
+    ImageTexture::new(
+        2,
+        2,
+        vec![
+            Vec3(0.0, 0.0, 0.0),
+            Vec3(1.0, 1.0, 1.0),
+            Vec3(1.0, 0.0, 0.0),
+            Vec3(0.0, 0.0, 1.0),
+        ],
+    )
+}
+
+#[cfg(test)]
+fn close(a: Vec3, b: Vec3) -> bool {
+    (a - b).length() < 1e-5
+}
+
+#[test]
+fn test_checker() {
+    let checker = Checker::new(Vec3(1.0, 1.0, 1.0), Vec3(0.0, 0.0, 0.0), 0.5);
+    let at = |x, y, z| checker.value(0.0, 0.0, &Vec3(x, y, z));
+
+    assert!(at(0.1, 0.1, 0.1) == Vec3(1.0, 1.0, 1.0));
+    assert!(at(0.6, 0.1, 0.1) == Vec3(0.0, 0.0, 0.0));
+    assert!(at(0.6, 0.6, 0.1) == Vec3(1.0, 1.0, 1.0));
+    // The cells carry on past the origin without a double-width row.
+    assert!(at(-0.1, 0.1, 0.1) == Vec3(0.0, 0.0, 0.0));
+    assert!(at(-0.6, 0.1, 0.1) == Vec3(1.0, 1.0, 1.0));
+}
+
+#[test]
+fn test_bilinear_filtering() {
+    let image = two_by_two();
+
+    // Pixel centers, with v running up from the bottom row.
+    assert!(close(
+        image.value(0.25, 0.75, &Vec3(0.0, 0.0, 0.0)),
+        Vec3(0.0, 0.0, 0.0)
+    ));
+    assert!(close(
+        image.value(0.75, 0.25, &Vec3(0.0, 0.0, 0.0)),
+        Vec3(0.0, 0.0, 1.0)
+    ));
+    // Halfway between the two top pixels, and in the middle of all four.
+    assert!(close(
+        image.value(0.5, 0.75, &Vec3(0.0, 0.0, 0.0)),
+        Vec3(0.5, 0.5, 0.5)
+    ));
+    assert!(close(
+        image.value(0.5, 0.5, &Vec3(0.0, 0.0, 0.0)),
+        Vec3(0.5, 0.25, 0.5)
+    ));
+}
+
+#[test]
+fn test_wrap_modes() {
+    let mut image = two_by_two();
+    // A quarter of a pixel right of the right edge of the top row.
+    let (u, v) = (1.125, 0.75);
+
+    assert!(close(
+        image.value(u, v, &Vec3(0.0, 0.0, 0.0)),
+        Vec3(0.25, 0.25, 0.25)
+    ));
+    image.wrap = WrapMode::Clamp;
+    assert!(close(
+        image.value(u, v, &Vec3(0.0, 0.0, 0.0)),
+        Vec3(1.0, 1.0, 1.0)
+    ));
+    image.wrap = WrapMode::Mirror;
+    assert!(close(
+        image.value(u, v, &Vec3(0.0, 0.0, 0.0)),
+        Vec3(1.0, 1.0, 1.0)
+    ));
+    assert!(close(
+        image.value(1.75, v, &Vec3(0.0, 0.0, 0.0)),
+        Vec3(0.0, 0.0, 0.0)
+    ));
+    assert!(WrapMode::from_name("mirror") == Some(WrapMode::Mirror));
+    assert!(WrapMode::from_name("wrap").is_none());
+}
+
+/// A baseline JPEG of one 8x8 block of gray 131: a DC coefficient of 24 over the 128 midpoint,
+/// with single-code Huffman tables and no AC coefficients.
+#[cfg(test)]
+fn gray_jpeg() -> Vec<u8> {
+    let mut bytes = vec![0xff, 0xd8];
+    bytes.extend(&[0xff, 0xdb, 0x00, 0x43, 0x00]);
+    bytes.extend(&[1; 64]);
+    bytes.extend(&[
+        0xff, 0xc0, 0x00, 0x0b, 0x08, 0x00, 0x08, 0x00, 0x08, 0x01, 0x01, 0x11, 0x00,
+    ]);
+    for (table, symbol) in &[(0x00, 0x05), (0x10, 0x00)] {
+        bytes.extend(&[0xff, 0xc4, 0x00, 0x14, *table, 1]);
+        bytes.extend(&[0; 15]);
+        bytes.push(*symbol);
+    }
+    bytes.extend(&[0xff, 0xda, 0x00, 0x08, 0x01, 0x01, 0x00, 0x00, 0x3f, 0x00]);
+    // DC size 5 as code 0, the difference 11000, then end-of-block as code 0, padded with ones.
+    bytes.push(0b0110_0001);
+    bytes.extend(&[0xff, 0xd9]);
+    bytes
+}
+
+#[test]
+fn test_load_images() {
+    let directory =
+        std::env::temp_dir().join(format!("raytracing-textures-{}", std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    let mut framebuffer = Framebuffer::new(2, 1);
+    let tile = Tile {
+        x: 0,
+        y: 0,
+        width: 2,
+        height: 1,
+    };
+    framebuffer.write_tile(&tile, &[Vec3(0.2, 0.4, 0.6), Vec3(1.0, 0.0, 0.5)]);
+    image::save(&framebuffer, &directory.join("pair.png"), ImageFormat::Png).unwrap();
+    fs::write(directory.join("gray.jpg"), gray_jpeg()).unwrap();
+    fs::write(directory.join("text.png"), "not an image").unwrap();
+    let png = ImageTexture::load(&directory.join("pair.png"));
+    let jpeg = ImageTexture::load(&directory.join("gray.jpg"));
+    let text = ImageTexture::load(&directory.join("text.png"));
+    let missing = ImageTexture::load(&directory.join("missing.png"));
+    fs::remove_dir_all(&directory).unwrap();
+
+    // The 8-bit sRGB round trip gives back the linear colors, give or take quantization.
+    let png = png.unwrap();
+    assert!(png.width() == 2 && png.height() == 1);
+    let left = png.value(0.25, 0.5, &Vec3(0.0, 0.0, 0.0));
+    assert!((left - Vec3(0.2, 0.4, 0.6)).length() < 0.01);
+    let jpeg = jpeg.unwrap();
+    assert!(jpeg.width() == 8 && jpeg.height() == 8);
+    let gray = srgb_to_linear(131.0 / 255.0);
+    assert!(close(
+        jpeg.value(0.5, 0.5, &Vec3(0.0, 0.0, 0.0)),
+        Vec3(gray, gray, gray)
+    ));
+    match text {
+        Err(ImportError::Malformed { message, .. }) => {
+            assert!(message == "not a PNG or JPEG image")
+        }
+        _ => panic!("expected the text file to be rejected"),
+    }
+    assert!(matches!(missing, Err(ImportError::Io { .. })));
+}
+
+#[test]
+fn test_perlin_noise() {
+    let perlin = Perlin::new(1);
+    let mut sampler = Sampler::new(4);
+    let random_point = |sampler: &mut Sampler| {
+        Vec3(
+            sampler.gen_range(-20.0, 20.0),
+            sampler.gen_range(-20.0, 20.0),
+            sampler.gen_range(-20.0, 20.0),
+        )
+    };
+
+    assert!(perlin.noise(&Vec3(3.0, -2.0, 7.0)) == 0.0);
+    let mut values = Vec::new();
+    for _ in 0..1000 {
+        let point = random_point(&mut sampler);
+        let value = perlin.noise(&point);
+        assert!(value.abs() < 1.0);
+        // Smooth: nearby points have nearby values.
+        let nearby = perlin.noise(&(point + Vec3(1e-3, -1e-3, 1e-3)));
+        assert!((value - nearby).abs() < 0.01);
+        let turbulence = perlin.turbulence(&point, 5);
+        assert!((0.0..2.0).contains(&turbulence));
+        values.push(value);
+    }
+    let mean = values.iter().sum::<f32>() / values.len() as f32;
+    assert!(mean.abs() < 0.05);
+    assert!(values.iter().any(|value| value.abs() > 0.2));
+    // The seed picks the noise.
+    let point = Vec3(0.5, 1.25, -3.5);
+    assert!(Perlin::new(1).noise(&point) == perlin.noise(&point));
+    assert!(Perlin::new(2).noise(&point) != perlin.noise(&point));
+}
+
+#[test]
+fn test_color_ramp() {
+    let ramp = ColorRamp::new(vec![
+        (1.0, Vec3(0.0, 0.0, 1.0)),
+        (0.0, Vec3(1.0, 0.0, 0.0)),
+        (0.25, Vec3(0.0, 1.0, 0.0)),
+    ]);
+
+    assert!(ramp.color(-1.0) == Vec3(1.0, 0.0, 0.0));
+    assert!(close(ramp.color(0.125), Vec3(0.5, 0.5, 0.0)));
+    assert!(close(ramp.color(0.625), Vec3(0.0, 0.5, 0.5)));
+    assert!(ramp.color(2.0) == Vec3(0.0, 0.0, 1.0));
+    let even = ColorRamp::even(&[
+        Vec3(0.0, 0.0, 0.0),
+        Vec3(1.0, 1.0, 1.0),
+        Vec3(0.0, 0.0, 0.0),
+    ]);
+    assert!(even.color(0.5) == Vec3(1.0, 1.0, 1.0));
+    assert!(close(even.color(0.75), Vec3(0.5, 0.5, 0.5)));
+}
+
+#[test]
+fn test_noise_patterns() {
+    let patterns = [
+        NoisePattern::Noise,
+        NoisePattern::Turbulence,
+        NoisePattern::Marble,
+        NoisePattern::Wood,
+    ];
+    let mut sampler = Sampler::new(6);
+    for pattern in patterns.iter() {
+        let mut texture = NoiseTexture::new(*pattern, 3.0);
+        texture.ramp = ColorRamp::even(&[Vec3(0.2, 0.1, 0.0), Vec3(0.4, 0.3, 0.2)]);
+        let colors: Vec<Vec3> = (0..200)
+            .map(|_| {
+                let point = Vec3(sampler.gen(), sampler.gen(), sampler.gen());
+                texture.value(0.0, 0.0, &point)
+            })
+            .collect();
+        // Every color is on the ramp, and the pattern doesn't stick to one end of it.
+        for color in colors.iter() {
+            assert!(color.0 >= 0.2 - 1e-6 && color.0 <= 0.4 + 1e-6);
+            let along = color.0 - 0.2;
+            assert!(close(
+                *color - Vec3(0.2, 0.1, 0.0),
+                Vec3(along, along, along)
+            ));
+        }
+        let (darkest, lightest) = colors.iter().fold((1.0f32, 0.0f32), |(low, high), color| {
+            (low.min(color.0), high.max(color.0))
+        });
+        assert!(lightest - darkest > 0.05, "{:?} is flat", pattern);
+    }
+}
@@ -0,0 +1,177 @@
+use super::{Vec3, Vector};
+use std::ops::Mul;
+
+/// A 4x4 matrix acting on points and vectors in homogeneous coordinates, row by row.
+///
+/// Only affine transforms can be built, so the bottom row is always `0 0 0 1`. Products apply
+/// the right-hand transform first: `translation * rotation` turns an object about the origin
+/// and then moves it.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Matrix4 {
+    rows: [[f32; 4]; 4],
+}
+
+impl Matrix4 {
+    pub fn identity() -> Self {
+        Self::from_columns(
+            Vec3(1.0, 0.0, 0.0),
+            Vec3(0.0, 1.0, 0.0),
+            Vec3(0.0, 0.0, 1.0),
+            Vec3(0.0, 0.0, 0.0),
+        )
+    }
+
+    /// The transform taking the x, y and z axes to the given vectors and the origin to
+    /// `translation`.
+    pub fn from_columns(x: Vec3, y: Vec3, z: Vec3, translation: Vec3) -> Self {
+        Matrix4 {
+            rows: [
+                [x.0, y.0, z.0, translation.0],
+                [x.1, y.1, z.1, translation.1],
+                [x.2, y.2, z.2, translation.2],
+                [0.0, 0.0, 0.0, 1.0],
+            ],
+        }
+    }
+
+    pub fn translation(offset: Vec3) -> Self {
+        Self::from_columns(
+            Vec3(1.0, 0.0, 0.0),
+            Vec3(0.0, 1.0, 0.0),
+            Vec3(0.0, 0.0, 1.0),
+            offset,
+        )
+    }
+
+    /// Scaling by a separate factor along each axis.
+    pub fn scaling(factors: Vec3) -> Self {
+        Self::from_columns(
+            Vec3(factors.0, 0.0, 0.0),
+            Vec3(0.0, factors.1, 0.0),
+            Vec3(0.0, 0.0, factors.2),
+            Vec3(0.0, 0.0, 0.0),
+        )
+    }
+
+    /// Rotation by `degrees` about `axis`, counter-clockwise when looking down the axis
+    /// towards the origin.
+    pub fn rotation(axis: Vec3, degrees: f32) -> Self {
+        let Vec3(x, y, z) = axis.unit_vector();
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        let t = 1.0 - cos;
+        Matrix4 {
+            rows: [
+                [
+                    t * x * x + cos,
+                    t * x * y - sin * z,
+                    t * x * z + sin * y,
+                    0.0,
+                ],
+                [
+                    t * x * y + sin * z,
+                    t * y * y + cos,
+                    t * y * z - sin * x,
+                    0.0,
+                ],
+                [
+                    t * x * z - sin * y,
+                    t * y * z + sin * x,
+                    t * z * z + cos,
+                    0.0,
+                ],
+                [0.0, 0.0, 0.0, 1.0],
+            ],
+        }
+    }
+
+    pub fn transform_point(&self, point: &Vec3) -> Vec3 {
+        self.transform_vector(point) + Vec3(self.rows[0][3], self.rows[1][3], self.rows[2][3])
+    }
+
+    /// Transforms a direction or offset, which translation leaves alone.
+    pub fn transform_vector(&self, vector: &Vec3) -> Vec3 {
+        let row = |r: &[f32; 4]| r[0] * vector.0 + r[1] * vector.1 + r[2] * vector.2;
+        Vec3(row(&self.rows[0]), row(&self.rows[1]), row(&self.rows[2]))
+    }
+
+    /// Transforms a surface normal. This is the inverse transpose at work, so call it on the
+    /// inverse of the matrix that moves the surface. The result isn't of unit length.
+    pub fn transform_normal(&self, normal: &Vec3) -> Vec3 {
+        let column = |c: usize| {
+            self.rows[0][c] * normal.0 + self.rows[1][c] * normal.1 + self.rows[2][c] * normal.2
+        };
+        Vec3(column(0), column(1), column(2))
+    }
+
+    /// The determinant, which for an affine transform is that of its upper-left 3x3 part:
+    /// the factor by which it scales volumes, negative if it mirrors.
+    pub fn determinant(&self) -> f32 {
+        let (x, y, z) = self.axes();
+        Vec3::dot(&x, &Vec3::cross(&y, &z))
+    }
+
+    /// The transform that undoes this one, or `None` if it flattens space, as scaling by zero
+    /// does.
+    pub fn inverse(&self) -> Option<Self> {
+        let determinant = self.determinant();
+        if determinant == 0.0 || !determinant.is_finite() {
+            return None;
+        }
+        // The rows of the inverse of the 3x3 part are the cross products of its columns.
+        let (x, y, z) = self.axes();
+        let rows = [
+            Vec3::cross(&y, &z) / determinant,
+            Vec3::cross(&z, &x) / determinant,
+            Vec3::cross(&x, &y) / determinant,
+        ];
+        let translation = Vec3(self.rows[0][3], self.rows[1][3], self.rows[2][3]);
+        let row = |r: &Vec3| [r.0, r.1, r.2, -Vec3::dot(r, &translation)];
+        Some(Matrix4 {
+            rows: [
+                row(&rows[0]),
+                row(&rows[1]),
+                row(&rows[2]),
+                [0.0, 0.0, 0.0, 1.0],
+            ],
+        })
+    }
+
+    /// The blend `along` the way from this matrix to `other`, entry by entry. Every point then
+    /// moves in a straight line between where the two matrices put it, which is exact for
+    /// moves and scaling, but cuts the corner of a turn and shrinks things on the way round.
+    pub fn lerp(&self, other: &Matrix4, along: f32) -> Self {
+        let mut rows = self.rows;
+        for (row, other_row) in rows.iter_mut().zip(other.rows.iter()) {
+            for (value, other_value) in row.iter_mut().zip(other_row.iter()) {
+                *value += along * (other_value - *value);
+            }
+        }
+        Matrix4 { rows }
+    }
+
+    /// The images of the x, y and z axes.
+    fn axes(&self) -> (Vec3, Vec3, Vec3) {
+        let column = |c: usize| Vec3(self.rows[0][c], self.rows[1][c], self.rows[2][c]);
+        (column(0), column(1), column(2))
+    }
+}
+
+impl Default for Matrix4 {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl Mul for Matrix4 {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self::Output {
+        let mut rows = [[0.0; 4]; 4];
+        for (i, row) in rows.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = (0..4).map(|k| self.rows[i][k] * other.rows[k][j]).sum();
+            }
+        }
+        Matrix4 { rows }
+    }
+}
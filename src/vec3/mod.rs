@@ -1,33 +1,10 @@
+mod matrix;
 mod tests;
-use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+pub use matrix::Matrix4;
+use std::ops::{Add, AddAssign, Div, DivAssign, Index, Mul, MulAssign, Neg, Sub, SubAssign};
 
-#[derive(Copy)]
+#[derive(Copy, Clone, Debug)]
 pub struct Vec3(pub f32, pub f32, pub f32);
-pub trait Color {
-    fn r(&self) -> u8;
-    fn g(&self) -> u8;
-    fn b(&self) -> u8;
-}
-impl Color for Vec3 {
-    fn r(&self) -> u8 {
-        self.0 as u8
-    }
-
-    fn g(&self) -> u8 {
-        self.1 as u8
-    }
-
-    fn b(&self) -> u8 {
-        self.2 as u8
-    }
-}
-
-pub trait Coordinate<T> {
-    fn x(&self) -> T;
-    fn y(&self) -> T;
-    fn z(&self) -> T;
-}
-
 pub trait Vector<T> {
     fn x(&self) -> T;
     fn y(&self) -> T;
@@ -68,7 +45,7 @@ impl Vector<f32> for Vec3 {
     }
 
     fn squared_length(&self) -> f32 {
-        Self::dot(&self, &self)
+        Self::dot(self, self)
     }
 
     fn cross(v1: &Vec3, v2: &Vec3) -> Vec3 {
@@ -80,17 +57,41 @@ impl Vector<f32> for Vec3 {
     }
 }
 
-impl Clone for Vec3 {
-    fn clone(&self) -> Self {
-        Self(self.0, self.1, self.2)
-    }
+/// Two unit vectors that form a right-handed orthonormal basis with the unit vector `normal`,
+/// by the branchless construction of Duff et al. (2017).
+pub fn orthonormal_basis(normal: &Vec3) -> (Vec3, Vec3) {
+    let sign = 1.0_f32.copysign(normal.z());
+    let a = -1.0 / (sign + normal.z());
+    let b = normal.x() * normal.y() * a;
+    (
+        Vec3(
+            1.0 + sign * normal.x() * normal.x() * a,
+            sign * b,
+            -sign * normal.x(),
+        ),
+        Vec3(b, sign + normal.y() * normal.y() * a, -normal.y()),
+    )
 }
+
 impl PartialEq for Vec3 {
     fn eq(&self, other: &Vec3) -> bool {
         self.0 == other.0 && self.1 == other.1 && self.2 == other.2
     }
 }
 
+impl Index<usize> for Vec3 {
+    type Output = f32;
+
+    fn index(&self, axis: usize) -> &f32 {
+        match axis {
+            0 => &self.0,
+            1 => &self.1,
+            2 => &self.2,
+            _ => panic!("Vec3 index out of range: {}", axis),
+        }
+    }
+}
+
 impl Add for Vec3 {
     type Output = Self;
 
@@ -109,7 +110,7 @@ impl Add for &Vec3 {
 }
 */
 
-impl<'a, 'b> Add<&'b Vec3> for &'a Vec3 {
+impl<'b> Add<&'b Vec3> for &Vec3 {
     type Output = Vec3;
 
     fn add(self, other: &'b Vec3) -> Vec3 {
@@ -1,5 +1,5 @@
 #[cfg(test)]
-use crate::vec3::Vec3;
+use crate::vec3::{orthonormal_basis, Matrix4, Vec3, Vector};
 
 #[test]
 fn test_add_vector3() {
@@ -114,3 +114,92 @@ fn test_scalar_div_assign_vector3() {
     let v3 = Vec3(1.0, 2.0, 3.0);
     assert!(v1 == v3);
 }
+
+#[test]
+fn test_index_vector3() {
+    let v1 = Vec3(1.0, 2.0, 3.0);
+
+    assert!(v1[0] == 1.0 && v1[1] == 2.0 && v1[2] == 3.0);
+}
+
+#[test]
+fn test_orthonormal_basis() {
+    for normal in &[
+        Vec3(0.0, 0.0, 1.0),
+        Vec3(0.0, 0.0, -1.0),
+        Vec3(0.6, 0.0, 0.8),
+        Vec3(1.0, 2.0, -3.0).unit_vector(),
+    ] {
+        let (tangent, bitangent) = orthonormal_basis(normal);
+
+        assert!((tangent.length() - 1.0).abs() < 1e-6 && (bitangent.length() - 1.0).abs() < 1e-6);
+        assert!(Vec3::dot(&tangent, normal).abs() < 1e-6);
+        assert!(Vec3::dot(&bitangent, normal).abs() < 1e-6);
+        assert!((Vec3::cross(&tangent, &bitangent) - *normal).length() < 1e-6);
+    }
+}
+
+#[cfg(test)]
+fn close(v1: Vec3, v2: Vec3) -> bool {
+    (v1 - v2).length() < 1e-5
+}
+
+#[test]
+fn test_matrix_transforms() {
+    let point = Vec3(1.0, 2.0, 3.0);
+    let translation = Matrix4::translation(Vec3(1.0, -1.0, 0.5));
+    assert!(translation.transform_point(&point) == Vec3(2.0, 1.0, 3.5));
+    assert!(translation.transform_vector(&point) == point);
+
+    let scaling = Matrix4::scaling(Vec3(2.0, 3.0, -1.0));
+    assert!(scaling.transform_point(&point) == Vec3(2.0, 6.0, -3.0));
+    assert!(scaling.determinant() == -6.0);
+
+    // A quarter turn about y takes x to -z, and about z takes x to y.
+    let about_y = Matrix4::rotation(Vec3(0.0, 2.0, 0.0), 90.0);
+    assert!(close(
+        about_y.transform_vector(&Vec3(1.0, 0.0, 0.0)),
+        Vec3(0.0, 0.0, -1.0)
+    ));
+    let about_z = Matrix4::rotation(Vec3(0.0, 0.0, 1.0), 90.0);
+    assert!(close(
+        about_z.transform_vector(&Vec3(1.0, 0.0, 0.0)),
+        Vec3(0.0, 1.0, 0.0)
+    ));
+    assert!((about_z.determinant() - 1.0).abs() < 1e-6);
+
+    // Products apply the right-hand transform first.
+    let turned_then_moved = translation * about_z;
+    assert!(close(
+        turned_then_moved.transform_point(&Vec3(1.0, 0.0, 0.0)),
+        Vec3(1.0, 0.0, 0.5)
+    ));
+    assert!(Matrix4::identity() * scaling == scaling);
+}
+
+#[test]
+fn test_matrix_inverse() {
+    let transform = Matrix4::translation(Vec3(3.0, -2.0, 1.0))
+        * Matrix4::rotation(Vec3(1.0, 1.0, 0.0), 30.0)
+        * Matrix4::scaling(Vec3(2.0, 0.5, 1.0));
+    let inverse = transform.inverse().unwrap();
+    let point = Vec3(0.3, -1.2, 2.5);
+    assert!(close(
+        inverse.transform_point(&transform.transform_point(&point)),
+        point
+    ));
+    assert!(close(
+        transform.transform_point(&inverse.transform_point(&point)),
+        point
+    ));
+    assert!((transform.determinant() * inverse.determinant() - 1.0).abs() < 1e-5);
+
+    // Normals stay perpendicular to the surface after a stretch.
+    let tangent = Vec3(1.0, -1.0, 0.0);
+    let normal = Vec3(1.0, 1.0, 0.0);
+    let moved_tangent = transform.transform_vector(&tangent);
+    let moved_normal = inverse.transform_normal(&normal);
+    assert!(Vec3::dot(&moved_tangent, &moved_normal).abs() < 1e-5);
+
+    assert!(Matrix4::scaling(Vec3(1.0, 0.0, 1.0)).inverse().is_none());
+}